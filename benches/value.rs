@@ -0,0 +1,127 @@
+use std::{hint::black_box, sync::Arc};
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use systema::{attribute::map::AttributeMap, prelude::*};
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum AttributeKey {
+	MaxHealth,
+	Speed,
+	Size,
+	Stamina,
+	Strength,
+	Dexterity,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum ModifierKey {
+	Attribute(AttributeKey),
+	Buff,
+}
+
+type Map = AttributeMap<AttributeKey, ModifierKey, u8>;
+
+const READ_ORDER: [AttributeKey; 6] = [
+	AttributeKey::MaxHealth,
+	AttributeKey::Speed,
+	AttributeKey::Size,
+	AttributeKey::Stamina,
+	AttributeKey::Strength,
+	AttributeKey::Dexterity,
+];
+
+fn derived(from: &[AttributeKey]) -> AttributeInstance<AttributeKey, ModifierKey, u8> {
+	from.iter()
+		.fold(
+			AttributeInstance::builder(Attribute::Derived),
+			|builder, attr| {
+				builder.modifier(
+					ModifierKey::Attribute(attr.clone()),
+					AttributeModifier::new(Value::Attribute(attr.clone()), Operation::Add).base(),
+				)
+			},
+		)
+		.into()
+}
+
+#[cfg_attr(feature = "single-threaded", allow(clippy::arc_with_non_send_sync))]
+fn supplier() -> Arc<AttributeSupplier<AttributeKey, ModifierKey, u8>> {
+	Arc::new(
+		AttributeSupplier::builder()
+			.add(
+				AttributeKey::MaxHealth,
+				derived(&[AttributeKey::Stamina, AttributeKey::Size]),
+			)
+			.add(
+				AttributeKey::Speed,
+				derived(&[AttributeKey::Dexterity, AttributeKey::Strength]),
+			)
+			.add(AttributeKey::Size, Attribute::Value(5))
+			.add(AttributeKey::Stamina, Attribute::Value(1))
+			.add(AttributeKey::Strength, Attribute::Value(1))
+			.add(AttributeKey::Dexterity, Attribute::Value(1))
+			.build(),
+	)
+}
+
+fn actors(count: usize) -> Vec<Map> {
+	let supplier = supplier();
+	(0..count)
+		.map(|_| {
+			let mut map = Map::new(supplier.clone());
+			// Materialise every attribute so reads go through the instance caches.
+			for attr in &READ_ORDER {
+				map.add_modifier(
+					attr,
+					ModifierKey::Buff,
+					AttributeModifier::new(1, Operation::Add),
+				);
+			}
+			map
+		})
+		.collect()
+}
+
+fn read_heavy(c: &mut Criterion) {
+	let mut group = c.benchmark_group("read_heavy");
+
+	for count in [1_000, 10_000] {
+		let maps = actors(count);
+
+		group.bench_with_input(BenchmarkId::new("cached", count), &maps, |b, maps| {
+			b.iter(|| {
+				for map in maps {
+					for attr in &READ_ORDER {
+						black_box(map.value(attr));
+					}
+				}
+			});
+		});
+
+		group.bench_with_input(BenchmarkId::new("invalidated", count), &maps, |b, maps| {
+			b.iter_batched_ref(
+				|| {
+					let mut maps = maps.clone();
+					for map in &mut maps {
+						map.set_raw_value(&AttributeKey::Stamina, 2);
+						map.set_raw_value(&AttributeKey::Dexterity, 2);
+					}
+					maps
+				},
+				|maps| {
+					for map in maps.iter() {
+						for attr in &READ_ORDER {
+							black_box(map.value(attr));
+						}
+					}
+				},
+				criterion::BatchSize::LargeInput,
+			);
+		});
+	}
+
+	group.finish();
+}
+
+criterion_group!(benches, read_heavy);
+criterion_main!(benches);
@@ -1,11 +1,229 @@
-use crate::{prelude::AttributeMap, system::System};
+use std::{collections::HashMap, time::Duration};
+
+use crate::{
+	prelude::{AttributeMap, AttributeModifier},
+	system::System,
+};
+
+type Attr<S> = <S as System>::AttributeKey;
+type Mod<S> = <S as System>::ModifierKey;
+type Val<S> = <S as System>::AttributeValue;
+type ModifierOf<S> = AttributeModifier<Attr<S>, Val<S>, <S as System>::Operation>;
 
 pub trait Actor {
 	type System: System;
 	type Kind;
 
+	/// Creates an actor of `kind`, typically starting from
+	/// [`System::attributes`](crate::system::System::attributes).
 	fn new(kind: Self::Kind) -> Self;
 
 	fn attributes(&self) -> &AttributeMap<Self::System>;
 	fn attributes_mut(&mut self) -> &mut AttributeMap<Self::System>;
+
+	fn value(&self, attribute: &Attr<Self::System>) -> Option<Val<Self::System>> {
+		self.attributes().value(attribute)
+	}
+
+	fn base_value(&self, attribute: &Attr<Self::System>) -> Option<Val<Self::System>> {
+		self.attributes().base_value(attribute)
+	}
+
+	fn raw_value(&self, attribute: &Attr<Self::System>) -> Option<Val<Self::System>> {
+		self.attributes().raw_value(attribute)
+	}
+
+	fn set_raw_value(&mut self, attribute: &Attr<Self::System>, value: Val<Self::System>) {
+		self.attributes_mut().set_raw_value(attribute, value);
+	}
+
+	/// Adds a set of modifiers sharing one key, e.g. every stat change granted by a form or effect.
+	fn apply_modifiers<I>(&mut self, key: &Mod<Self::System>, modifiers: I)
+	where
+		I: IntoIterator<Item = (Attr<Self::System>, ModifierOf<Self::System>)>,
+	{
+		let attributes = self.attributes_mut();
+		for (attribute, modifier) in modifiers {
+			attributes.add_modifier(&attribute, key.clone(), modifier);
+		}
+	}
+
+	/// Like [`apply_modifiers`](Self::apply_modifiers), removing the set again after `duration`
+	/// has been [`tick`](Self::tick)ed away.
+	fn apply_timed_modifiers<I>(
+		&mut self,
+		key: &Mod<Self::System>,
+		modifiers: I,
+		duration: Duration,
+	) where
+		I: IntoIterator<Item = (Attr<Self::System>, ModifierOf<Self::System>)>,
+	{
+		self.apply_modifiers(key, modifiers);
+		self.attributes_mut()
+			.expire_modifiers(key.clone(), duration);
+	}
+
+	fn remove_modifiers(&mut self, key: &Mod<Self::System>) {
+		self.attributes_mut().remove_modifiers(key);
+	}
+
+	/// Advances timed modifiers, returning the keys of those that expired.
+	fn tick(&mut self, elapsed: Duration) -> Vec<Mod<Self::System>> {
+		self.attributes_mut().tick(elapsed)
+	}
+
+	fn snapshot(&self) -> HashMap<Attr<Self::System>, Val<Self::System>> {
+		self.attributes().snapshot()
+	}
+}
+
+/// An [`Actor`] with nothing but attributes, for systems that need no per-actor state.
+#[derive(derive_more::Debug)]
+pub struct BasicActor<S: System> {
+	attributes: AttributeMap<S>,
+}
+
+impl<S: System> Clone for BasicActor<S> {
+	fn clone(&self) -> Self {
+		Self {
+			attributes: self.attributes.clone(),
+		}
+	}
+}
+
+impl<S: System> Actor for BasicActor<S> {
+	type System = S;
+	type Kind = S::Kind;
+
+	fn new(kind: S::Kind) -> Self {
+		Self {
+			attributes: S::attributes(&kind),
+		}
+	}
+
+	fn attributes(&self) -> &AttributeMap<S> {
+		&self.attributes
+	}
+
+	fn attributes_mut(&mut self) -> &mut AttributeMap<S> {
+		&mut self.attributes
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use super::*;
+	use crate::{
+		kind::KindRegistry,
+		prelude::{Attribute, AttributeSupplier, Operation},
+	};
+
+	#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+	enum TestAttribute {
+		Strength,
+		Agility,
+	}
+
+	#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+	enum TestModifier {
+		Haste,
+		Rage,
+	}
+
+	struct TestSystem;
+
+	impl System for TestSystem {
+		type AttributeKey = TestAttribute;
+		type ModifierKey = TestModifier;
+		type AttributeValue = i32;
+		type Operation = Operation;
+
+		type Kind = ();
+		type Actor = BasicActor<Self>;
+
+		fn kinds() -> Option<Arc<KindRegistry<Self>>> {
+			crate::shared_kinds!(
+				TestSystem,
+				KindRegistry::builder()
+					.kind(
+						(),
+						AttributeSupplier::builder()
+							.add(TestAttribute::Strength, Attribute::Value(3))
+							.add(TestAttribute::Agility, Attribute::Value(2)),
+					)
+					.build()
+			)
+		}
+	}
+
+	fn actor() -> BasicActor<TestSystem> {
+		BasicActor::new(())
+	}
+
+	#[test]
+	fn test_apply_and_remove_modifiers() {
+		let mut actor = actor();
+		actor.apply_modifiers(
+			&TestModifier::Rage,
+			[
+				(
+					TestAttribute::Strength,
+					AttributeModifier::new(2, Operation::Add),
+				),
+				(
+					TestAttribute::Agility,
+					AttributeModifier::new(1, Operation::Sub),
+				),
+			],
+		);
+		assert_eq!(actor.value(&TestAttribute::Strength), Some(5));
+		assert_eq!(actor.value(&TestAttribute::Agility), Some(1));
+
+		actor.remove_modifiers(&TestModifier::Rage);
+		assert_eq!(actor.value(&TestAttribute::Strength), Some(3));
+		assert_eq!(actor.value(&TestAttribute::Agility), Some(2));
+	}
+
+	#[test]
+	fn test_raw_value_shorthands() {
+		let mut actor = actor();
+		assert_eq!(actor.raw_value(&TestAttribute::Strength), Some(3));
+
+		actor.set_raw_value(&TestAttribute::Strength, 7);
+		assert_eq!(actor.raw_value(&TestAttribute::Strength), Some(7));
+		assert_eq!(actor.base_value(&TestAttribute::Strength), Some(7));
+	}
+
+	#[test]
+	fn test_timed_modifiers_expire() {
+		let mut actor = actor();
+		actor.apply_timed_modifiers(
+			&TestModifier::Haste,
+			[(
+				TestAttribute::Agility,
+				AttributeModifier::new(3, Operation::Add),
+			)],
+			Duration::from_secs(2),
+		);
+		assert_eq!(actor.value(&TestAttribute::Agility), Some(5));
+
+		assert!(actor.tick(Duration::from_secs(1)).is_empty());
+		assert_eq!(actor.value(&TestAttribute::Agility), Some(5));
+
+		assert_eq!(actor.tick(Duration::from_secs(1)), [TestModifier::Haste]);
+		assert_eq!(actor.value(&TestAttribute::Agility), Some(2));
+	}
+
+	#[test]
+	fn test_snapshot() {
+		let mut actor = actor();
+		actor.set_raw_value(&TestAttribute::Agility, 4);
+
+		let snapshot = actor.snapshot();
+		assert_eq!(snapshot.len(), 2);
+		assert_eq!(snapshot[&TestAttribute::Strength], 3);
+		assert_eq!(snapshot[&TestAttribute::Agility], 4);
+	}
 }
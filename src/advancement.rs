@@ -0,0 +1,327 @@
+//! Raising raw values by spending a currency attribute, e.g. experience.
+//!
+//! The [`AttributeSupplier`](crate::attribute::supplier::AttributeSupplier) declares a
+//! [`CostCurve`] for every attribute that can be advanced and the attribute that pays for it;
+//! [`AttributeMap::advance`](crate::attribute::map::AttributeMap::advance) charges the cost and
+//! records an [`Advancement`].
+
+use std::{error::Error, fmt};
+
+use crate::number::Arithmetic;
+
+/// The price of raising an attribute, charged per step of one.
+#[derive(Clone, Debug)]
+pub enum CostCurve<V> {
+	/// Every step costs the same.
+	Flat(V),
+	/// Each step costs the new value times the factor, e.g. "new dots × 4".
+	Linear(V),
+	/// Each step costs what the function returns for the new value.
+	///
+	/// The function is called once per step, so at most [`MAX_CUSTOM_STEPS`](Self::MAX_CUSTOM_STEPS)
+	/// steps are charged at once.
+	Custom(fn(V) -> V),
+}
+
+impl<V: Arithmetic> CostCurve<V> {
+	/// The most steps a [`Custom`](Self::Custom) curve charges in one call to
+	/// [`cost`](Self::cost).
+	pub const MAX_CUSTOM_STEPS: u32 = 10_000;
+
+	/// The total cost of raising a raw value from `from` to `to`, one step at a time.
+	///
+	/// Nothing is charged if `to` is not above `from`. A final partial step is charged in full.
+	/// [`Flat`](Self::Flat) and [`Linear`](Self::Linear) costs are computed in closed form, so
+	/// any distance is priced at once.
+	///
+	/// # Errors
+	///
+	/// Fails if the cost, or a value needed to compute it, does not fit `V`, and if a
+	/// [`Custom`](Self::Custom) curve would take more than
+	/// [`MAX_CUSTOM_STEPS`](Self::MAX_CUSTOM_STEPS) steps.
+	pub fn cost(&self, from: V, to: V) -> Result<V, CostError> {
+		if to.partial_cmp(&from) != Some(std::cmp::Ordering::Greater) {
+			return Ok(V::default());
+		}
+		let distance = to.checked_sub(from).ok_or(CostError::Overflow)?;
+		let steps = distance.to_f64().ceil();
+		let n = V::from_f64(steps);
+		match self {
+			Self::Flat(cost) => n.checked_mul(*cost).ok_or(CostError::Overflow),
+			Self::Linear(factor) => {
+				// The new values are from + 1, ..., from + n, summing to n * from + n * (n + 1) / 2.
+				let (one, two) = (V::from_f64(1.0), V::from_f64(2.0));
+				let next = n.checked_add(one).ok_or(CostError::Overflow)?;
+				let triangle = if steps % 2.0 == 0.0 {
+					(n / two).checked_mul(next)
+				} else {
+					n.checked_mul(next / two)
+				};
+				n.checked_mul(from)
+					.zip(triangle)
+					.and_then(|(base, triangle)| base.checked_add(triangle))
+					.and_then(|levels| levels.checked_mul(*factor))
+					.ok_or(CostError::Overflow)
+			}
+			Self::Custom(step) => {
+				if steps > f64::from(Self::MAX_CUSTOM_STEPS) {
+					return Err(CostError::TooManySteps);
+				}
+				let one = V::from_f64(1.0);
+				let mut cost = V::default();
+				let mut level = from;
+				while level < to {
+					level = level.checked_add(one).ok_or(CostError::Overflow)?;
+					cost = cost.checked_add(step(level)).ok_or(CostError::Overflow)?;
+				}
+				Ok(cost)
+			}
+		}
+	}
+}
+
+/// Why a [`CostCurve`] could not price an advancement.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CostError {
+	/// The cost does not fit the value type.
+	Overflow,
+	/// A [`CostCurve::Custom`] curve would take more than
+	/// [`CostCurve::MAX_CUSTOM_STEPS`] steps.
+	TooManySteps,
+}
+
+impl fmt::Display for CostError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Overflow => f.write_str("the cost overflows the value type"),
+			Self::TooManySteps => f.write_str("the advancement takes too many steps to price"),
+		}
+	}
+}
+
+impl Error for CostError {}
+
+/// A change made by [`AttributeMap::advance`](crate::attribute::map::AttributeMap::advance).
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Advancement<A, V> {
+	pub attribute: A,
+	pub from: V,
+	pub to: V,
+	pub cost: V,
+}
+
+/// Why an advancement was refused. Nothing is changed when it is.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AdvanceError<A, V> {
+	/// The map's supplier does not declare the attribute, or no cost curve for it.
+	NotAdvanceable(A),
+	/// The map's supplier declares no currency attribute.
+	NoCurrency,
+	/// The target is not above the current raw value.
+	NotAnIncrease {
+		current: V,
+		target: V,
+	},
+	/// The target lies beyond the maximum of a ranged attribute.
+	OutOfRange {
+		target: V,
+		max: V,
+	},
+	InsufficientCurrency {
+		cost: V,
+		available: V,
+	},
+	/// The cost curve could not price the advancement.
+	Cost(CostError),
+}
+
+impl<A: fmt::Debug, V: fmt::Debug> fmt::Display for AdvanceError<A, V> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::NotAdvanceable(attribute) => write!(f, "{attribute:?} cannot be advanced"),
+			Self::NoCurrency => f.write_str("no currency attribute is declared"),
+			Self::NotAnIncrease { current, target } => {
+				write!(f, "{target:?} is not above the current value {current:?}")
+			}
+			Self::OutOfRange { target, max } => {
+				write!(f, "{target:?} is above the maximum {max:?}")
+			}
+			Self::InsufficientCurrency { cost, available } => {
+				write!(
+					f,
+					"advancing costs {cost:?}, but only {available:?} is available"
+				)
+			}
+			Self::Cost(error) => error.fmt(f),
+		}
+	}
+}
+
+impl<A: fmt::Debug, V: fmt::Debug> Error for AdvanceError<A, V> {}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use super::*;
+	use crate::attribute::{Attribute, map::AttributeMap, supplier::AttributeSupplier};
+
+	#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+	enum TestAttribute {
+		Experience,
+		Strength,
+		Brawl,
+		Size,
+	}
+
+	type MockMap = AttributeMap<TestAttribute, (), i32>;
+
+	fn mock_map() -> MockMap {
+		AttributeMap::new(Arc::new(
+			AttributeSupplier::builder()
+				.add(TestAttribute::Experience, Attribute::Value(30))
+				.add(TestAttribute::Strength, Attribute::Ranged(1, 1, 5))
+				.add(TestAttribute::Brawl, Attribute::Value(0))
+				.add(TestAttribute::Size, Attribute::Value(5))
+				.currency(TestAttribute::Experience)
+				.cost(TestAttribute::Strength, CostCurve::Linear(4))
+				.cost(TestAttribute::Brawl, CostCurve::Linear(2))
+				.build(),
+		))
+	}
+
+	#[test]
+	fn test_cost_curves() {
+		assert_eq!(CostCurve::Linear(4).cost(1, 3), Ok(8 + 12));
+		assert_eq!(CostCurve::Linear(4).cost(1, 4), Ok(8 + 12 + 16));
+		assert_eq!(CostCurve::Flat(3).cost(0, 2), Ok(6));
+		assert_eq!(
+			CostCurve::Custom(|level| level * level).cost(1, 3),
+			Ok(4 + 9)
+		);
+		assert_eq!(CostCurve::Flat(3).cost(2, 2), Ok(0));
+		assert_eq!(CostCurve::Linear(2.0).cost(0.0, 1.5), Ok(2.0 + 4.0));
+	}
+
+	#[test]
+	fn test_huge_costs() {
+		assert_eq!(CostCurve::Flat(1.0f32).cost(0.0, 1e30), Ok(1e30));
+		assert_eq!(
+			CostCurve::Linear(2.0f32).cost(0.0, 1e30),
+			Err(CostError::Overflow)
+		);
+		assert_eq!(
+			CostCurve::Flat(1.0f32).cost(0.0, f32::INFINITY),
+			Err(CostError::Overflow)
+		);
+		assert_eq!(
+			CostCurve::Custom(|_| 1.0f32).cost(0.0, 1e30),
+			Err(CostError::TooManySteps)
+		);
+		assert_eq!(CostCurve::Linear(40u8).cost(0, 5), Err(CostError::Overflow));
+		assert_eq!(
+			CostCurve::Custom(|_| 200u8).cost(253, 255),
+			Err(CostError::Overflow)
+		);
+		assert_eq!(CostCurve::Linear(8u8).cost(0, 5), Ok(120));
+	}
+
+	#[test]
+	fn test_advance() {
+		let mut map = mock_map();
+
+		assert_eq!(map.advance(&TestAttribute::Strength, 3), Ok(20));
+		assert_eq!(map.raw_value(&TestAttribute::Strength), Some(3));
+		assert_eq!(map.raw_value(&TestAttribute::Experience), Some(10));
+
+		assert_eq!(map.advance(&TestAttribute::Brawl, 2), Ok(6));
+		assert_eq!(map.raw_value(&TestAttribute::Experience), Some(4));
+
+		assert_eq!(
+			map.history(),
+			[
+				Advancement {
+					attribute: TestAttribute::Strength,
+					from: 1,
+					to: 3,
+					cost: 20
+				},
+				Advancement {
+					attribute: TestAttribute::Brawl,
+					from: 0,
+					to: 2,
+					cost: 6
+				},
+			]
+		);
+	}
+
+	#[test]
+	fn test_undo_advance() {
+		let mut map = mock_map();
+		map.enable_journal();
+		assert_eq!(map.advance(&TestAttribute::Strength, 3), Ok(20));
+
+		// One undo restores the currency, the value and the history.
+		assert!(map.undo());
+		assert_eq!(map.raw_value(&TestAttribute::Strength), Some(1));
+		assert_eq!(map.raw_value(&TestAttribute::Experience), Some(30));
+		assert!(map.history().is_empty());
+		assert!(!map.can_undo());
+
+		assert!(map.redo());
+		assert_eq!(map.raw_value(&TestAttribute::Experience), Some(10));
+		assert_eq!(map.history().len(), 1);
+	}
+
+	#[test]
+	fn test_advance_errors() {
+		let mut map = mock_map();
+
+		assert_eq!(
+			map.advance(&TestAttribute::Size, 6),
+			Err(AdvanceError::NotAdvanceable(TestAttribute::Size))
+		);
+		assert_eq!(
+			map.advance(&TestAttribute::Strength, 1),
+			Err(AdvanceError::NotAnIncrease {
+				current: 1,
+				target: 1
+			})
+		);
+		assert_eq!(
+			map.advance(&TestAttribute::Strength, 6),
+			Err(AdvanceError::OutOfRange { target: 6, max: 5 })
+		);
+		assert_eq!(
+			map.advance(&TestAttribute::Strength, 5),
+			Err(AdvanceError::InsufficientCurrency {
+				cost: 56,
+				available: 30
+			})
+		);
+
+		assert_eq!(map.raw_value(&TestAttribute::Strength), Some(1));
+		assert_eq!(map.raw_value(&TestAttribute::Experience), Some(30));
+		assert!(map.history().is_empty());
+	}
+
+	#[test]
+	fn test_advance_overflow() {
+		let mut map: AttributeMap<TestAttribute, (), u8> = AttributeMap::new(Arc::new(
+			AttributeSupplier::builder()
+				.add(TestAttribute::Experience, Attribute::Value(255))
+				.add(TestAttribute::Strength, Attribute::Value(0))
+				.currency(TestAttribute::Experience)
+				.cost(TestAttribute::Strength, CostCurve::Linear(40))
+				.build(),
+		));
+
+		assert_eq!(
+			map.advance(&TestAttribute::Strength, 5),
+			Err(AdvanceError::Cost(CostError::Overflow))
+		);
+		assert_eq!(map.advance(&TestAttribute::Strength, 2), Ok(120));
+	}
+}
@@ -0,0 +1,111 @@
+//! Per-instance cache for computed attribute values.
+//!
+//! By default the cache is an [`AtomicCell`] holding the value next to an [`AtomicBool`] marking
+//! it valid. The flag is always lock-free, and so is the value if it fits a native atomic, e.g.
+//! `f32`, `i32` or `i64`; larger values fall back to crossbeam's striped sequence lock. Enabling
+//! the `single-threaded` feature swaps both for a plain [`Cell`], dropping all synchronisation
+//! (and `Sync`).
+//!
+//! In both modes the cache is only touched to read or publish a finished value, never while the
+//! value is being computed, so recursive dependency lookups cannot dead-lock on it.
+
+#[cfg(feature = "single-threaded")]
+use std::cell::Cell;
+#[cfg(not(feature = "single-threaded"))]
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(not(feature = "single-threaded"))]
+use crossbeam_utils::atomic::AtomicCell;
+
+#[cfg(not(feature = "single-threaded"))]
+pub(crate) struct ValueCache<V> {
+	value: AtomicCell<V>,
+	valid: AtomicBool,
+}
+
+#[cfg(feature = "single-threaded")]
+pub(crate) struct ValueCache<V>(Cell<Option<V>>);
+
+impl<V: Copy + Default> ValueCache<V> {
+	pub(crate) fn new(value: Option<V>) -> Self {
+		#[cfg(not(feature = "single-threaded"))]
+		return Self {
+			value: AtomicCell::new(value.unwrap_or_default()),
+			valid: AtomicBool::new(value.is_some()),
+		};
+		#[cfg(feature = "single-threaded")]
+		return Self(Cell::new(value));
+	}
+
+	pub(crate) fn get(&self) -> Option<V> {
+		#[cfg(not(feature = "single-threaded"))]
+		return self
+			.valid
+			.load(Ordering::Acquire)
+			.then(|| self.value.load());
+		#[cfg(feature = "single-threaded")]
+		return self.0.get();
+	}
+
+	pub(crate) fn set(&self, value: V) {
+		#[cfg(not(feature = "single-threaded"))]
+		{
+			self.value.store(value);
+			self.valid.store(true, Ordering::Release);
+		}
+		#[cfg(feature = "single-threaded")]
+		self.0.set(Some(value));
+	}
+
+	pub(crate) fn clear(&self) {
+		#[cfg(not(feature = "single-threaded"))]
+		self.valid.store(false, Ordering::Release);
+		#[cfg(feature = "single-threaded")]
+		self.0.set(None);
+	}
+}
+
+impl<V: Copy + Default> Clone for ValueCache<V> {
+	fn clone(&self) -> Self {
+		Self::new(self.get())
+	}
+}
+
+impl<V: Copy + Default> Default for ValueCache<V> {
+	fn default() -> Self {
+		Self::new(None)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_cache_set_and_clear() {
+		let cache = ValueCache::<u8>::default();
+		assert_eq!(cache.get(), None);
+
+		cache.set(5);
+		assert_eq!(cache.get(), Some(5));
+
+		cache.clear();
+		assert_eq!(cache.get(), None);
+	}
+
+	#[test]
+	fn test_cache_clone_is_independent() {
+		let cache = ValueCache::new(Some(1.0f32));
+		let clone = cache.clone();
+
+		cache.clear();
+		assert_eq!(clone.get(), Some(1.0));
+	}
+
+	#[cfg(not(feature = "single-threaded"))]
+	#[test]
+	fn test_cache_is_lock_free() {
+		assert!(AtomicCell::<f32>::is_lock_free());
+		assert!(AtomicCell::<i32>::is_lock_free());
+	}
+}
@@ -0,0 +1,149 @@
+//! Attributes holding a label rather than a number, e.g. a size category, the current form or
+//! whether the actor is flying.
+//!
+//! A supplier declares discrete attributes with
+//! [`discrete`](crate::attribute::supplier::AttributeSupplierBuilder::discrete) next to its
+//! numeric ones, under the same keys. The map stores their state alongside the numeric instances;
+//! modifiers [override](crate::attribute::map::AttributeMap::override_state) it by priority and
+//! [conditions](crate::attribute::modifier::Condition::State) on numeric modifiers test it.
+//! A flag is a discrete attribute with two states, e.g. `"grounded"` and `"flying"`.
+
+use crate::attribute::{journal, tag::Tag};
+
+/// A state placed on a discrete attribute by the modifiers keyed `M`, with its priority.
+pub(crate) type Override<M> = (M, i32, Tag);
+
+/// The state of one discrete attribute of an actor: its own and the overrides placed on it.
+///
+/// Of the overrides the one with the highest priority wins, and of equal ones the last placed.
+/// Without overrides the attribute is in its raw state.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiscreteInstance<M> {
+	raw_state: Tag,
+	/// Whether the raw state was set rather than taken from the supplier; a
+	/// [rebase](crate::attribute::map::AttributeMap::rebase) keeps set states.
+	#[cfg_attr(feature = "serde", serde(default))]
+	raw_set: bool,
+	overrides: Vec<Override<M>>,
+}
+
+impl<M: PartialEq> DiscreteInstance<M> {
+	#[must_use]
+	pub fn new(state: Tag) -> Self {
+		Self {
+			raw_state: state,
+			raw_set: false,
+			overrides: Vec::new(),
+		}
+	}
+
+	/// The state of the winning override, or the raw state.
+	#[must_use]
+	pub fn state(&self) -> &Tag {
+		self.overrides
+			.iter()
+			.fold(None, |winner: Option<&Override<M>>, item| match winner {
+				Some((_, best, _)) if item.1 < *best => winner,
+				_ => Some(item),
+			})
+			.map_or(&self.raw_state, |(_, _, state)| state)
+	}
+
+	#[must_use]
+	pub fn raw_state(&self) -> &Tag {
+		&self.raw_state
+	}
+
+	/// The overrides with their keys and priorities, in the order they were placed.
+	pub fn overrides(&self) -> impl Iterator<Item = (&M, i32, &Tag)> {
+		self.overrides
+			.iter()
+			.map(|(key, priority, state)| (key, *priority, state))
+	}
+
+	/// Whether the raw state was set since the instance was created.
+	#[must_use]
+	pub fn is_raw_state_set(&self) -> bool {
+		self.raw_set
+	}
+
+	/// Replaces the raw state, returning the previous one.
+	pub(crate) fn set_raw_state(&mut self, state: Tag) -> Tag {
+		self.raw_set = true;
+		std::mem::replace(&mut self.raw_state, state)
+	}
+
+	/// Takes the initial state of a rebased supplier, unless the raw state was set.
+	pub(crate) fn rebase(&mut self, state: &Tag) {
+		if !self.raw_set {
+			self.raw_state = state.clone();
+		}
+	}
+
+	/// Puts back a raw state and whether it was set, e.g. when an edit is undone.
+	pub(crate) fn restore_raw_state(&mut self, state: Tag, set: bool) {
+		self.raw_state = state;
+		self.raw_set = set;
+	}
+
+	pub(crate) fn add_override(&mut self, key: M, priority: i32, state: Tag) {
+		self.overrides.push((key, priority, state));
+	}
+
+	pub(crate) fn pop_override(&mut self) {
+		self.overrides.pop();
+	}
+
+	/// Removes the overrides keyed `key`, returning them with their indices, ascending.
+	pub(crate) fn take_overrides(&mut self, key: &M) -> Vec<(usize, Override<M>)> {
+		journal::extract(&mut self.overrides, |(m, _, _)| key.eq(m))
+	}
+
+	/// Removes every override, returning whether there were any.
+	pub(crate) fn clear_overrides(&mut self) -> bool {
+		let cleared = !self.overrides.is_empty();
+		self.overrides.clear();
+		cleared
+	}
+
+	/// Puts back overrides removed by [`take_overrides`](Self::take_overrides).
+	pub(crate) fn restore_overrides(&mut self, removed: &[(usize, Override<M>)])
+	where
+		M: Clone,
+	{
+		journal::restore(&mut self.overrides, removed);
+	}
+
+	/// Removes again overrides put back by [`restore_overrides`](Self::restore_overrides).
+	pub(crate) fn retake_overrides(&mut self, removed: &[(usize, Override<M>)]) {
+		journal::remove_at(&mut self.overrides, removed);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_override_priority() {
+		let mut form = DiscreteInstance::new(Tag::new("human"));
+		assert_eq!(form.state(), &Tag::new("human"));
+
+		form.add_override("shapechange", 1, Tag::new("wolf"));
+		form.add_override("curse", 0, Tag::new("toad"));
+		assert_eq!(form.state(), &Tag::new("wolf"));
+
+		// The later of two equal overrides wins.
+		form.add_override("polymorph", 1, Tag::new("bat"));
+		assert_eq!(form.state(), &Tag::new("bat"));
+
+		let removed = form.take_overrides(&"polymorph");
+		assert_eq!(form.state(), &Tag::new("wolf"));
+		form.restore_overrides(&removed);
+		assert_eq!(form.state(), &Tag::new("bat"));
+
+		assert!(form.clear_overrides());
+		assert_eq!(form.state(), form.raw_state());
+	}
+}
@@ -0,0 +1,511 @@
+//! Mutations of an [`AttributeMap`] as serialisable events, for deterministic replays of a
+//! session, e.g. in lockstep multiplayer or to reproduce a bug.
+//!
+//! Once [recording](AttributeMap::record_events) is started, every mutating call on the map
+//! appends an [`AttributeEvent`] to its [`EventLog`]. [`AttributeMap::replay`] applies a log to a
+//! fresh map of the same supplier, reconstructing the exact state: raw values, modifier order,
+//! timers, journal and advancement history, and thus every [`value`](AttributeMap::value). Maps
+//! that were rebased onto other suppliers replay with [`AttributeMap::replay_rebased`].
+
+use std::{error::Error, fmt, hash::Hash, slice, sync::Arc, time::Duration};
+
+use crate::{
+	advancement::Advancement,
+	attribute::{
+		instance::AttributeInstance,
+		map::AttributeMap,
+		modifier::{AttributeModifier, Op},
+		storage::AttributeStorage,
+		supplier::AttributeSupplier,
+		tag::Tag,
+	},
+	util_traits::{Key, Number},
+};
+
+/// A single mutation of an [`AttributeMap`], named after the method making it.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub enum AttributeEvent<A, M, V, O>
+where
+	V: 'static,
+	O: Op<V>,
+{
+	SetRawValue {
+		attribute: A,
+		value: V,
+	},
+	AddModifier {
+		attribute: A,
+		key: M,
+		modifier: AttributeModifier<A, V, O>,
+	},
+	RemoveModifier {
+		attribute: A,
+		key: M,
+	},
+	RemoveModifiers(M),
+	RemoveTemplateModifier {
+		attribute: A,
+		key: M,
+	},
+	AddTagModifier {
+		tag: Tag,
+		key: M,
+		modifier: AttributeModifier<A, V, O>,
+	},
+	SetRawState {
+		attribute: A,
+		state: Tag,
+	},
+	OverrideState {
+		attribute: A,
+		key: M,
+		priority: i32,
+		state: Tag,
+	},
+	ClearRuntimeModifiers(A),
+	Reset(A),
+	ExpireModifiers {
+		key: M,
+		duration: Duration,
+	},
+	Tick(Duration),
+	/// An [`advance`](AttributeMap::advance) that was paid for.
+	Advance(Advancement<A, V>),
+	/// A [`rebase`](AttributeMap::rebase). Suppliers are not serialisable, so the event only
+	/// marks where the next supplier of [`replay_rebased`](AttributeMap::replay_rebased) takes
+	/// over.
+	Rebase,
+	EnableJournal,
+	DisableJournal,
+	Undo,
+	Redo,
+}
+
+impl<A, M, V, O> AttributeEvent<A, M, V, O>
+where
+	V: 'static,
+	O: Op<V>,
+{
+	/// Whether applying the event records an entry in the map's journal that undoes it. The
+	/// others clear the journal or, like [`Undo`](Self::Undo), move through it.
+	pub(crate) fn is_undoable(&self) -> bool {
+		matches!(
+			self,
+			Self::SetRawValue { .. }
+				| Self::AddModifier { .. }
+				| Self::RemoveModifier { .. }
+				| Self::RemoveModifiers(_)
+				| Self::RemoveTemplateModifier { .. }
+				| Self::SetRawState { .. }
+				| Self::OverrideState { .. }
+				| Self::ExpireModifiers { .. }
+				| Self::Tick(_)
+				| Self::Advance(_)
+		)
+	}
+}
+
+/// An append-only sequence of [`AttributeEvent`]s, oldest first.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct EventLog<A, M, V, O>
+where
+	V: 'static,
+	O: Op<V>,
+{
+	events: Vec<AttributeEvent<A, M, V, O>>,
+}
+
+impl<A, M, V, O> EventLog<A, M, V, O>
+where
+	V: 'static,
+	O: Op<V>,
+{
+	#[must_use]
+	pub fn new() -> Self {
+		Self { events: Vec::new() }
+	}
+
+	pub fn push(&mut self, event: AttributeEvent<A, M, V, O>) {
+		self.events.push(event);
+	}
+
+	#[must_use]
+	pub fn len(&self) -> usize {
+		self.events.len()
+	}
+
+	#[must_use]
+	pub fn is_empty(&self) -> bool {
+		self.events.is_empty()
+	}
+
+	#[must_use]
+	pub fn as_slice(&self) -> &[AttributeEvent<A, M, V, O>] {
+		&self.events
+	}
+
+	pub fn iter(&self) -> slice::Iter<'_, AttributeEvent<A, M, V, O>> {
+		self.events.iter()
+	}
+}
+
+impl<A, M, V, O> Default for EventLog<A, M, V, O>
+where
+	V: 'static,
+	O: Op<V>,
+{
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<'a, A, M, V, O> IntoIterator for &'a EventLog<A, M, V, O>
+where
+	V: 'static,
+	O: Op<V>,
+{
+	type Item = &'a AttributeEvent<A, M, V, O>;
+	type IntoIter = slice::Iter<'a, AttributeEvent<A, M, V, O>>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.iter()
+	}
+}
+
+impl<A, M, V, O> IntoIterator for EventLog<A, M, V, O>
+where
+	V: 'static,
+	O: Op<V>,
+{
+	type Item = AttributeEvent<A, M, V, O>;
+	type IntoIter = std::vec::IntoIter<AttributeEvent<A, M, V, O>>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.events.into_iter()
+	}
+}
+
+impl<A, M, V, O> FromIterator<AttributeEvent<A, M, V, O>> for EventLog<A, M, V, O>
+where
+	V: 'static,
+	O: Op<V>,
+{
+	fn from_iter<I: IntoIterator<Item = AttributeEvent<A, M, V, O>>>(iter: I) -> Self {
+		Self {
+			events: iter.into_iter().collect(),
+		}
+	}
+}
+
+impl<A, M, V, O, S> AttributeMap<A, M, V, O, S>
+where
+	A: Key + Hash,
+	M: Key,
+	V: Number + 'static,
+	O: Op<V>,
+	S: AttributeStorage<A, AttributeInstance<A, M, V, O>>,
+{
+	/// Makes the mutation described by `event`, recording it if events are being recorded.
+	///
+	/// [`Rebase`](AttributeEvent::Rebase) rebases onto the current supplier; use
+	/// [`replay_rebased`](Self::replay_rebased) to switch suppliers.
+	pub fn apply(&mut self, event: &AttributeEvent<A, M, V, O>) {
+		match event {
+			AttributeEvent::SetRawValue { attribute, value } => {
+				self.set_raw_value(attribute, *value);
+			}
+			AttributeEvent::AddModifier {
+				attribute,
+				key,
+				modifier,
+			} => {
+				self.add_modifier(attribute, key.clone(), modifier.clone());
+			}
+			AttributeEvent::RemoveModifier { attribute, key } => {
+				self.remove_modifier(attribute, key);
+			}
+			AttributeEvent::RemoveModifiers(key) => self.remove_modifiers(key),
+			AttributeEvent::RemoveTemplateModifier { attribute, key } => {
+				self.remove_template_modifier(attribute, key);
+			}
+			AttributeEvent::AddTagModifier { tag, key, modifier } => {
+				self.add_tag_modifier(tag, key.clone(), modifier.clone());
+			}
+			AttributeEvent::SetRawState { attribute, state } => {
+				self.set_raw_state(attribute, state.clone());
+			}
+			AttributeEvent::OverrideState {
+				attribute,
+				key,
+				priority,
+				state,
+			} => {
+				self.override_state(attribute, key.clone(), *priority, state.clone());
+			}
+			AttributeEvent::ClearRuntimeModifiers(attribute) => {
+				self.clear_runtime_modifiers(attribute);
+			}
+			AttributeEvent::Reset(attribute) => self.reset(attribute),
+			AttributeEvent::ExpireModifiers { key, duration } => {
+				self.expire_modifiers(key.clone(), *duration);
+			}
+			AttributeEvent::Tick(elapsed) => {
+				self.tick(*elapsed);
+			}
+			AttributeEvent::Advance(advancement) => self.apply_advancement(advancement.clone()),
+			AttributeEvent::Rebase => {
+				if let Some(supplier) = self.supplier().cloned() {
+					self.rebase(supplier);
+				}
+			}
+			AttributeEvent::EnableJournal => self.enable_journal(),
+			AttributeEvent::DisableJournal => self.disable_journal(),
+			AttributeEvent::Undo => {
+				self.undo();
+			}
+			AttributeEvent::Redo => {
+				self.redo();
+			}
+		}
+	}
+
+	/// Builds a map of `supplier` and applies `events` to it in order.
+	///
+	/// Given the supplier of the recorded map, the result matches it exactly, provided any
+	/// [rebase](Self::rebase) while recording was onto the same supplier.
+	pub fn replay<'a, I>(supplier: Arc<AttributeSupplier<A, M, V, O>>, events: I) -> Self
+	where
+		I: IntoIterator<Item = &'a AttributeEvent<A, M, V, O>>,
+		A: 'a,
+		M: 'a,
+		O: 'a,
+	{
+		let mut map = Self::new(supplier);
+		for event in events {
+			map.apply(event);
+		}
+		map
+	}
+
+	/// Like [`replay`](Self::replay), but the map is built of the first of `suppliers` and
+	/// every [`Rebase`](AttributeEvent::Rebase) rebases onto the next one.
+	///
+	/// # Errors
+	///
+	/// Fails if `suppliers` runs out before the log does.
+	pub fn replay_rebased<'a, I, P>(suppliers: P, events: I) -> Result<Self, ReplayError>
+	where
+		I: IntoIterator<Item = &'a AttributeEvent<A, M, V, O>>,
+		P: IntoIterator<Item = Arc<AttributeSupplier<A, M, V, O>>>,
+		A: 'a,
+		M: 'a,
+		O: 'a,
+	{
+		let mut suppliers = suppliers.into_iter();
+		let mut next = || suppliers.next().ok_or(ReplayError::MissingSupplier);
+		let mut map = Self::new(next()?);
+		for event in events {
+			match event {
+				AttributeEvent::Rebase => map.rebase(next()?),
+				event => map.apply(event),
+			}
+		}
+		Ok(map)
+	}
+}
+
+/// Why a log could not be [replayed](AttributeMap::replay_rebased).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReplayError {
+	/// The log rebases more often than suppliers were given, or none were given at all.
+	MissingSupplier,
+}
+
+impl fmt::Display for ReplayError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::MissingSupplier => f.write_str("the log rebases onto more suppliers than given"),
+		}
+	}
+}
+
+impl Error for ReplayError {}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		advancement::CostCurve,
+		attribute::Attribute,
+		prelude::{Operation, Value},
+	};
+
+	#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+	enum TestAttribute {
+		Experience,
+		Strength,
+		Agility,
+		Speed,
+	}
+
+	#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+	enum TestModifier {
+		Strength,
+		Buff,
+		Haste,
+	}
+
+	type MockMap = AttributeMap<TestAttribute, TestModifier, f32>;
+
+	const PHYSICAL: Tag = Tag::new("physical");
+
+	fn mock_supplier() -> Arc<AttributeSupplier<TestAttribute, TestModifier, f32>> {
+		Arc::new(
+			AttributeSupplier::builder()
+				.add(TestAttribute::Experience, Attribute::Value(20.0))
+				.add(TestAttribute::Strength, Attribute::Ranged(1.0, 1.0, 5.0))
+				.add(TestAttribute::Agility, Attribute::Value(2.0))
+				.add(
+					TestAttribute::Speed,
+					AttributeInstance::builder(Attribute::Derived).modifier(
+						TestModifier::Strength,
+						AttributeModifier::new(
+							Value::Attribute(TestAttribute::Strength),
+							Operation::Add,
+						),
+					),
+				)
+				.tag(TestAttribute::Strength, PHYSICAL)
+				.tag(TestAttribute::Agility, PHYSICAL)
+				.cost(TestAttribute::Strength, CostCurve::Linear(2.0))
+				.currency(TestAttribute::Experience)
+				.build(),
+		)
+	}
+
+	fn play(map: &mut MockMap) {
+		map.enable_journal();
+		map.set_raw_value(&TestAttribute::Agility, 3.0);
+		map.add_tag_modifier(
+			&PHYSICAL,
+			TestModifier::Buff,
+			AttributeModifier::new(1.0, Operation::Add),
+		);
+		map.add_modifier(
+			&TestAttribute::Speed,
+			TestModifier::Haste,
+			AttributeModifier::new(2.0, Operation::Add),
+		);
+		map.expire_modifiers(TestModifier::Haste, Duration::from_secs(2));
+		map.advance(&TestAttribute::Strength, 3.0).unwrap();
+		map.remove_modifier(&TestAttribute::Agility, &TestModifier::Buff);
+		map.undo();
+		map.tick(Duration::from_secs(3));
+	}
+
+	#[test]
+	fn test_replay() {
+		let mut map: MockMap = AttributeMap::new(mock_supplier());
+		assert!(map.record_events());
+		play(&mut map);
+
+		let log = map.events().unwrap();
+		assert_eq!(log.len(), 9);
+		assert!(matches!(
+			log.as_slice()[..2],
+			[
+				AttributeEvent::EnableJournal,
+				AttributeEvent::SetRawValue { .. }
+			]
+		));
+
+		let replayed = MockMap::replay(mock_supplier(), log);
+		assert_eq!(replayed.snapshot(), map.snapshot());
+		for (id, instance) in map.instances() {
+			let modifiers: Vec<_> = instance.modifiers().collect();
+			let replayed_modifiers: Vec<_> = replayed.instance(id).unwrap().modifiers().collect();
+			assert_eq!(modifiers, replayed_modifiers);
+		}
+		assert_eq!(replayed.history(), map.history());
+		assert_eq!(replayed.can_undo(), map.can_undo());
+		assert_eq!(replayed.value(&TestAttribute::Speed), Some(4.0));
+	}
+
+	#[test]
+	fn test_replay_rebased() {
+		let stronger = || {
+			Arc::new(
+				AttributeSupplier::extend(mock_supplier())
+					.add(TestAttribute::Strength, Attribute::Ranged(2.0, 1.0, 5.0))
+					.build(),
+			)
+		};
+		let mut map: MockMap = AttributeMap::new(mock_supplier());
+		assert!(map.record_events());
+		map.set_raw_value(&TestAttribute::Agility, 3.0);
+		map.rebase(stronger());
+		map.set_raw_value(&TestAttribute::Agility, 4.0);
+
+		let log = map.take_events().unwrap();
+		assert!(matches!(log.as_slice()[1], AttributeEvent::Rebase));
+		let replayed = MockMap::replay_rebased([mock_supplier(), stronger()], &log).unwrap();
+		assert_eq!(replayed.snapshot(), map.snapshot());
+		assert_eq!(replayed.value(&TestAttribute::Strength), Some(2.0));
+
+		assert_eq!(
+			MockMap::replay_rebased([mock_supplier()], &log).err(),
+			Some(ReplayError::MissingSupplier)
+		);
+		assert_eq!(
+			MockMap::replay_rebased([], &log).err(),
+			Some(ReplayError::MissingSupplier)
+		);
+	}
+
+	#[test]
+	fn test_record_only_fresh_maps() {
+		let mut map: MockMap = AttributeMap::new(mock_supplier());
+		map.set_raw_value(&TestAttribute::Agility, 3.0);
+		assert!(!map.record_events());
+		assert!(map.events().is_none());
+	}
+
+	#[test]
+	fn test_replay_unaffordable_advance() {
+		let log: EventLog<_, _, _, _> = [AttributeEvent::Advance(Advancement {
+			attribute: TestAttribute::Strength,
+			from: 1.0,
+			to: 5.0,
+			cost: 100.0,
+		})]
+		.into_iter()
+		.collect();
+		let replayed = MockMap::replay(mock_supplier(), &log);
+		assert_eq!(replayed.raw_value(&TestAttribute::Experience), Some(20.0));
+		assert_eq!(replayed.raw_value(&TestAttribute::Strength), Some(1.0));
+		assert!(replayed.history().is_empty());
+	}
+
+	#[test]
+	fn test_replay_without_recording() {
+		let mut map: MockMap = AttributeMap::new(mock_supplier());
+		play(&mut map);
+		assert!(map.events().is_none());
+
+		let log: EventLog<_, _, _, _> = [
+			AttributeEvent::SetRawValue {
+				attribute: TestAttribute::Strength,
+				value: 4.0,
+			},
+			AttributeEvent::Reset(TestAttribute::Strength),
+		]
+		.into_iter()
+		.collect();
+		let replayed = MockMap::replay(mock_supplier(), &log);
+		assert_eq!(replayed.raw_value(&TestAttribute::Strength), Some(1.0));
+		assert!(replayed.has_attribute(&TestAttribute::Strength));
+	}
+}
@@ -1,17 +1,26 @@
-use std::hash::Hash;
-
-use parking_lot::Mutex;
+use std::{cmp::Ordering, hash::Hash};
 
 use crate::{
 	attribute::{
 		Attribute,
+		cache::ValueCache,
+		journal::{self, RemovedModifier},
 		map::AttributeMap,
-		modifier::{AttributeModifier, Op, Value},
+		modifier::{AttributeModifier, ModifierOrigin, Op, Value},
+		storage::AttributeStorage,
+		tag::Tag,
 	},
+	number::{Arithmetic, Curve, Rounding, Shaping, Widening},
 	prelude::Operation,
 	util_traits::{Key, Number},
 };
 
+/// An attribute of a single actor: its kind, raw value and modifiers.
+///
+/// Modifiers are kept apart by [`ModifierOrigin`]: template modifiers come from the supplier the
+/// instance was created from and are applied first, runtime modifiers are added afterwards in
+/// insertion order. Template modifiers are not serialised; give a deserialised map its supplier
+/// back with [`AttributeMap::attach`] to restore them.
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 // #[derive(Clone)]
 pub struct AttributeInstance<A, M, V = f32, O = Operation>
@@ -23,12 +32,26 @@ where
 {
 	// #[cfg_attr(feature = "serde", serde(skip))]
 	attribute: Attribute<V>,
-	// #[cfg_attr(feature = "serde", serde(skip))]
+	#[cfg_attr(feature = "serde", serde(skip, default = "Vec::new"))]
+	template: Vec<(M, AttributeModifier<A, V, O>)>,
 	modifiers: Vec<(M, AttributeModifier<A, V, O>)>,
 
 	raw_value: V,
+	/// Whether the raw value was set rather than taken from the template; a
+	/// [rebase](AttributeMap::rebase) keeps set raw values.
+	#[cfg_attr(feature = "serde", serde(default))]
+	raw_set: bool,
 	#[cfg_attr(feature = "serde", serde(skip))]
-	cached_value: Mutex<Option<V>>,
+	cached_value: ValueCache<V>,
+	/// Set for attributes that compute in `f64`; restored from the template like its modifiers.
+	#[cfg_attr(feature = "serde", serde(skip, default = "Option::default"))]
+	widening: Option<Widening<V, O>>,
+	/// Declared by the supplier and restored from the template like the widening.
+	#[cfg_attr(feature = "serde", serde(skip, default = "Option::default"))]
+	curve: Option<Shaping<V>>,
+	/// Caps on the total bonus per category, `None` for all categorised bonuses together.
+	#[cfg_attr(feature = "serde", serde(skip, default = "Vec::new"))]
+	caps: Vec<(Option<Tag>, V)>,
 }
 
 impl<A, M, V, O> Clone for AttributeInstance<A, M, V, O>
@@ -41,9 +64,14 @@ where
 	fn clone(&self) -> Self {
 		Self {
 			attribute: self.attribute.clone(),
+			template: self.template.clone(),
 			modifiers: self.modifiers.clone(),
 			raw_value: self.raw_value,
-			cached_value: Mutex::new(*self.cached_value.lock()),
+			raw_set: self.raw_set,
+			cached_value: self.cached_value.clone(),
+			widening: self.widening,
+			curve: self.curve.clone(),
+			caps: self.caps.clone(),
 		}
 	}
 }
@@ -59,9 +87,14 @@ where
 		let raw_value = attribute.default_value();
 		Self {
 			attribute,
+			template: Vec::new(),
 			modifiers: Vec::new(),
 			raw_value,
-			cached_value: Mutex::new(None),
+			raw_set: false,
+			cached_value: ValueCache::default(),
+			widening: None,
+			curve: None,
+			caps: Vec::new(),
 		}
 	}
 
@@ -69,95 +102,390 @@ where
 		AttributeBuilder {
 			attribute,
 			modifiers: Vec::new(),
+			widening: None,
+			caps: Vec::new(),
 		}
 	}
 
+	pub fn attribute(&self) -> &Attribute<V> {
+		&self.attribute
+	}
+
 	pub fn raw_value(&self) -> V {
 		self.raw_value
 	}
 
+	/// The curve shaping the value, as declared by the supplier.
+	pub fn curve(&self) -> Option<&Curve> {
+		self.curve.as_ref().map(Shaping::curve)
+	}
+
+	pub(super) fn set_curve(&mut self, curve: Shaping<V>) {
+		self.curve = Some(curve);
+		self.mark_dirty();
+	}
+
 	pub(super) fn mark_dirty(&self) {
-		self.cached_value.lock().take();
+		self.cached_value.clear();
+	}
+
+	/// Whether the raw value was [set](Self::set_raw_value) since the instance was created or
+	/// [reset](Self::reset).
+	pub fn is_raw_value_set(&self) -> bool {
+		self.raw_set
 	}
 
 	pub fn set_raw_value(&mut self, value: V) {
+		self.raw_set = true;
 		if value != self.raw_value {
 			self.raw_value = value;
 			self.mark_dirty();
 		}
 	}
 
-	pub(super) fn compute_value(&self, attributes: &AttributeMap<A, M, V, O>, base: bool) -> V {
-		let mut value = self.raw_value;
+	pub(super) fn compute_value<S: AttributeStorage<A, Self>>(
+		&self,
+		attributes: &AttributeMap<A, M, V, O, S>,
+		base: bool,
+	) -> V {
+		self.evaluate(attributes, base, |_| {})
+	}
+
+	/// Applies the modifiers that take effect to the raw value, passing each one to `step`.
+	///
+	/// Categorised modifiers are summed per category, and the part of each sum over the caps is
+	/// cut from the category's last modifier. Overriding modifiers are settled last: the winner
+	/// replaces whatever the others made of the value. The curve then shapes the result before
+	/// the range is applied.
+	fn evaluate<S: AttributeStorage<A, Self>>(
+		&self,
+		attributes: &AttributeMap<A, M, V, O, S>,
+		base: bool,
+		mut step: impl FnMut(BreakdownStep<&M, V>),
+	) -> V {
+		let mut stacked = Vec::new();
+		let mut winner: Option<(i32, &M, ModifierOrigin, V)> = None;
+		for (key, modifier, origin) in self.modifiers() {
+			if (base && !modifier.base) || !Self::is_active(modifier, attributes) {
+				continue;
+			}
+			let amount = Self::modifier_value(modifier, attributes);
+			if let Some(priority) = modifier.priority {
+				if winner.as_ref().is_none_or(|(best, ..)| priority >= *best) {
+					winner = Some((priority, key, origin, amount));
+				}
+				continue;
+			}
+			stacked.push((key, modifier, origin, amount));
+		}
 
-		for (_, modifier) in &self.modifiers {
-			if !base || modifier.base {
-				value = Self::apply_modifier(value, modifier, attributes);
+		let mut value = self.raw_value;
+		let wide = if let Some(widening) = &self.widening {
+			let apply = |modifier: &AttributeModifier<A, V, O>, old, amount| {
+				let new = widening.apply(&modifier.op, old, widening.widen(amount));
+				modifier
+					.rounding
+					.map_or(new, |rounding| rounding.apply(new))
+			};
+			let limits = Limits::new(&self.caps, |limit| widening.widen(limit));
+			let mut wide = widening.widen(value);
+			for ((key, _, origin, amount), (new, cut)) in
+				stacked.iter().zip(limits.stack(&stacked, wide, apply))
+			{
+				wide = new;
+				value = widening.narrow(new);
+				step(BreakdownStep {
+					key,
+					origin: *origin,
+					amount: *amount,
+					value,
+					cut: widening.narrow(cut),
+				});
 			}
+			Some(wide)
+		} else {
+			let apply =
+				|modifier: &AttributeModifier<A, V, O>, old, amount| modifier.op.apply(old, amount);
+			let limits = Limits::new(&self.caps, |limit| limit);
+			for ((key, _, origin, amount), (new, cut)) in
+				stacked.iter().zip(limits.stack(&stacked, value, apply))
+			{
+				value = new;
+				step(BreakdownStep {
+					key,
+					origin: *origin,
+					amount: *amount,
+					value,
+					cut,
+				});
+			}
+			None
+		};
+
+		if let Some((_, key, origin, amount)) = winner {
+			value = amount;
+			step(BreakdownStep {
+				key,
+				origin,
+				amount,
+				value,
+				cut: V::default(),
+			});
+		}
+
+		if let Some(curve) = &self.curve {
+			value = match (&self.widening, wide) {
+				// Shaped before rounding, so the curve sees the fractions the modifiers left.
+				(Some(widening), Some(wide)) if winner.is_none() => {
+					widening.narrow(curve.curve().apply(wide))
+				}
+				_ => curve.apply(value),
+			};
 		}
+		self.attribute.sanitize_with::<O>(value)
+	}
 
-		self.attribute.sanitize_value(value)
+	fn is_active<S: AttributeStorage<A, Self>>(
+		modifier: &AttributeModifier<A, V, O>,
+		attributes: &AttributeMap<A, M, V, O, S>,
+	) -> bool {
+		modifier
+			.condition
+			.as_ref()
+			.is_none_or(|condition| condition.holds(attributes))
 	}
 
-	fn apply_modifier(
-		value: V,
+	fn modifier_value<S: AttributeStorage<A, Self>>(
 		modifier: &AttributeModifier<A, V, O>,
-		attributes: &AttributeMap<A, M, V, O>,
+		attributes: &AttributeMap<A, M, V, O, S>,
 	) -> V {
-		let mod_val = match &modifier.value {
+		match &modifier.value {
 			Value::Value(val) => *val,
 			Value::Attribute(attr) => attributes.value(attr).unwrap_or_default(),
-		};
+		}
+	}
 
-		modifier.op.apply(value, mod_val)
+	/// The steps [`value`](Self::value) takes from the raw value to the result.
+	pub fn breakdown<S: AttributeStorage<A, Self>>(
+		&self,
+		attributes: &AttributeMap<A, M, V, O, S>,
+	) -> Breakdown<M, V> {
+		let mut steps = Vec::new();
+		let value = self.evaluate(attributes, false, |step| {
+			steps.push(BreakdownStep {
+				key: step.key.clone(),
+				origin: step.origin,
+				amount: step.amount,
+				value: step.value,
+				cut: step.cut,
+			});
+		});
+
+		Breakdown {
+			raw_value: self.raw_value,
+			steps,
+			value,
+		}
 	}
 
-	pub fn base_value(&self, attributes: &AttributeMap<A, M, V, O>) -> V {
+	pub fn base_value<S: AttributeStorage<A, Self>>(
+		&self,
+		attributes: &AttributeMap<A, M, V, O, S>,
+	) -> V {
 		self.compute_value(attributes, true)
 	}
 
-	pub fn value(&self, attributes: &AttributeMap<A, M, V, O>) -> V {
-		let mut cached_value = self.cached_value.lock();
-		match *cached_value {
-			None => {
-				let val = self.compute_value(attributes, false);
-				cached_value.replace(val);
-				val
-			}
-			Some(val) => val,
+	pub fn value<S: AttributeStorage<A, Self>>(
+		&self,
+		attributes: &AttributeMap<A, M, V, O, S>,
+	) -> V {
+		if let Some(val) = self.cached_value.get() {
+			return val;
 		}
+
+		// Computed without holding the cache, so dependency lookups may re-enter freely. Racing
+		// readers may compute the same value twice, but always publish identical results.
+		let val = self.compute_value(attributes, false);
+		self.cached_value.set(val);
+		val
+	}
+
+	fn all_modifiers(&self) -> impl Iterator<Item = &(M, AttributeModifier<A, V, O>)> {
+		self.template.iter().chain(&self.modifiers)
 	}
 
 	pub fn has_modifier(&self, modifier: &M) -> bool {
-		self.modifiers.iter().any(|(m, _)| modifier.eq(m))
+		self.all_modifiers().any(|(m, _)| modifier.eq(m))
 	}
 	pub fn modifier(&self, modifier: &M) -> Option<&AttributeModifier<A, V, O>> {
-		self.modifiers
-			.iter()
+		self.all_modifiers()
 			.find(|(m, _)| modifier.eq(m))
 			.map(|(_, v)| v)
 	}
 
+	/// Every modifier in the order it is applied, tagged with where it came from.
+	pub fn modifiers(
+		&self,
+	) -> impl Iterator<Item = (&M, &AttributeModifier<A, V, O>, ModifierOrigin)> {
+		let template = self
+			.template
+			.iter()
+			.map(|(m, v)| (m, v, ModifierOrigin::Template));
+		let runtime = self
+			.modifiers
+			.iter()
+			.map(|(m, v)| (m, v, ModifierOrigin::Runtime));
+		template.chain(runtime)
+	}
+
+	pub fn runtime_modifiers(&self) -> impl Iterator<Item = (&M, &AttributeModifier<A, V, O>)> {
+		self.modifiers.iter().map(|(m, v)| (m, v))
+	}
+
+	pub fn template_modifiers(&self) -> impl Iterator<Item = (&M, &AttributeModifier<A, V, O>)> {
+		self.template.iter().map(|(m, v)| (m, v))
+	}
+
+	/// Adds a runtime modifier.
 	pub fn add_modifier(&mut self, id: M, modifier: AttributeModifier<A, V, O>) {
 		self.modifiers.push((id, modifier));
 		self.mark_dirty();
 	}
 
+	pub(super) fn add_template_modifier(&mut self, id: M, modifier: AttributeModifier<A, V, O>) {
+		self.template.push((id, modifier));
+		self.mark_dirty();
+	}
+
+	/// Removes the runtime modifiers keyed `id`, leaving template modifiers in place.
 	pub fn remove_modifier(&mut self, id: &M) -> bool {
-		let l1 = self.modifiers.len();
-		self.modifiers.retain(|(m, _)| id.ne(m));
-		if self.modifiers.len() == l1 {
-			false
-		} else {
+		!self.take_modifiers(id, ModifierOrigin::Runtime).is_empty()
+	}
+
+	/// Removes the template modifiers keyed `id`, e.g. a derivation this actor does without.
+	pub fn remove_template_modifier(&mut self, id: &M) -> bool {
+		!self.take_modifiers(id, ModifierOrigin::Template).is_empty()
+	}
+
+	/// Removes the modifiers of `origin` keyed `id`, returning them with their positions.
+	pub(super) fn take_modifiers(
+		&mut self,
+		id: &M,
+		origin: ModifierOrigin,
+	) -> Vec<RemovedModifier<A, M, V, O>> {
+		let removed: Vec<_> = journal::extract(self.origin_mut(origin), |(m, _)| id.eq(m))
+			.into_iter()
+			.map(|(i, (m, v))| (origin, i, m, v))
+			.collect();
+		if !removed.is_empty() {
 			self.mark_dirty();
-			true
 		}
+		removed
+	}
+
+	/// Puts modifiers taken by [`take_modifiers`](Self::take_modifiers) back in place.
+	pub(super) fn restore_modifiers(&mut self, removed: &[RemovedModifier<A, M, V, O>]) {
+		for (origin, i, m, v) in removed {
+			self.origin_mut(*origin).insert(*i, (m.clone(), v.clone()));
+		}
+		self.mark_dirty();
+	}
+
+	/// Takes the modifiers [`take_modifiers`](Self::take_modifiers) took once more.
+	pub(super) fn retake_modifiers(&mut self, removed: &[RemovedModifier<A, M, V, O>]) {
+		for (origin, i, _, _) in removed.iter().rev() {
+			self.origin_mut(*origin).remove(*i);
+		}
+		self.mark_dirty();
+	}
+
+	/// Removes the last runtime modifier.
+	pub(super) fn pop_modifier(&mut self) {
+		self.modifiers.pop();
+		self.mark_dirty();
+	}
+
+	/// Replaces the modifiers of `origin` wholesale.
+	#[cfg(feature = "replication")]
+	pub(crate) fn replace_modifiers(
+		&mut self,
+		origin: ModifierOrigin,
+		modifiers: Vec<(M, AttributeModifier<A, V, O>)>,
+	) {
+		*self.origin_mut(origin) = modifiers;
+		self.mark_dirty();
+	}
+
+	fn origin_mut(&mut self, origin: ModifierOrigin) -> &mut Vec<(M, AttributeModifier<A, V, O>)> {
+		match origin {
+			ModifierOrigin::Template => &mut self.template,
+			ModifierOrigin::Runtime => &mut self.modifiers,
+		}
+	}
+
+	/// Removes every runtime modifier, returning whether there were any.
+	pub fn clear_runtime_modifiers(&mut self) -> bool {
+		if self.modifiers.is_empty() {
+			return false;
+		}
+		self.modifiers.clear();
+		self.mark_dirty();
+		true
+	}
+
+	/// Drops runtime modifiers and restores the default raw value of the attribute.
+	pub fn reset(&mut self) {
+		self.modifiers.clear();
+		self.raw_value = self.attribute.default_value();
+		self.raw_set = false;
+		self.mark_dirty();
+	}
+
+	/// Moves this instance onto `template`, keeping its runtime modifiers and, if it was
+	/// [set](Self::is_raw_value_set), its raw value.
+	///
+	/// Without a new template the instance keeps its `Attribute` and only loses its template
+	/// modifiers.
+	pub(super) fn rebase(&mut self, template: Option<Self>) {
+		if let Some(template) = &template {
+			if !self.raw_set {
+				self.raw_value = template.raw_value;
+			}
+			self.attribute = template.attribute.clone();
+		}
+		self.restore_template(template);
+	}
+
+	/// Puts back a raw value and whether it was set, e.g. when an edit is undone.
+	pub(crate) fn restore_raw_value(&mut self, value: V, set: bool) {
+		self.raw_value = value;
+		self.raw_set = set;
+		self.mark_dirty();
+	}
+
+	/// Takes the template modifiers, rounding, curve and caps from `template`, or drops the
+	/// template modifiers without one. Raw value and runtime modifiers are kept.
+	pub(super) fn restore_template(&mut self, template: Option<Self>) {
+		match template {
+			Some(template) => {
+				self.template = template.template;
+				self.widening = template.widening;
+				self.curve = template.curve;
+				self.caps = template.caps;
+			}
+			None => self.template.clear(),
+		}
+		self.mark_dirty();
 	}
 
 	pub fn depends_on(&self, attr: &A) -> bool {
-		self.modifiers
-			.iter()
-			.any(|(_, modifier)| modifier.value.is_attribute(attr))
+		self.sources().any(|source| source == attr)
+	}
+
+	/// The attributes the modifiers read, repeated for every modifier reading them.
+	pub(super) fn sources(&self) -> impl Iterator<Item = &A> {
+		self.all_modifiers()
+			.flat_map(|(_, modifier)| modifier.sources())
 	}
 }
 
@@ -185,6 +513,151 @@ where
 	}
 }
 
+/// How an attribute arrives at its value, see [`AttributeInstance::breakdown`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Breakdown<M, V> {
+	pub raw_value: V,
+	/// Every modifier that took effect in the order it was applied, the winning override last.
+	pub steps: Vec<BreakdownStep<M, V>>,
+	/// The final value, after the attribute's curve and range were applied.
+	pub value: V,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BreakdownStep<M, V> {
+	pub key: M,
+	pub origin: ModifierOrigin,
+	/// The value the modifier contributed.
+	pub amount: V,
+	/// The running value after applying it.
+	pub value: V,
+	/// How much of the bonuses was cut here to keep them under the
+	/// [caps](AttributeBuilder::cap); it is already taken off `value`.
+	pub cut: V,
+}
+
+/// The caps of an attribute, in the type it computes in.
+struct Limits<'a, T> {
+	/// The category of each cap, `None` for all categories, and its limit.
+	caps: Vec<(Option<&'a Tag>, T)>,
+}
+
+impl<'a, T: Number> Limits<'a, T> {
+	fn new<V: Copy>(caps: &'a [(Option<Tag>, V)], convert: impl Fn(V) -> T) -> Self {
+		Self {
+			caps: caps
+				.iter()
+				.map(|(category, limit)| (category.as_ref(), convert(*limit)))
+				.collect(),
+		}
+	}
+
+	/// The lowest limit of the caps on `scope`.
+	fn limit(&self, scope: Option<&Tag>) -> Option<T> {
+		self.caps
+			.iter()
+			.filter(|(category, _)| *category == scope)
+			.map(|(_, limit)| *limit)
+			.reduce(|a, b| if b < a { b } else { a })
+	}
+
+	/// Applies `stacked` to `start` with `apply`, returning the running value after each
+	/// modifier and the amount cut from it.
+	///
+	/// Each bonus or penalty is measured on the running value, earlier cuts included. A
+	/// category's bonuses and penalties are summed; the part of the sum over its cap is cut from
+	/// its last modifier. The same then goes for the sum of all categories under the bonus cap,
+	/// cut from the last categorised modifier. A cut stops at zero, so it cannot underflow an
+	/// unsigned value.
+	fn stack<K, U: Copy, W>(
+		&self,
+		stacked: &[(K, &W, ModifierOrigin, U)],
+		start: T,
+		apply: impl Fn(&W, T, U) -> T,
+	) -> Vec<(T, T)>
+	where
+		W: Categorised,
+	{
+		// The index of the last modifier of each category.
+		let mut lasts: Vec<(&Tag, usize)> = Vec::new();
+		if !self.caps.is_empty() {
+			for (i, (_, modifier, ..)) in stacked.iter().enumerate() {
+				if let Some(category) = modifier.category() {
+					match lasts.iter_mut().find(|(c, _)| *c == category) {
+						Some((_, last)) => *last = i,
+						None => lasts.push((category, i)),
+					}
+				}
+			}
+		}
+		let last = lasts.iter().map(|(_, i)| *i).max();
+
+		// The bonuses and penalties of each category, and of all categories after their caps.
+		let mut sums: Vec<(&Tag, T, T)> = Vec::new();
+		let (mut bonus, mut penalty) = (T::default(), T::default());
+		let mut value = start;
+		let mut steps = Vec::with_capacity(stacked.len());
+		for (i, (_, modifier, _, amount)) in stacked.iter().enumerate() {
+			let new = apply(modifier, value, *amount);
+			let mut cut = T::default();
+			if let Some(category) = modifier.category().filter(|_| last.is_some()) {
+				let index = sums
+					.iter()
+					.position(|(c, ..)| *c == category)
+					.unwrap_or_else(|| {
+						sums.push((category, T::default(), T::default()));
+						sums.len() - 1
+					});
+				let (_, category_bonus, category_penalty) = &mut sums[index];
+				match new.partial_cmp(&value) {
+					Some(Ordering::Greater) => *category_bonus = *category_bonus + (new - value),
+					Some(Ordering::Less) => *category_penalty = *category_penalty + (value - new),
+					_ => {}
+				}
+				if lasts.contains(&(category, i)) {
+					cut = Self::excess(
+						*category_bonus,
+						*category_penalty,
+						self.limit(Some(category)),
+					);
+					bonus = bonus + (*category_bonus - cut);
+					penalty = penalty + *category_penalty;
+				}
+				if last == Some(i) {
+					cut = cut + Self::excess(bonus, penalty, self.limit(None));
+				}
+			}
+			value = if cut > new && new >= T::default() {
+				T::default()
+			} else {
+				new - cut
+			};
+			steps.push((value, new - value));
+		}
+		steps
+	}
+
+	/// How far `bonus` less `penalty` exceeds `limit`.
+	fn excess(bonus: T, penalty: T, limit: Option<T>) -> T {
+		match limit {
+			Some(limit) if bonus > penalty && bonus - penalty > limit => bonus - penalty - limit,
+			_ => T::default(),
+		}
+	}
+}
+
+/// A modifier that may belong to a capped category.
+trait Categorised {
+	fn category(&self) -> Option<&Tag>;
+}
+
+impl<A, V: 'static, O: Op<V>> Categorised for AttributeModifier<A, V, O> {
+	fn category(&self) -> Option<&Tag> {
+		self.category.as_ref()
+	}
+}
+
+/// Builds a template instance; its modifiers are [template](ModifierOrigin::Template) modifiers.
 #[must_use]
 pub struct AttributeBuilder<A, M, V = f32, O = Operation>
 where
@@ -195,6 +668,8 @@ where
 {
 	attribute: Attribute<V>,
 	modifiers: Vec<(M, AttributeModifier<A, V, O>)>,
+	widening: Option<Widening<V, O>>,
+	caps: Vec<(Option<Tag>, V)>,
 }
 
 impl<A, M, V, O> AttributeBuilder<A, M, V, O>
@@ -209,6 +684,32 @@ where
 		self
 	}
 
+	/// Makes the attribute compute in [`f64`], so fractional modifiers like halving or
+	/// percentages neither truncate nor overflow along the way, and round the result with
+	/// `rounding`. Modifiers can round sooner with
+	/// [`AttributeModifier::rounded`].
+	pub fn rounding(mut self, rounding: Rounding) -> Self
+	where
+		V: Arithmetic,
+		O: Op<f64>,
+	{
+		self.widening = Some(Widening::new(rounding));
+		self
+	}
+
+	/// Caps the total bonus of the modifiers in `category` at `limit`, e.g. "equipment bonuses
+	/// cap at +5". Penalties in the category count against its bonuses before the cap applies.
+	pub fn cap<T: Into<Tag>>(mut self, category: T, limit: V) -> Self {
+		self.caps.push((Some(category.into()), limit));
+		self
+	}
+
+	/// Caps the total bonus of all categorised modifiers together at `limit`.
+	pub fn bonus_cap(mut self, limit: V) -> Self {
+		self.caps.push((None, limit));
+		self
+	}
+
 	// pub fn attribute(mut self, attr: A, modifier: AttributeModifier<A, V>) -> Self {
 	// 	self.modifier()
 	// }
@@ -225,8 +726,10 @@ where
 		let raw_value = value.attribute.default_value();
 		Self {
 			attribute: value.attribute,
-			modifiers: value.modifiers,
+			template: value.modifiers,
 			raw_value,
+			widening: value.widening,
+			caps: value.caps,
 			..Self::default()
 		}
 	}
@@ -238,7 +741,7 @@ mod tests {
 	use crate::attribute::{
 		Attribute,
 		map::AttributeMap,
-		modifier::{AttributeModifier, Operation, Value},
+		modifier::{ArithmeticOp, AttributeModifier, Operation, Value},
 	};
 
 	#[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -251,13 +754,11 @@ mod tests {
 		let builder: AttributeBuilder<TestKey, TestKey, i32> = AttributeBuilder {
 			attribute: attr,
 			modifiers: Vec::new(),
+			widening: None,
+			caps: Vec::new(),
 		};
 
-		let modifier = AttributeModifier {
-			value: Value::Value(5),
-			op: Operation::Add,
-			base: false,
-		};
+		let modifier = AttributeModifier::new(5, Operation::Add);
 
 		let builder = builder.modifier(TestKey("mod1"), modifier.clone());
 		assert_eq!(builder.modifiers.len(), 1);
@@ -283,11 +784,7 @@ mod tests {
 	fn test_attribute_instance_add_modifier() {
 		let attr = Attribute::Value(10);
 		let mut instance = AttributeInstance::<TestKey, TestKey, i32>::new(attr);
-		let modifier = AttributeModifier {
-			value: Value::Value(5),
-			op: Operation::Add,
-			base: false,
-		};
+		let modifier = AttributeModifier::new(5, Operation::Add);
 		instance.add_modifier(TestKey("mod1"), modifier);
 		assert!(instance.has_modifier(&TestKey("mod1")));
 	}
@@ -296,11 +793,7 @@ mod tests {
 	fn test_attribute_instance_remove_modifier() {
 		let attr = Attribute::Value(10);
 		let mut instance = AttributeInstance::<TestKey, TestKey, i32>::new(attr);
-		let modifier = AttributeModifier {
-			value: Value::Value(5),
-			op: Operation::Add,
-			base: false,
-		};
+		let modifier = AttributeModifier::new(5, Operation::Add);
 		instance.add_modifier(TestKey("mod1"), modifier);
 		assert!(instance.remove_modifier(&TestKey("mod1")));
 		assert!(!instance.has_modifier(&TestKey("mod1")));
@@ -310,16 +803,8 @@ mod tests {
 	fn test_attribute_instance_compute_value_with_multiple_modifiers() {
 		let attr = Attribute::Value(10);
 		let mut instance = AttributeInstance::<TestKey, TestKey, i32>::new(attr);
-		let modifier1 = AttributeModifier {
-			value: Value::Value(5),
-			op: Operation::Add,
-			base: false,
-		};
-		let modifier2 = AttributeModifier {
-			value: Value::Value(3),
-			op: Operation::Sub,
-			base: false,
-		};
+		let modifier1 = AttributeModifier::new(5, Operation::Add);
+		let modifier2 = AttributeModifier::new(3, Operation::Sub);
 		instance.add_modifier(TestKey("mod1"), modifier1);
 		instance.add_modifier(TestKey("mod2"), modifier2);
 		let attributes = AttributeMap::<TestKey, TestKey, i32>::default();
@@ -330,11 +815,8 @@ mod tests {
 	fn test_attribute_instance_depends_on() {
 		let attr = Attribute::Value(10);
 		let mut instance = AttributeInstance::<TestKey, TestKey, i32>::new(attr);
-		let modifier = AttributeModifier {
-			value: Value::Attribute(TestKey("dependency")),
-			op: Operation::Add,
-			base: false,
-		};
+		let modifier =
+			AttributeModifier::new(Value::Attribute(TestKey("dependency")), Operation::Add);
 		instance.add_modifier(TestKey("mod1"), modifier);
 		assert!(instance.depends_on(&TestKey("dependency")));
 	}
@@ -348,4 +830,168 @@ mod tests {
 		let val2 = instance.value(&attributes);
 		assert_eq!(val1, val2);
 	}
+
+	#[test]
+	fn test_rounding() {
+		let attributes = AttributeMap::<TestKey, TestKey, u8, ArithmeticOp>::default();
+		let bonus = || AttributeModifier::new(150, ArithmeticOp::Percent);
+		let value = |builder: AttributeBuilder<TestKey, TestKey, u8, ArithmeticOp>| {
+			AttributeInstance::from(builder).value(&attributes)
+		};
+
+		let seven =
+			|| AttributeInstance::builder(Attribute::Value(7)).modifier(TestKey("bonus"), bonus());
+		assert_eq!(value(seven()), 10);
+		assert_eq!(value(seven().rounding(Rounding::Floor)), 10);
+		assert_eq!(value(seven().rounding(Rounding::Ceil)), 11);
+		assert_eq!(value(seven().rounding(Rounding::Nearest)), 11);
+		assert_eq!(value(seven().rounding(Rounding::Banker)), 10);
+
+		// Halving rounds down on the spot, the bonus rounds up at the end.
+		let halved = |modifier: AttributeModifier<TestKey, u8, ArithmeticOp>| {
+			AttributeInstance::builder(Attribute::Value(5))
+				.modifier(TestKey("halved"), modifier)
+				.modifier(TestKey("bonus"), bonus())
+				.rounding(Rounding::Ceil)
+		};
+		let half = AttributeModifier::new(2, ArithmeticOp::Div);
+		assert_eq!(value(halved(half.clone())), 4);
+		assert_eq!(value(halved(half.rounded(Rounding::Floor))), 3);
+
+		// Intermediate values may leave the range of `V`.
+		let doubled = AttributeInstance::builder(Attribute::Value(200))
+			.modifier(
+				TestKey("double"),
+				AttributeModifier::new(2, ArithmeticOp::Mul),
+			)
+			.modifier(
+				TestKey("quarter"),
+				AttributeModifier::new(4, ArithmeticOp::Div),
+			)
+			.rounding(Rounding::Floor);
+		assert_eq!(value(doubled), 100);
+	}
+
+	#[test]
+	#[allow(clippy::float_cmp)]
+	fn test_rounded_modifier_without_rounding_mode() {
+		let attributes = AttributeMap::<TestKey, TestKey, f32, ArithmeticOp>::default();
+		let mut instance =
+			AttributeInstance::<TestKey, TestKey, f32, ArithmeticOp>::new(Attribute::Value(5.0));
+		instance.add_modifier(
+			TestKey("halved"),
+			AttributeModifier::new(2.0, ArithmeticOp::Div).rounded(Rounding::Floor),
+		);
+		assert_eq!(instance.value(&attributes), 2.5);
+	}
+
+	#[test]
+	fn test_caps() {
+		let attributes = AttributeMap::<TestKey, TestKey, i32>::default();
+		let bonus = |amount| AttributeModifier::new(amount, Operation::Add);
+		let instance = AttributeInstance::from(
+			AttributeInstance::builder(Attribute::Value(10))
+				.cap("equipment", 5)
+				.cap("magic", 3)
+				.bonus_cap(7)
+				.modifier(TestKey("sword"), bonus(3).category("equipment"))
+				.modifier(TestKey("helm"), bonus(4).category("equipment"))
+				.modifier(TestKey("curse"), bonus(-2).category("magic"))
+				.modifier(TestKey("ring"), bonus(3).category("magic"))
+				.modifier(TestKey("amulet"), bonus(2).category("magic"))
+				.modifier(TestKey("training"), bonus(1)),
+		);
+		assert_eq!(instance.value(&attributes), 18);
+
+		let breakdown = instance.breakdown(&attributes);
+		let steps: Vec<_> = breakdown
+			.steps
+			.iter()
+			.map(|step| (step.key.0, step.value, step.cut))
+			.collect();
+		assert_eq!(
+			steps,
+			[
+				("sword", 13, 0),
+				("helm", 15, 2),
+				// Penalties count against the bonuses of their category.
+				("curse", 13, 0),
+				("ring", 16, 0),
+				// Within the magic cap, but over the cap on all bonuses.
+				("amulet", 17, 1),
+				("training", 18, 0),
+			]
+		);
+
+		// The sum is capped, not each bonus in turn.
+		let instance = AttributeInstance::from(
+			AttributeInstance::builder(Attribute::Value(10))
+				.cap("equipment", 5)
+				.modifier(TestKey("sword"), bonus(4).category("equipment"))
+				.modifier(TestKey("helm"), bonus(4).category("equipment"))
+				.modifier(TestKey("rust"), bonus(-3).category("equipment")),
+		);
+		assert_eq!(instance.value(&attributes), 15);
+
+		let attributes = AttributeMap::<TestKey, TestKey, u8, ArithmeticOp>::default();
+		let instance = AttributeInstance::from(
+			AttributeInstance::builder(Attribute::Value(10))
+				.cap("equipment", 5)
+				.rounding(Rounding::Floor)
+				.modifier(
+					TestKey("enchant"),
+					AttributeModifier::new(160, ArithmeticOp::Percent).category("equipment"),
+				),
+		);
+		assert_eq!(instance.value(&attributes), 15);
+	}
+
+	#[test]
+	fn test_caps_measure_the_capped_value() {
+		let attributes = AttributeMap::<TestKey, TestKey, i32, ArithmeticOp>::default();
+		let instance = AttributeInstance::from(
+			AttributeInstance::builder(Attribute::Value(10))
+				.cap("equipment", 5)
+				.cap("magic", 12)
+				.modifier(
+					TestKey("sword"),
+					AttributeModifier::new(8, ArithmeticOp::Add).category("equipment"),
+				)
+				.modifier(
+					TestKey("blessing"),
+					AttributeModifier::new(200, ArithmeticOp::Percent).category("magic"),
+				),
+		);
+
+		// The blessing doubles the capped 15, a bonus of 15 rather than 18.
+		let steps: Vec<_> = instance
+			.breakdown(&attributes)
+			.steps
+			.iter()
+			.map(|step| (step.key.0, step.value, step.cut))
+			.collect();
+		assert_eq!(steps, [("sword", 15, 3), ("blessing", 27, 3)]);
+		assert_eq!(instance.value(&attributes), 27);
+	}
+
+	#[test]
+	fn test_caps_do_not_underflow() {
+		let attributes = AttributeMap::<TestKey, TestKey, u8>::default();
+		let bonus = |amount| AttributeModifier::new(amount, Operation::Add).category("equipment");
+		let instance = AttributeInstance::from(
+			AttributeInstance::builder(Attribute::Value(0))
+				.cap("equipment", 5)
+				.modifier(TestKey("sword"), bonus(10))
+				.modifier(TestKey("drain"), AttributeModifier::new(10, Operation::Sub))
+				.modifier(TestKey("helm"), bonus(0)),
+		);
+
+		let steps: Vec<_> = instance
+			.breakdown(&attributes)
+			.steps
+			.iter()
+			.map(|step| (step.key.0, step.value, step.cut))
+			.collect();
+		assert_eq!(steps, [("sword", 10, 0), ("drain", 0, 0), ("helm", 0, 0)]);
+	}
 }
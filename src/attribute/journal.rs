@@ -0,0 +1,147 @@
+use std::time::Duration;
+
+use crate::{
+	advancement::Advancement,
+	attribute::{
+		discrete::Override,
+		modifier::{AttributeModifier, ModifierOrigin, Op},
+		tag::Tag,
+	},
+};
+
+/// A modifier removed from an instance, with its origin and index among those of that origin.
+pub(crate) type RemovedModifier<A, M, V, O> =
+	(ModifierOrigin, usize, M, AttributeModifier<A, V, O>);
+
+/// A tag modifier removed from a map, with its index.
+pub(crate) type RemovedTagModifier<A, M, V, O> = (usize, (Tag, M, AttributeModifier<A, V, O>));
+
+/// A single reversible change to an [`AttributeMap`](super::map::AttributeMap).
+#[derive(Clone, derive_more::Debug)]
+pub(crate) enum Edit<A, M, V, O>
+where
+	V: 'static,
+	O: Op<V>,
+{
+	/// The attribute was materialised from the supplier.
+	Materialise(A),
+	SetRawValue {
+		attribute: A,
+		from: V,
+		/// Whether `from` had been set rather than taken from the template.
+		from_set: bool,
+		to: V,
+	},
+	/// A runtime modifier was pushed onto the attribute.
+	AddModifier {
+		attribute: A,
+		key: M,
+		modifier: AttributeModifier<A, V, O>,
+	},
+	/// Modifiers were removed from the attribute, in ascending index order.
+	RemoveModifiers {
+		attribute: A,
+		removed: Vec<RemovedModifier<A, M, V, O>>,
+	},
+	Timers(Vec<(usize, (M, Duration))>),
+	/// The timers were set or ran down, from the first list to the second.
+	SetTimers {
+		from: Vec<(M, Duration)>,
+		to: Vec<(M, Duration)>,
+	},
+	/// The advancement was appended to the history.
+	Advance(Advancement<A, V>),
+	TagModifiers(Vec<RemovedTagModifier<A, M, V, O>>),
+	/// The discrete attribute was materialised from the supplier.
+	MaterialiseState(A),
+	SetRawState {
+		attribute: A,
+		from: Tag,
+		/// Whether `from` had been set rather than taken from the supplier.
+		from_set: bool,
+		to: Tag,
+	},
+	/// An override was pushed onto the discrete attribute.
+	AddOverride {
+		attribute: A,
+		key: M,
+		priority: i32,
+		state: Tag,
+	},
+	/// Overrides were removed from the discrete attribute, in ascending index order.
+	RemoveOverrides {
+		attribute: A,
+		removed: Vec<(usize, Override<M>)>,
+	},
+}
+
+/// Undo and redo stacks of edits; every entry holds the edits of one map operation.
+#[derive(Clone, derive_more::Debug)]
+pub(crate) struct Journal<A, M, V, O>
+where
+	V: 'static,
+	O: Op<V>,
+{
+	pub(crate) undo: Vec<Vec<Edit<A, M, V, O>>>,
+	pub(crate) redo: Vec<Vec<Edit<A, M, V, O>>>,
+}
+
+impl<A, M, V, O> Default for Journal<A, M, V, O>
+where
+	V: 'static,
+	O: Op<V>,
+{
+	fn default() -> Self {
+		Self {
+			undo: Vec::new(),
+			redo: Vec::new(),
+		}
+	}
+}
+
+/// Removes the items matching `pred`, returning them with their original indices, ascending.
+pub(crate) fn extract<T>(items: &mut Vec<T>, mut pred: impl FnMut(&T) -> bool) -> Vec<(usize, T)> {
+	let mut removed = Vec::new();
+	let mut i = 0;
+	while i < items.len() {
+		if pred(&items[i]) {
+			removed.push((i + removed.len(), items.remove(i)));
+		} else {
+			i += 1;
+		}
+	}
+	removed
+}
+
+/// Puts items taken by [`extract`] back at their original indices.
+pub(crate) fn restore<T: Clone>(items: &mut Vec<T>, removed: &[(usize, T)]) {
+	for (i, item) in removed {
+		items.insert(*i, item.clone());
+	}
+}
+
+/// Removes the items at the given original indices again, e.g. to redo an [`extract`].
+pub(crate) fn remove_at<T>(items: &mut Vec<T>, removed: &[(usize, T)]) {
+	for (i, _) in removed.iter().rev() {
+		items.remove(*i);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_extract_and_restore() {
+		let mut items = vec![1, 2, 3, 4, 5, 6];
+		let removed = extract(&mut items, |i| i % 2 == 0);
+		assert_eq!(items, [1, 3, 5]);
+		assert_eq!(removed, [(1, 2), (3, 4), (5, 6)]);
+
+		restore(&mut items, &removed);
+		assert_eq!(items, [1, 2, 3, 4, 5, 6]);
+
+		remove_at(&mut items, &removed);
+		assert_eq!(items, [1, 3, 5]);
+	}
+}
@@ -2,51 +2,180 @@ use std::{
 	collections::{HashMap, hash_map::Entry},
 	hash::Hash,
 	sync::Arc,
+	time::Duration,
 };
 
 use crate::{
+	advancement::{AdvanceError, Advancement},
 	attribute::{
-		instance::AttributeInstance,
-		modifier::{AttributeModifier, Op},
+		Attribute,
+		discrete::DiscreteInstance,
+		event::{AttributeEvent, EventLog},
+		instance::{AttributeInstance, Breakdown},
+		journal::{self, Edit, Journal},
+		modifier::{AttributeModifier, ModifierOrigin, Op},
+		storage::{AttributeStorage, DenseStorage},
 		supplier::AttributeSupplier,
+		tag::Tag,
 	},
+	number::Arithmetic,
 	prelude::Operation,
 	util_traits::{Key, Number},
 };
 
+/// An instance borrowed to change it, and whether it was materialised for that.
+type Materialised<'a, T> = Option<(&'a mut T, bool)>;
+
 #[cfg(feature = "serde")]
 fn sp_default<A: Key + Hash, M: Key, V: Number + 'static, O: Op<V>>()
 -> Option<Arc<AttributeSupplier<A, M, V, O>>> {
 	None
 }
 
+/// The attributes of a single actor, materialised lazily from an [`AttributeSupplier`].
+///
+/// # Thread safety
+///
+/// Unless the `single-threaded` feature is enabled, an `AttributeMap` is `Send + Sync` whenever
+/// its key, value and operation types are. Any number of threads may call [`value`](Self::value)
+/// and [`base_value`](Self::base_value) through a shared reference at the same time: computed
+/// values are cached in atomic cells that are never held while dependencies are evaluated, so
+/// recursive cache fills cannot dead-lock. Racing readers may compute the same value twice, but
+/// always store the same result. Mutation requires `&mut self`; to mix readers with a writer, wrap
+/// the map in a [`SharedAttributeMap`](crate::attribute::shared::SharedAttributeMap).
+///
+/// # Storage
+///
+/// Materialised instances live in `S`, a [`HashMap`] by default. Keys implementing
+/// [`AttributeIndex`](crate::attribute::storage::AttributeIndex) can use [`DenseAttributeMap`]
+/// to store them in a slice instead.
+///
+/// # Serialisation
+///
+/// Only the per-actor state is serialised: the supplier, and with it each attribute's template
+/// modifiers, rounding, curve and caps, is skipped. Give a deserialised map its supplier back with
+/// [`attach`](Self::attach) before reading values.
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, derive_more::Debug)]
-pub struct AttributeMap<A, M, V = f32, O = Operation>
+pub struct AttributeMap<A, M, V = f32, O = Operation, S = HashMap<A, AttributeInstance<A, M, V, O>>>
 where
 	A: Key + Hash + 'static,
 	M: Key + 'static,
 	V: Number + 'static,
 	O: Op<V>,
+	S: AttributeStorage<A, AttributeInstance<A, M, V, O>>,
 {
 	#[cfg_attr(feature = "serde", serde(skip, default = "sp_default"))]
 	supplier: Option<Arc<AttributeSupplier<A, M, V, O>>>,
-	attributes: HashMap<A, AttributeInstance<A, M, V, O>>,
+	attributes: S,
+	/// Remaining lifetime of timed modifiers, see [`tick`](Self::tick).
+	#[cfg_attr(feature = "serde", serde(default = "Vec::new"))]
+	timers: Vec<(M, Duration)>,
+	/// Materialised [discrete](crate::attribute::discrete) attributes.
+	#[cfg_attr(feature = "serde", serde(default = "HashMap::new"))]
+	discrete: HashMap<A, DiscreteInstance<M>>,
+	/// Modifiers given to every attribute carrying a tag, see
+	/// [`add_tag_modifier`](Self::add_tag_modifier).
+	#[cfg_attr(feature = "serde", serde(default = "Vec::new"))]
+	tag_modifiers: Vec<(Tag, M, AttributeModifier<A, V, O>)>,
+	/// Every [`advance`](Self::advance) made, oldest first.
+	#[cfg_attr(feature = "serde", serde(default = "Vec::new"))]
+	history: Vec<Advancement<A, V>>,
+	/// Undo and redo stacks, while [journaling](Self::enable_journal).
+	#[cfg_attr(feature = "serde", serde(skip, default = "Option::default"))]
+	journal: Option<Journal<A, M, V, O>>,
+	/// Mutations made since [recording](Self::record_events) started.
+	#[cfg_attr(feature = "serde", serde(skip, default = "Option::default"))]
+	events: Option<EventLog<A, M, V, O>>,
 }
 
-impl<A, M, V, O> AttributeMap<A, M, V, O>
+/// An [`AttributeMap`] backed by [`DenseStorage`].
+pub type DenseAttributeMap<A, M, V = f32, O = Operation> =
+	AttributeMap<A, M, V, O, DenseStorage<A, AttributeInstance<A, M, V, O>>>;
+
+impl<A, M, V, O, S> AttributeMap<A, M, V, O, S>
 where
 	A: Key + Hash,
 	M: Key,
 	V: Number + 'static,
 	O: Op<V>,
+	S: AttributeStorage<A, AttributeInstance<A, M, V, O>>,
 {
 	#[must_use]
 	pub fn new(supplier: Arc<AttributeSupplier<A, M, V, O>>) -> Self {
 		AttributeMap {
 			supplier: Some(supplier),
-			attributes: HashMap::new(),
+			attributes: S::default(),
+			discrete: HashMap::new(),
+			timers: Vec::new(),
+			tag_modifiers: Vec::new(),
+			history: Vec::new(),
+			journal: None,
+			events: None,
+		}
+	}
+
+	pub fn supplier(&self) -> Option<&Arc<AttributeSupplier<A, M, V, O>>> {
+		self.supplier.as_ref()
+	}
+
+	/// Swaps in `supplier`, e.g. after its definitions were reloaded, migrating every
+	/// materialised attribute onto its new template.
+	///
+	/// Runtime modifiers and raw values that were [set](Self::set_raw_value) are kept;
+	/// template modifiers, the [`Attribute`](crate::attribute::Attribute) kind and its range are
+	/// taken from the new supplier. [Tag modifiers](Self::add_tag_modifier) follow the new tags,
+	/// removing every runtime modifier keyed like one whose tag an attribute lost. Attributes the
+	/// new supplier no longer defines keep their runtime state. Discrete attributes keep their
+	/// overrides, and take the new initial state unless their raw state was set. All cached
+	/// values are invalidated.
+	pub fn rebase(&mut self, supplier: Arc<AttributeSupplier<A, M, V, O>>) {
+		self.log(|| AttributeEvent::Rebase);
+		for (id, instance) in &mut self.discrete {
+			if let Some(state) = supplier.discrete_state(id) {
+				instance.rebase(state);
+			}
 		}
+		for (id, attr) in self.attributes.iter_mut() {
+			let old = self.supplier.as_ref();
+			attr.rebase(supplier.create_instance(id));
+
+			// Tag modifiers are re-evaluated against the new tags: a key whose tag was dropped is
+			// removed and granted again only by the tags that remain.
+			let had = |tag| old.is_some_and(|old| old.has_tag(id, tag));
+			let stale: Vec<&M> = self
+				.tag_modifiers
+				.iter()
+				.filter(|(tag, ..)| had(tag) && !supplier.has_tag(id, tag))
+				.map(|(_, key, _)| key)
+				.collect();
+			for key in &stale {
+				attr.take_modifiers(key, ModifierOrigin::Runtime);
+			}
+			for (tag, key, modifier) in &self.tag_modifiers {
+				if supplier.has_tag(id, tag) && (!had(tag) || stale.contains(&key)) {
+					attr.add_modifier(key.clone(), modifier.clone());
+				}
+			}
+		}
+		self.supplier = Some(supplier);
+		self.link_all();
+		self.materialise_tagged();
+		self.discard_journal();
+	}
+
+	/// Gives a deserialised map its `supplier`, restoring each attribute's template modifiers,
+	/// rounding, curve and caps from it, and the storage's notes of what reads what.
+	///
+	/// Unlike [`rebase`](Self::rebase), raw values, runtime modifiers, tag modifiers and
+	/// discrete states are kept exactly as they were serialised.
+	pub fn attach(&mut self, supplier: Arc<AttributeSupplier<A, M, V, O>>) {
+		for (id, attr) in self.attributes.iter_mut() {
+			attr.restore_template(supplier.create_instance(id));
+		}
+		self.supplier = Some(supplier);
+		self.link_all();
+		self.discard_journal();
 	}
 
 	pub fn has_attribute(&self, attribute: &A) -> bool {
@@ -65,46 +194,465 @@ where
 		modifier: M,
 		instance: AttributeModifier<A, V, O>,
 	) -> &mut Self {
-		if let Some(attr) = self.get_mut(attribute.clone()) {
-			attr.add_modifier(modifier, instance);
+		self.log(|| AttributeEvent::AddModifier {
+			attribute: attribute.clone(),
+			key: modifier.clone(),
+			modifier: instance.clone(),
+		});
+		let journaling = self.journal.is_some();
+		if let Some((attr, materialise)) = self.get_mut(attribute) {
+			if journaling {
+				attr.add_modifier(modifier.clone(), instance.clone());
+				let add = Edit::AddModifier {
+					attribute: attribute.clone(),
+					key: modifier,
+					modifier: instance,
+				};
+				self.record(attribute, materialise, vec![add]);
+			} else {
+				attr.add_modifier(modifier, instance);
+			}
 			self.mark_dependents_dirty(attribute);
 		}
 
 		self
 	}
 
+	/// Removes the runtime modifiers keyed `modifier` from `attribute`, and the overrides keyed
+	/// `modifier` of the discrete `attribute`. Template modifiers sharing the key are left in
+	/// place; see [`remove_template_modifier`](Self::remove_template_modifier).
 	pub fn remove_modifier(&mut self, attribute: &A, modifier: &M) {
+		self.log(|| AttributeEvent::RemoveModifier {
+			attribute: attribute.clone(),
+			key: modifier.clone(),
+		});
+		let mut edits = Vec::new();
 		if let Some(attr) = self.attributes.get_mut(attribute) {
-			attr.remove_modifier(modifier);
+			let removed = attr.take_modifiers(modifier, ModifierOrigin::Runtime);
+			if !removed.is_empty() {
+				edits.push(Edit::RemoveModifiers {
+					attribute: attribute.clone(),
+					removed,
+				});
+			}
+		}
+		if let Some(instance) = self.discrete.get_mut(attribute) {
+			let removed = instance.take_overrides(modifier);
+			if !removed.is_empty() {
+				edits.push(Edit::RemoveOverrides {
+					attribute: attribute.clone(),
+					removed,
+				});
+			}
+		}
+		if !edits.is_empty() {
+			self.record(attribute, false, edits);
 			self.mark_dependents_dirty(attribute);
 		}
 	}
 
-	pub fn remove_modifiers(&mut self, modifier: &M) {
-		let v: Box<[A]> = self
-			.attributes
-			.iter_mut()
-			.filter_map(|(id, attr)| {
-				if attr.remove_modifier(modifier) {
-					Some(id.clone())
-				} else {
-					None
+	/// Removes the template modifiers keyed `modifier` from this map's `attribute`, e.g. a
+	/// derivation one actor does without. The supplier is left unchanged.
+	pub fn remove_template_modifier(&mut self, attribute: &A, modifier: &M) {
+		self.log(|| AttributeEvent::RemoveTemplateModifier {
+			attribute: attribute.clone(),
+			key: modifier.clone(),
+		});
+		if let Some((attr, materialise)) = self.get_mut(attribute) {
+			let removed = attr.take_modifiers(modifier, ModifierOrigin::Template);
+			if removed.is_empty() {
+				if materialise {
+					self.attributes.remove(attribute);
 				}
-			})
+				return;
+			}
+			let edit = Edit::RemoveModifiers {
+				attribute: attribute.clone(),
+				removed,
+			};
+			self.record(attribute, materialise, vec![edit]);
+			self.mark_dependents_dirty(attribute);
+		}
+	}
+
+	/// Adds `instance` as a runtime modifier to every attribute carrying `tag`, including those
+	/// materialised later on.
+	///
+	/// Tags are declared by the supplier; without one this does nothing. Remove the modifier again
+	/// with [`remove_modifiers`](Self::remove_modifiers).
+	pub fn add_tag_modifier(
+		&mut self,
+		tag: &Tag,
+		modifier: M,
+		instance: AttributeModifier<A, V, O>,
+	) -> &mut Self {
+		self.log(|| AttributeEvent::AddTagModifier {
+			tag: tag.clone(),
+			key: modifier.clone(),
+			modifier: instance.clone(),
+		});
+		let Some(supplier) = self.supplier.clone() else {
+			return self;
+		};
+
+		let tagged: Box<[A]> = self
+			.attributes
+			.iter()
+			.map(|(id, _)| id)
+			.filter(|id| supplier.has_tag(id, tag))
+			.cloned()
 			.collect();
+		self.unlogged(|map| {
+			for id in tagged {
+				map.add_modifier(&id, modifier.clone(), instance.clone());
+			}
+		});
 
-		for id in v {
-			self.mark_dependents_dirty(&id);
+		self.tag_modifiers.push((tag.clone(), modifier, instance));
+		self.materialise_tagged();
+		self.discard_journal();
+		self
+	}
+
+	/// The materialised or supplier-known attributes carrying `tag`.
+	pub fn tagged<'a>(&'a self, tag: &'a Tag) -> impl Iterator<Item = &'a A> {
+		self.keys()
+			.filter(move |id| self.supplier.as_ref().is_some_and(|s| s.has_tag(id, tag)))
+	}
+
+	/// Removes the runtime modifiers keyed `modifier` from every attribute, together with their
+	/// timer, any [tag modifier](Self::add_tag_modifier) and any override of a discrete
+	/// attribute. Template modifiers sharing the key are left in place.
+	pub fn remove_modifiers(&mut self, modifier: &M) {
+		self.log(|| AttributeEvent::RemoveModifiers(modifier.clone()));
+		let timers = journal::extract(&mut self.timers, |(m, _)| modifier.eq(m));
+		let tag_modifiers = journal::extract(&mut self.tag_modifiers, |(_, m, _)| modifier.eq(m));
+
+		let mut edits = Vec::new();
+		for (id, attr) in self.attributes.iter_mut() {
+			let removed = attr.take_modifiers(modifier, ModifierOrigin::Runtime);
+			if !removed.is_empty() {
+				edits.push(Edit::RemoveModifiers {
+					attribute: id.clone(),
+					removed,
+				});
+			}
+		}
+
+		for (id, instance) in &mut self.discrete {
+			let removed = instance.take_overrides(modifier);
+			if !removed.is_empty() {
+				edits.push(Edit::RemoveOverrides {
+					attribute: id.clone(),
+					removed,
+				});
+			}
+		}
+
+		for edit in &edits {
+			if let Edit::RemoveModifiers { attribute, .. }
+			| Edit::RemoveOverrides { attribute, .. } = edit
+			{
+				self.mark_dependents_dirty(attribute);
+			}
 		}
+
+		if !timers.is_empty() {
+			edits.push(Edit::Timers(timers));
+		}
+		if !tag_modifiers.is_empty() {
+			edits.push(Edit::TagModifiers(tag_modifiers));
+		}
+		if !edits.is_empty()
+			&& let Some(journal) = &mut self.journal
+		{
+			journal.undo.push(edits);
+			journal.redo.clear();
+		}
+	}
+
+	/// Removes every runtime modifier of `attribute`, or every override of the discrete
+	/// `attribute`.
+	pub fn clear_runtime_modifiers(&mut self, attribute: &A) {
+		self.log(|| AttributeEvent::ClearRuntimeModifiers(attribute.clone()));
+		let cleared = self
+			.attributes
+			.get_mut(attribute)
+			.is_some_and(AttributeInstance::clear_runtime_modifiers);
+		let overrides = self
+			.discrete
+			.get_mut(attribute)
+			.is_some_and(DiscreteInstance::clear_overrides);
+		if cleared || overrides {
+			self.mark_dependents_dirty(attribute);
+			self.discard_journal();
+		}
+	}
+
+	/// Resets `attribute` to its template, dropping its runtime modifiers and raw value. Tag
+	/// modifiers applying to it are kept. A discrete `attribute` returns to its initial state
+	/// without overrides.
+	pub fn reset(&mut self, attribute: &A) {
+		self.log(|| AttributeEvent::Reset(attribute.clone()));
+		let template = self.instantiate(attribute);
+		let discrete = self.discrete.remove(attribute).is_some();
+		let Some(attr) = self.attributes.get_mut(attribute) else {
+			if discrete {
+				self.mark_dependents_dirty(attribute);
+				self.discard_journal();
+			}
+			return;
+		};
+		match template {
+			Some(template) => *attr = template,
+			None => attr.reset(),
+		}
+		self.mark_dependents_dirty(attribute);
+		self.discard_journal();
+	}
+
+	pub fn instance(&self, attribute: &A) -> Option<&AttributeInstance<A, M, V, O>> {
+		self.attributes.get(attribute)
 	}
 
 	pub fn set_raw_value(&mut self, attribute: &A, value: V) {
-		if let Some(attr) = self.get_mut(attribute.clone()) {
+		self.log(|| AttributeEvent::SetRawValue {
+			attribute: attribute.clone(),
+			value,
+		});
+		let journaling = self.journal.is_some();
+		if let Some((attr, materialise)) = self.get_mut(attribute) {
+			let from = attr.raw_value();
+			let from_set = attr.is_raw_value_set();
 			attr.set_raw_value(value);
+			if journaling {
+				let set = Edit::SetRawValue {
+					attribute: attribute.clone(),
+					from,
+					from_set,
+					to: value,
+				};
+				self.record(attribute, materialise, vec![set]);
+			}
+			self.mark_dependents_dirty(attribute);
+		}
+	}
+
+	/// The state of the [discrete](crate::attribute::discrete) `attribute`, after overrides.
+	pub fn state(&self, attribute: &A) -> Option<&Tag> {
+		match self.discrete.get(attribute) {
+			Some(instance) => Some(instance.state()),
+			None => self.supplier.as_ref()?.discrete_state(attribute),
+		}
+	}
+
+	/// The state of the discrete `attribute` before overrides.
+	pub fn raw_state(&self, attribute: &A) -> Option<&Tag> {
+		match self.discrete.get(attribute) {
+			Some(instance) => Some(instance.raw_state()),
+			None => self.supplier.as_ref()?.discrete_state(attribute),
+		}
+	}
+
+	/// Puts the discrete `attribute` into `state`, underneath any overrides.
+	pub fn set_raw_state<T: Into<Tag>>(&mut self, attribute: &A, state: T) {
+		let state = state.into();
+		self.log(|| AttributeEvent::SetRawState {
+			attribute: attribute.clone(),
+			state: state.clone(),
+		});
+		let journaling = self.journal.is_some();
+		if let Some((instance, materialise)) = self.discrete_mut(attribute) {
+			if journaling {
+				let from_set = instance.is_raw_state_set();
+				let from = instance.set_raw_state(state.clone());
+				let set = Edit::SetRawState {
+					attribute: attribute.clone(),
+					from,
+					from_set,
+					to: state,
+				};
+				self.record_state(attribute, materialise, set);
+			} else {
+				instance.set_raw_state(state);
+			}
 			self.mark_dependents_dirty(attribute);
 		}
 	}
 
+	/// Overrides the state of the discrete `attribute` with `state` until the modifiers keyed
+	/// `modifier` are removed. Of several overrides the one with the highest `priority` wins, and
+	/// of equal ones the last.
+	pub fn override_state<T: Into<Tag>>(
+		&mut self,
+		attribute: &A,
+		modifier: M,
+		priority: i32,
+		state: T,
+	) -> &mut Self {
+		let state = state.into();
+		self.log(|| AttributeEvent::OverrideState {
+			attribute: attribute.clone(),
+			key: modifier.clone(),
+			priority,
+			state: state.clone(),
+		});
+		let journaling = self.journal.is_some();
+		if let Some((instance, materialise)) = self.discrete_mut(attribute) {
+			if journaling {
+				instance.add_override(modifier.clone(), priority, state.clone());
+				let add = Edit::AddOverride {
+					attribute: attribute.clone(),
+					key: modifier,
+					priority,
+					state,
+				};
+				self.record_state(attribute, materialise, add);
+			} else {
+				instance.add_override(modifier, priority, state);
+			}
+			self.mark_dependents_dirty(attribute);
+		}
+		self
+	}
+
+	/// The materialised discrete `attribute`, with its overrides.
+	pub fn discrete(&self, attribute: &A) -> Option<&DiscreteInstance<M>> {
+		self.discrete.get(attribute)
+	}
+
+	/// Every materialised discrete attribute.
+	pub fn discrete_instances(&self) -> impl Iterator<Item = (&A, &DiscreteInstance<M>)> {
+		self.discrete.iter()
+	}
+
+	/// Starts recording [`set_raw_value`](Self::set_raw_value),
+	/// [`add_modifier`](Self::add_modifier), [`remove_modifier`](Self::remove_modifier),
+	/// [`remove_modifiers`](Self::remove_modifiers), [`set_raw_state`](Self::set_raw_state) and
+	/// [`override_state`](Self::override_state) so they can be [undone](Self::undo).
+	///
+	/// Other mutations, like [`rebase`](Self::rebase) or [`reset`](Self::reset), cannot be undone
+	/// and clear the recorded history.
+	pub fn enable_journal(&mut self) {
+		self.log(|| AttributeEvent::EnableJournal);
+		self.journal.get_or_insert_with(Journal::default);
+	}
+
+	/// Stops recording and drops the recorded history.
+	pub fn disable_journal(&mut self) {
+		self.log(|| AttributeEvent::DisableJournal);
+		self.journal = None;
+	}
+
+	pub fn can_undo(&self) -> bool {
+		self.journal.as_ref().is_some_and(|j| !j.undo.is_empty())
+	}
+
+	pub fn can_redo(&self) -> bool {
+		self.journal.as_ref().is_some_and(|j| !j.redo.is_empty())
+	}
+
+	/// Reverts the last recorded operation, restoring raw values and modifier positions exactly.
+	/// Returns whether there was one.
+	pub fn undo(&mut self) -> bool {
+		let Some(edits) = self.journal.as_mut().and_then(|j| j.undo.pop()) else {
+			return false;
+		};
+		for edit in edits.iter().rev() {
+			self.apply_edit(edit, false);
+		}
+		if let Some(journal) = &mut self.journal {
+			journal.redo.push(edits);
+		}
+		self.log(|| AttributeEvent::Undo);
+		true
+	}
+
+	/// Repeats the last undone operation. Returns whether there was one.
+	pub fn redo(&mut self) -> bool {
+		let Some(edits) = self.journal.as_mut().and_then(|j| j.redo.pop()) else {
+			return false;
+		};
+		for edit in &edits {
+			self.apply_edit(edit, true);
+		}
+		if let Some(journal) = &mut self.journal {
+			journal.undo.push(edits);
+		}
+		self.log(|| AttributeEvent::Redo);
+		true
+	}
+
+	/// Removes every modifier keyed `modifier` once `duration` has elapsed in [`tick`](Self::tick).
+	///
+	/// Replaces any timer already running for `modifier`.
+	pub fn expire_modifiers(&mut self, modifier: M, duration: Duration) {
+		self.log(|| AttributeEvent::ExpireModifiers {
+			key: modifier.clone(),
+			duration,
+		});
+		let from = self.journal.is_some().then(|| self.timers.clone());
+		match self.timers.iter_mut().find(|(m, _)| modifier.eq(m)) {
+			Some((_, remaining)) => *remaining = duration,
+			None => self.timers.push((modifier, duration)),
+		}
+		if let Some(from) = from
+			&& let Some(journal) = &mut self.journal
+		{
+			let to = self.timers.clone();
+			journal.undo.push(vec![Edit::SetTimers { from, to }]);
+			journal.redo.clear();
+		}
+	}
+
+	pub fn remaining(&self, modifier: &M) -> Option<Duration> {
+		self.timers
+			.iter()
+			.find(|(m, _)| modifier.eq(m))
+			.map(|(_, remaining)| *remaining)
+	}
+
+	/// Advances all modifier timers by `elapsed`, removing and returning the modifiers that ran out.
+	pub fn tick(&mut self, elapsed: Duration) -> Vec<M> {
+		self.log(|| AttributeEvent::Tick(elapsed));
+		if self.timers.is_empty() {
+			return Vec::new();
+		}
+		let from = self.journal.is_some().then(|| self.timers.clone());
+		let mut expired = Vec::new();
+		self.timers.retain_mut(|(modifier, remaining)| {
+			*remaining = remaining.saturating_sub(elapsed);
+			if remaining.is_zero() {
+				expired.push(modifier.clone());
+				false
+			} else {
+				true
+			}
+		});
+
+		let edits = from
+			.map(|from| {
+				let to = self.timers.clone();
+				vec![Edit::SetTimers { from, to }]
+			})
+			.unwrap_or_default();
+		self.compound(edits, |map| {
+			map.unlogged(|map| {
+				for modifier in &expired {
+					map.remove_modifiers(modifier);
+				}
+			});
+		});
+		expired
+	}
+
+	pub fn raw_value(&self, attribute: &A) -> Option<V> {
+		self.attributes
+			.get(attribute)
+			.map(AttributeInstance::raw_value)
+			.or_else(|| self.supplier.as_ref().and_then(|s| s.raw_value(attribute)))
+	}
+
 	pub fn value(&self, attribute: &A) -> Option<V> {
 		self.attributes
 			.get(attribute)
@@ -126,56 +674,577 @@ where
 			})
 	}
 
-	fn mark_dependents_dirty(&self, id: &A) {
-		for (id, attr) in self
+	/// The kind and range of `attribute`, as materialised or declared by the supplier.
+	pub fn definition(&self, attribute: &A) -> Option<Attribute<V>> {
+		match self.attributes.get(attribute) {
+			Some(attr) => Some(attr.attribute().clone()),
+			None => self
+				.supplier
+				.as_ref()?
+				.create_instance(attribute)
+				.map(|attr| attr.attribute().clone()),
+		}
+	}
+
+	/// The steps that lead to the current [`value`](Self::value) of `attribute`.
+	pub fn breakdown(&self, attribute: &A) -> Option<Breakdown<M, V>> {
+		match self.attributes.get(attribute) {
+			Some(attr) => Some(attr.breakdown(self)),
+			None => Some(self.instantiate(attribute)?.breakdown(self)),
+		}
+	}
+
+	/// Every supplier-declared attribute in declaration order, then materialised ones the
+	/// supplier doesn't declare.
+	pub fn keys(&self) -> impl Iterator<Item = &A> {
+		let undeclared = self
 			.attributes
 			.iter()
+			.map(|(id, _)| id)
+			.filter(|id| !self.supplier.as_ref().is_some_and(|s| s.has_attribute(id)));
+
+		self.supplier
+			.iter()
+			.flat_map(|s| s.keys())
+			.chain(undeclared)
+	}
+
+	/// The current value of every attribute in [`keys`](Self::keys).
+	pub fn iter(&self) -> impl Iterator<Item = (&A, V)> {
+		self.keys().filter_map(|id| Some((id, self.value(id)?)))
+	}
+
+	/// Every materialised attribute instance.
+	pub fn instances(&self) -> impl Iterator<Item = (&A, &AttributeInstance<A, M, V, O>)> {
+		self.attributes.iter()
+	}
+
+	/// The modifiers of `attribute`, in the order they are applied. Attributes that were never
+	/// materialised yield the template modifiers of their supplier.
+	pub fn modifiers(
+		&self,
+		attribute: &A,
+	) -> impl Iterator<Item = (&M, &AttributeModifier<A, V, O>, ModifierOrigin)> {
+		self.attributes
+			.get(attribute)
+			.or_else(|| self.supplier.as_ref()?.resolve(attribute))
+			.into_iter()
+			.flat_map(AttributeInstance::modifiers)
+	}
+
+	/// The materialised attributes carrying a modifier keyed `modifier`.
+	pub fn with_modifier<'a>(&'a self, modifier: &'a M) -> impl Iterator<Item = &'a A> {
+		self.attributes
+			.iter()
+			.filter(|(_, attr)| attr.has_modifier(modifier))
+			.map(|(id, _)| id)
+	}
+
+	/// The current value of every materialised or supplier-known attribute.
+	pub fn snapshot(&self) -> HashMap<A, V> {
+		self.iter().map(|(id, value)| (id.clone(), value)).collect()
+	}
+
+	/// Starts appending every mutation to an [`EventLog`], from which
+	/// [`replay`](Self::replay) can rebuild this map. Returns whether the map is recording.
+	///
+	/// A replay starts from a fresh map, so recording only starts on a map that was not changed
+	/// yet; a map already recording keeps its log.
+	pub fn record_events(&mut self) -> bool {
+		if self.events.is_none() && !self.is_pristine() {
+			return false;
+		}
+		self.events.get_or_insert_with(EventLog::new);
+		true
+	}
+
+	/// Whether the map is still as [`new`](Self::new) made it.
+	fn is_pristine(&self) -> bool {
+		self.attributes.is_empty()
+			&& self.discrete.is_empty()
+			&& self.timers.is_empty()
+			&& self.tag_modifiers.is_empty()
+			&& self.history.is_empty()
+			&& self.journal.is_none()
+	}
+
+	/// The mutations recorded so far, if [recording](Self::record_events).
+	pub fn events(&self) -> Option<&EventLog<A, M, V, O>> {
+		self.events.as_ref()
+	}
+
+	/// Stops recording, returning the recorded mutations.
+	pub fn take_events(&mut self) -> Option<EventLog<A, M, V, O>> {
+		self.events.take()
+	}
+
+	fn log(&mut self, event: impl FnOnce() -> AttributeEvent<A, M, V, O>) {
+		if let Some(events) = &mut self.events {
+			events.push(event());
+		}
+	}
+
+	/// Runs `f` without recording the mutations it makes, which the caller records as one event.
+	fn unlogged<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+		let events = self.events.take();
+		let result = f(self);
+		self.events = events;
+		result
+	}
+
+	/// Pays for and makes `advancement`, from the currency declared by the supplier. An
+	/// advancement the currency cannot pay, e.g. one replayed onto another state, is skipped.
+	pub(crate) fn apply_advancement(&mut self, advancement: Advancement<A, V>) {
+		self.log(|| AttributeEvent::Advance(advancement.clone()));
+		let currency = self.supplier.as_ref().and_then(|s| s.currency()).cloned();
+		let available = currency.as_ref().map(|currency| {
+			let available = self.raw_value(currency).unwrap_or_default();
+			// Both checks together keep the subtraction from overflowing.
+			(advancement.cost >= V::default() && available >= advancement.cost)
+				.then(|| available - advancement.cost)
+		});
+		if available == Some(None) {
+			return;
+		}
+		let edits = if self.journal.is_some() {
+			vec![Edit::Advance(advancement.clone())]
+		} else {
+			Vec::new()
+		};
+		self.compound(edits, |map| {
+			map.unlogged(|map| {
+				if let (Some(currency), Some(Some(left))) = (currency, available) {
+					map.set_raw_value(&currency, left);
+				}
+				map.set_raw_value(&advancement.attribute, advancement.to);
+			});
+		});
+		self.history.push(advancement);
+	}
+
+	/// Runs `f`, folding everything it journals into one entry after `edits`, so a single
+	/// [`undo`](Self::undo) reverts the whole operation.
+	fn compound(&mut self, edits: Vec<Edit<A, M, V, O>>, f: impl FnOnce(&mut Self)) {
+		let mark = self.journal.as_ref().map(|journal| journal.undo.len());
+		f(self);
+		if let (Some(mark), Some(journal)) = (mark, &mut self.journal) {
+			let mark = mark.min(journal.undo.len());
+			let mut entry = edits;
+			entry.extend(journal.undo.drain(mark..).flatten());
+			if !entry.is_empty() {
+				journal.undo.push(entry);
+				journal.redo.clear();
+			}
+		}
+	}
+
+	/// Records the edits of one operation on `attribute`, which it `materialised` first.
+	fn record(&mut self, attribute: &A, materialised: bool, mut edits: Vec<Edit<A, M, V, O>>) {
+		let Some(journal) = &mut self.journal else {
+			return;
+		};
+		if materialised {
+			edits.insert(0, Edit::Materialise(attribute.clone()));
+		}
+		journal.undo.push(edits);
+		journal.redo.clear();
+	}
+
+	/// Records an edit of the discrete `attribute`, which it `materialised` first.
+	fn record_state(&mut self, attribute: &A, materialised: bool, edit: Edit<A, M, V, O>) {
+		let Some(journal) = &mut self.journal else {
+			return;
+		};
+		let mut edits = vec![edit];
+		if materialised {
+			edits.insert(0, Edit::MaterialiseState(attribute.clone()));
+		}
+		journal.undo.push(edits);
+		journal.redo.clear();
+	}
+
+	/// Undoes recorded operations until `depth` are left, without logging them or keeping them
+	/// for a redo.
+	pub(crate) fn rewind(&mut self, depth: usize) {
+		while let Some(edits) = self
+			.journal
+			.as_mut()
+			.filter(|journal| journal.undo.len() > depth)
+			.and_then(|journal| journal.undo.pop())
+		{
+			for edit in edits.iter().rev() {
+				self.apply_edit(edit, false);
+			}
+		}
+		if let Some(journal) = &mut self.journal {
+			journal.redo.clear();
+		}
+	}
+
+	pub(crate) fn discard_journal(&mut self) {
+		if let Some(journal) = &mut self.journal {
+			*journal = Journal::default();
+		}
+	}
+
+	/// Applies `edit`, or reverts it unless `forward`.
+	fn apply_edit(&mut self, edit: &Edit<A, M, V, O>, forward: bool) {
+		match edit {
+			Edit::Materialise(attribute) => {
+				if forward {
+					self.get_mut(attribute);
+				} else {
+					self.attributes.remove(attribute);
+				}
+				self.mark_dependents_dirty(attribute);
+			}
+			Edit::SetRawValue {
+				attribute,
+				from,
+				from_set,
+				to,
+			} => {
+				if let Some(attr) = self.attributes.get_mut(attribute) {
+					if forward {
+						attr.set_raw_value(*to);
+					} else {
+						attr.restore_raw_value(*from, *from_set);
+					}
+					self.mark_dependents_dirty(attribute);
+				}
+			}
+			Edit::AddModifier {
+				attribute,
+				key,
+				modifier,
+			} => {
+				if let Some(attr) = self.attributes.get_mut(attribute) {
+					if forward {
+						attr.add_modifier(key.clone(), modifier.clone());
+					} else {
+						attr.pop_modifier();
+					}
+					self.mark_dependents_dirty(attribute);
+				}
+			}
+			Edit::RemoveModifiers { attribute, removed } => {
+				if let Some(attr) = self.attributes.get_mut(attribute) {
+					if forward {
+						attr.retake_modifiers(removed);
+					} else {
+						attr.restore_modifiers(removed);
+					}
+					self.mark_dependents_dirty(attribute);
+				}
+			}
+			Edit::Timers(removed) => {
+				if forward {
+					journal::remove_at(&mut self.timers, removed);
+				} else {
+					journal::restore(&mut self.timers, removed);
+				}
+			}
+			Edit::TagModifiers(removed) => {
+				if forward {
+					journal::remove_at(&mut self.tag_modifiers, removed);
+				} else {
+					journal::restore(&mut self.tag_modifiers, removed);
+				}
+			}
+			Edit::SetTimers { from, to } => {
+				self.timers.clone_from(if forward { to } else { from });
+			}
+			Edit::Advance(advancement) => {
+				if forward {
+					self.history.push(advancement.clone());
+				} else {
+					self.history.pop();
+				}
+			}
+			Edit::MaterialiseState(_)
+			| Edit::SetRawState { .. }
+			| Edit::AddOverride { .. }
+			| Edit::RemoveOverrides { .. } => self.apply_state_edit(edit, forward),
+		}
+	}
+
+	/// Applies or reverts an `edit` of a discrete attribute.
+	fn apply_state_edit(&mut self, edit: &Edit<A, M, V, O>, forward: bool) {
+		match edit {
+			Edit::MaterialiseState(attribute) => {
+				if forward {
+					self.discrete_mut(attribute);
+				} else {
+					self.discrete.remove(attribute);
+				}
+				self.mark_dependents_dirty(attribute);
+			}
+			Edit::SetRawState {
+				attribute,
+				from,
+				from_set,
+				to,
+			} => {
+				if let Some(instance) = self.discrete.get_mut(attribute) {
+					if forward {
+						instance.set_raw_state(to.clone());
+					} else {
+						instance.restore_raw_state(from.clone(), *from_set);
+					}
+					self.mark_dependents_dirty(attribute);
+				}
+			}
+			Edit::AddOverride {
+				attribute,
+				key,
+				priority,
+				state,
+			} => {
+				if let Some(instance) = self.discrete.get_mut(attribute) {
+					if forward {
+						instance.add_override(key.clone(), *priority, state.clone());
+					} else {
+						instance.pop_override();
+					}
+					self.mark_dependents_dirty(attribute);
+				}
+			}
+			Edit::RemoveOverrides { attribute, removed } => {
+				if let Some(instance) = self.discrete.get_mut(attribute) {
+					if forward {
+						instance.retake_overrides(removed);
+					} else {
+						instance.restore_overrides(removed);
+					}
+					self.mark_dependents_dirty(attribute);
+				}
+			}
+			// Edits of numeric attributes are applied by `apply_edit`.
+			_ => {}
+		}
+	}
+
+	/// Notes what `id` reads after a change to it, then invalidates what reads `id`.
+	fn mark_dependents_dirty(&mut self, id: &A) {
+		if let Some(attr) = self.attributes.get(id) {
+			let sources: Vec<A> = attr.sources().cloned().collect();
+			self.attributes.link(id, &sources);
+		}
+		self.invalidate_dependents(id);
+	}
+
+	fn invalidate_dependents(&self, id: &A) {
+		for (id, attr) in self
+			.attributes
+			.dependents(id)
 			.filter(|(_, attr)| attr.depends_on(id))
 		{
 			attr.mark_dirty();
-			self.mark_dependents_dirty(id);
+			self.invalidate_dependents(id);
 		}
 	}
 
-	fn get_mut(&mut self, attribute: A) -> Option<&mut AttributeInstance<A, M, V, O>> {
-		let entry = self.attributes.entry(attribute);
+	/// Notes what every instance reads, after instances changed wholesale.
+	fn link_all(&mut self) {
+		let links: Vec<(A, Vec<A>)> = self
+			.attributes
+			.iter()
+			.map(|(id, attr)| (id.clone(), attr.sources().cloned().collect()))
+			.collect();
+		for (id, sources) in &links {
+			self.attributes.link(id, sources);
+		}
+	}
 
-		match entry {
-			Entry::Occupied(e) => {
-				let attr = e.into_mut();
-				attr.mark_dirty();
-				Some(attr)
+	/// `attribute`, materialised from the supplier if needed, and whether it was.
+	fn get_mut(&mut self, attribute: &A) -> Materialised<'_, AttributeInstance<A, M, V, O>> {
+		let (attr, materialised) = self.attributes.get_or_insert_with(attribute, || {
+			Self::instantiate_from(self.supplier.as_deref(), &self.tag_modifiers, attribute)
+		})?;
+		if !materialised {
+			attr.mark_dirty();
+		}
+		Some((attr, materialised))
+	}
+
+	/// The discrete `attribute`, materialised in the supplier's initial state if needed, and
+	/// whether it was.
+	fn discrete_mut(&mut self, attribute: &A) -> Materialised<'_, DiscreteInstance<M>> {
+		match self.discrete.entry(attribute.clone()) {
+			Entry::Occupied(e) => Some((e.into_mut(), false)),
+			Entry::Vacant(e) => {
+				let state = self.supplier.as_ref()?.discrete_state(attribute)?.clone();
+				Some((e.insert(DiscreteInstance::new(state)), true))
 			}
-			Entry::Vacant(e) => self
-				.supplier
-				.as_ref()
-				.and_then(|s| s.create_instance(e.key()))
-				.map(|attr| e.insert(attr)),
 		}
 	}
+
+	/// Materialises `attribute` if needed and lets `f` change it, invalidating what depends on it.
+	#[cfg(feature = "replication")]
+	pub(crate) fn patch(
+		&mut self,
+		attribute: &A,
+		f: impl FnOnce(&mut AttributeInstance<A, M, V, O>),
+	) {
+		if let Some((attr, _)) = self.get_mut(attribute) {
+			f(attr);
+			self.mark_dependents_dirty(attribute);
+		}
+	}
+
+	/// Replaces the discrete `attribute` with `instance`, invalidating what depends on it.
+	#[cfg(feature = "replication")]
+	pub(crate) fn put_discrete(&mut self, attribute: A, instance: DiscreteInstance<M>) {
+		self.mark_dependents_dirty(&attribute);
+		self.discrete.insert(attribute, instance);
+	}
+
+	/// Drops the instance of `attribute`, so it falls back to the supplier's.
+	#[cfg(feature = "replication")]
+	pub(crate) fn dematerialise(&mut self, attribute: &A) {
+		let numeric = self.attributes.remove(attribute).is_some();
+		let discrete = self.discrete.remove(attribute).is_some();
+		if numeric || discrete {
+			self.mark_dependents_dirty(attribute);
+		}
+	}
+
+	/// A fresh instance of `attribute` from the supplier, with the tag modifiers applying to it.
+	fn instantiate(&self, attribute: &A) -> Option<AttributeInstance<A, M, V, O>> {
+		Self::instantiate_from(self.supplier.as_deref(), &self.tag_modifiers, attribute)
+	}
+
+	fn instantiate_from(
+		supplier: Option<&AttributeSupplier<A, M, V, O>>,
+		tag_modifiers: &[(Tag, M, AttributeModifier<A, V, O>)],
+		attribute: &A,
+	) -> Option<AttributeInstance<A, M, V, O>> {
+		let supplier = supplier?;
+		let mut instance = supplier.create_instance(attribute)?;
+		for (tag, key, modifier) in tag_modifiers {
+			if supplier.has_tag(attribute, tag) {
+				instance.add_modifier(key.clone(), modifier.clone());
+			}
+		}
+		Some(instance)
+	}
+
+	/// Materialises every supplier-known attribute a tag modifier applies to, so that it takes
+	/// effect.
+	fn materialise_tagged(&mut self) {
+		let Some(supplier) = &self.supplier else {
+			return;
+		};
+
+		let pending: Box<[A]> = supplier
+			.keys()
+			.into_iter()
+			.filter(|id| !self.attributes.contains_key(id))
+			.filter(|id| {
+				self.tag_modifiers
+					.iter()
+					.any(|(tag, _, _)| supplier.has_tag(id, tag))
+			})
+			.cloned()
+			.collect();
+		for id in pending {
+			self.get_mut(&id);
+			self.mark_dependents_dirty(&id);
+		}
+	}
+}
+
+impl<A, M, V, O, S> AttributeMap<A, M, V, O, S>
+where
+	A: Key + Hash,
+	M: Key,
+	V: Arithmetic,
+	O: Op<V>,
+	S: AttributeStorage<A, AttributeInstance<A, M, V, O>>,
+{
+	/// Raises the raw value of `attribute` to `target`, paying its cost from the currency
+	/// attribute, and records the change in the [`history`](Self::history). Returns the cost.
+	///
+	/// # Errors
+	///
+	/// Fails without changing anything if the supplier declares no cost curve for `attribute` or
+	/// no currency, if `target` is not an increase or lies above the attribute's range, or if the
+	/// currency's raw value cannot cover the cost.
+	pub fn advance(&mut self, attribute: &A, target: V) -> Result<V, AdvanceError<A, V>> {
+		let not_advanceable = || AdvanceError::NotAdvanceable(attribute.clone());
+		let supplier = self.supplier.as_ref().ok_or_else(not_advanceable)?;
+		let curve = supplier.cost(attribute).ok_or_else(not_advanceable)?;
+		let currency = supplier.currency().ok_or(AdvanceError::NoCurrency)?.clone();
+
+		let current = self.raw_value(attribute).ok_or_else(not_advanceable)?;
+		if target <= current {
+			return Err(AdvanceError::NotAnIncrease { current, target });
+		}
+		if let Some(Attribute::Ranged(_, _, max)) = self.definition(attribute)
+			&& target > max
+		{
+			return Err(AdvanceError::OutOfRange { target, max });
+		}
+
+		let cost = curve.cost(current, target).map_err(AdvanceError::Cost)?;
+		let available = self.raw_value(&currency).ok_or(AdvanceError::NoCurrency)?;
+		if available < cost {
+			return Err(AdvanceError::InsufficientCurrency { cost, available });
+		}
+
+		self.apply_advancement(Advancement {
+			attribute: attribute.clone(),
+			from: current,
+			to: target,
+			cost,
+		});
+		Ok(cost)
+	}
+}
+
+impl<A, M, V, O, S> AttributeMap<A, M, V, O, S>
+where
+	A: Key + Hash,
+	M: Key,
+	V: Number + 'static,
+	O: Op<V>,
+	S: AttributeStorage<A, AttributeInstance<A, M, V, O>>,
+{
+	/// Every [`advance`](Self::advance) made, oldest first.
+	pub fn history(&self) -> &[Advancement<A, V>] {
+		&self.history
+	}
 }
 
-impl<A, M, V, O> Default for AttributeMap<A, M, V, O>
+impl<A, M, V, O, S> Default for AttributeMap<A, M, V, O, S>
 where
 	A: Key + Hash,
 	M: Key,
 	V: Number + 'static,
 	O: Op<V>,
+	S: AttributeStorage<A, AttributeInstance<A, M, V, O>>,
 {
 	fn default() -> Self {
 		AttributeMap {
 			supplier: None,
-			attributes: HashMap::new(),
+			attributes: S::default(),
+			discrete: HashMap::new(),
+			timers: Vec::new(),
+			tag_modifiers: Vec::new(),
+			history: Vec::new(),
+			journal: None,
+			events: None,
 		}
 	}
 }
 
 #[cfg(test)]
 mod tests {
-	use once_cell::sync::Lazy;
-
 	use super::*;
-	use crate::prelude::{Attribute, Operation};
+	use crate::{
+		attribute::{instance::BreakdownStep, modifier::Condition, storage::AttributeIndex},
+		prelude::{Attribute, Operation, Value},
+	};
 
 	#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 	#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -184,16 +1253,28 @@ mod tests {
 		Agility,
 	}
 
+	impl AttributeIndex for TestAttribute {
+		const COUNT: usize = 2;
+
+		fn index(&self) -> usize {
+			match self {
+				Self::Strength => 0,
+				Self::Agility => 1,
+			}
+		}
+	}
+
 	#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 	#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 	enum TestModifier {
 		Buff,
+		Rage,
 	}
 
 	type MockSupplier = AttributeSupplier<TestAttribute, TestModifier, f32>;
 	type MockMap = AttributeMap<TestAttribute, TestModifier, f32>;
 
-	static ATTRIBUTES: Lazy<Arc<MockSupplier>> = Lazy::new(|| {
+	fn mock_supplier() -> Arc<MockSupplier> {
 		Arc::new(
 			MockSupplier::builder()
 				.add(
@@ -206,11 +1287,11 @@ mod tests {
 				.add(TestAttribute::Agility, Attribute::Value(2.0))
 				.build(),
 		)
-	});
+	}
 
 	#[test]
 	fn test_new() {
-		let map: MockMap = AttributeMap::new(ATTRIBUTES.clone());
+		let map: MockMap = AttributeMap::new(mock_supplier());
 		assert!(map.supplier.is_some());
 		assert!(map.attributes.is_empty());
 	}
@@ -224,7 +1305,7 @@ mod tests {
 
 	#[test]
 	fn test_has_attribute() {
-		let mut map: MockMap = AttributeMap::new(ATTRIBUTES.clone());
+		let mut map: MockMap = AttributeMap::new(mock_supplier());
 		let attr = TestAttribute::Strength;
 
 		assert!(!map.has_attribute(&attr));
@@ -234,9 +1315,9 @@ mod tests {
 
 	#[test]
 	fn test_add_and_remove_modifier() {
-		let mut map: MockMap = AttributeMap::new(ATTRIBUTES.clone());
+		let mut map: MockMap = AttributeMap::new(mock_supplier());
 		let attr = TestAttribute::Strength;
-		let modifier = TestModifier::Buff;
+		let modifier = TestModifier::Rage;
 		let mod_instance = AttributeModifier::new(5.0, Operation::Add);
 
 		assert!(!map.has_modifier(&attr, &modifier));
@@ -250,7 +1331,7 @@ mod tests {
 
 	#[test]
 	fn test_set_raw_value() {
-		let mut map: MockMap = AttributeMap::new(ATTRIBUTES.clone());
+		let mut map: MockMap = AttributeMap::new(mock_supplier());
 		let attr = TestAttribute::Agility;
 
 		map.set_raw_value(&attr, 15.0);
@@ -259,7 +1340,7 @@ mod tests {
 
 	#[test]
 	fn test_value_computation() {
-		let map: MockMap = AttributeMap::new(ATTRIBUTES.clone());
+		let map: MockMap = AttributeMap::new(mock_supplier());
 
 		let value = map.value(&TestAttribute::Strength);
 		assert_eq!(value, Some(2.0));
@@ -270,10 +1351,10 @@ mod tests {
 
 	#[test]
 	fn test_remove_modifiers() {
-		let mut map: MockMap = AttributeMap::new(ATTRIBUTES.clone());
+		let mut map: MockMap = AttributeMap::new(mock_supplier());
 		let attr1 = TestAttribute::Strength;
 		let attr2 = TestAttribute::Agility;
-		let modifier = TestModifier::Buff;
+		let modifier = TestModifier::Rage;
 		let mod_instance = AttributeModifier::new(5.0, Operation::Add);
 
 		map.add_modifier(&attr1, modifier.clone(), mod_instance.clone());
@@ -287,4 +1368,536 @@ mod tests {
 		assert!(!map.has_modifier(&attr1, &modifier));
 		assert!(!map.has_modifier(&attr2, &modifier));
 	}
+
+	#[test]
+	fn test_dense_storage() {
+		let mut map: DenseAttributeMap<TestAttribute, TestModifier, f32> =
+			AttributeMap::new(mock_supplier());
+		let attr = TestAttribute::Agility;
+
+		assert!(!map.has_attribute(&attr));
+		assert_eq!(map.value(&TestAttribute::Strength), Some(2.0));
+
+		map.add_modifier(
+			&attr,
+			TestModifier::Buff,
+			AttributeModifier::new(3.0, Operation::Add),
+		);
+		assert!(map.has_attribute(&attr));
+		assert_eq!(map.value(&attr), Some(5.0));
+
+		map.remove_modifiers(&TestModifier::Buff);
+		assert_eq!(map.value(&attr), Some(2.0));
+
+		// A change reaches what reads the attribute through the storage's links.
+		map.add_modifier(
+			&TestAttribute::Strength,
+			TestModifier::Rage,
+			AttributeModifier::new(Value::Attribute(attr.clone()), Operation::Add),
+		);
+		assert_eq!(map.value(&TestAttribute::Strength), Some(4.0));
+		map.set_raw_value(&attr, 5.0);
+		assert_eq!(map.value(&TestAttribute::Strength), Some(7.0));
+	}
+
+	#[test]
+	fn test_rebase() {
+		let mut map: MockMap = AttributeMap::new(mock_supplier());
+		map.add_modifier(
+			&TestAttribute::Strength,
+			TestModifier::Rage,
+			AttributeModifier::new(5.0, Operation::Add),
+		);
+		map.set_raw_value(&TestAttribute::Agility, 4.0);
+		assert_eq!(map.value(&TestAttribute::Strength), Some(7.0));
+
+		let reloaded = Arc::new(
+			MockSupplier::builder()
+				.add(
+					TestAttribute::Strength,
+					AttributeInstance::builder(Attribute::Ranged(3.0, 0.0, 10.0)).modifier(
+						TestModifier::Buff,
+						AttributeModifier::new(2.0, Operation::Add),
+					),
+				)
+				.add(TestAttribute::Agility, Attribute::Value(1.0))
+				.build(),
+		);
+		map.rebase(reloaded);
+
+		// New default, range and template modifier, runtime modifier kept.
+		assert_eq!(map.raw_value(&TestAttribute::Strength), Some(3.0));
+		assert_eq!(map.value(&TestAttribute::Strength), Some(10.0));
+		map.remove_modifier(&TestAttribute::Strength, &TestModifier::Rage);
+		assert_eq!(map.value(&TestAttribute::Strength), Some(5.0));
+		// Raw value override kept.
+		assert_eq!(map.value(&TestAttribute::Agility), Some(4.0));
+	}
+
+	#[test]
+	fn test_rebase_keeps_set_values() {
+		let reloaded = || {
+			Arc::new(
+				MockSupplier::builder()
+					.add(TestAttribute::Strength, Attribute::Value(3.0))
+					.add(TestAttribute::Agility, Attribute::Value(1.0))
+					.discrete(TestAttribute::Strength, "sly")
+					.build(),
+			)
+		};
+		let mut map: MockMap = AttributeMap::new(Arc::new(
+			MockSupplier::builder()
+				.add(TestAttribute::Strength, Attribute::Value(1.0))
+				.add(TestAttribute::Agility, Attribute::Value(2.0))
+				.discrete(TestAttribute::Strength, "dull")
+				.build(),
+		));
+
+		// Set to the old defaults, which still counts as set.
+		map.set_raw_value(&TestAttribute::Agility, 2.0);
+		map.set_raw_state(&TestAttribute::Strength, "dull");
+		map.enable_journal();
+		map.set_raw_value(&TestAttribute::Strength, 1.0);
+		assert!(map.undo());
+		map.rebase(reloaded());
+
+		assert_eq!(map.raw_value(&TestAttribute::Strength), Some(3.0));
+		assert_eq!(map.raw_value(&TestAttribute::Agility), Some(2.0));
+		assert_eq!(map.state(&TestAttribute::Strength), Some(&Tag::new("dull")));
+
+		map.reset(&TestAttribute::Agility);
+		map.rebase(reloaded());
+		assert_eq!(map.raw_value(&TestAttribute::Agility), Some(1.0));
+	}
+
+	#[test]
+	fn test_rebase_tag_modifiers() {
+		let physical = Tag::new("physical");
+		let mental = Tag::new("mental");
+		let tagged = |strength: &[&Tag], agility: &[&Tag]| {
+			let mut builder = MockSupplier::builder()
+				.add(TestAttribute::Strength, Attribute::Value(1.0))
+				.add(TestAttribute::Agility, Attribute::Value(2.0));
+			for tag in strength {
+				builder = builder.tag(TestAttribute::Strength, (*tag).clone());
+			}
+			for tag in agility {
+				builder = builder.tag(TestAttribute::Agility, (*tag).clone());
+			}
+			Arc::new(builder.build())
+		};
+
+		let mut map: MockMap = AttributeMap::new(tagged(&[&physical, &mental], &[&physical]));
+		let rage = |amount| AttributeModifier::new(amount, Operation::Add);
+		map.add_tag_modifier(&physical, TestModifier::Rage, rage(1.0));
+		map.add_tag_modifier(&mental, TestModifier::Rage, rage(10.0));
+		assert_eq!(map.value(&TestAttribute::Strength), Some(12.0));
+		assert_eq!(map.value(&TestAttribute::Agility), Some(3.0));
+
+		// Physical is dropped: the mental modifier remains, Agility loses its own.
+		map.rebase(tagged(&[&mental], &[]));
+		assert_eq!(map.value(&TestAttribute::Strength), Some(11.0));
+		assert_eq!(map.value(&TestAttribute::Agility), Some(2.0));
+
+		map.rebase(tagged(&[&mental], &[&physical]));
+		assert_eq!(map.value(&TestAttribute::Strength), Some(11.0));
+		assert_eq!(map.value(&TestAttribute::Agility), Some(3.0));
+	}
+
+	#[test]
+	fn test_template_and_runtime_modifiers() {
+		let mut map: MockMap = AttributeMap::new(mock_supplier());
+		let attr = TestAttribute::Strength;
+		map.add_modifier(
+			&attr,
+			TestModifier::Buff,
+			AttributeModifier::new(3.0, Operation::Add),
+		);
+		map.set_raw_value(&attr, 4.0);
+		assert_eq!(map.value(&attr), Some(8.0));
+
+		let origins: Vec<_> = map
+			.instance(&attr)
+			.unwrap()
+			.modifiers()
+			.map(|(_, _, origin)| origin)
+			.collect();
+		assert_eq!(origins, [ModifierOrigin::Template, ModifierOrigin::Runtime]);
+
+		// Only the runtime buff is removed, the template one stays.
+		map.remove_modifiers(&TestModifier::Buff);
+		assert_eq!(map.value(&attr), Some(5.0));
+		assert!(map.has_modifier(&attr, &TestModifier::Buff));
+
+		map.add_modifier(
+			&attr,
+			TestModifier::Rage,
+			AttributeModifier::new(2.0, Operation::Add),
+		);
+		map.clear_runtime_modifiers(&attr);
+		assert_eq!(map.value(&attr), Some(5.0));
+
+		map.reset(&attr);
+		assert_eq!(map.value(&attr), Some(2.0));
+		assert_eq!(map.instance(&attr).unwrap().runtime_modifiers().count(), 0);
+	}
+
+	#[cfg(feature = "serde")]
+	#[test]
+	fn test_serde_skips_template_modifiers() {
+		let mut map: MockMap = AttributeMap::new(mock_supplier());
+		map.add_modifier(
+			&TestAttribute::Strength,
+			TestModifier::Rage,
+			AttributeModifier::new(3.0, Operation::Add),
+		);
+
+		let json = serde_json::to_string(&map).unwrap();
+		assert_eq!(json.matches("Buff").count(), 0);
+
+		let mut restored: MockMap = serde_json::from_str(&json).unwrap();
+		assert_eq!(restored.value(&TestAttribute::Strength), Some(4.0));
+		restored.attach(mock_supplier());
+		for id in [TestAttribute::Strength, TestAttribute::Agility] {
+			assert_eq!(restored.value(&id), map.value(&id));
+			assert_eq!(
+				restored.modifiers(&id).collect::<Vec<_>>(),
+				map.modifiers(&id).collect::<Vec<_>>()
+			);
+		}
+		assert_eq!(restored.value(&TestAttribute::Strength), Some(5.0));
+	}
+
+	#[test]
+	fn test_iteration() {
+		let mut map: MockMap = AttributeMap::new(mock_supplier());
+		map.add_modifier(
+			&TestAttribute::Agility,
+			TestModifier::Rage,
+			AttributeModifier::new(1.0, Operation::Add),
+		);
+
+		let mut keys: Vec<_> = map.keys().collect();
+		keys.sort_by_key(|id| format!("{id:?}"));
+		assert_eq!(keys, [&TestAttribute::Agility, &TestAttribute::Strength]);
+
+		let mut values: Vec<_> = map.iter().collect();
+		values.sort_by_key(|(id, _)| format!("{id:?}"));
+		assert_eq!(
+			values,
+			[
+				(&TestAttribute::Agility, 3.0),
+				(&TestAttribute::Strength, 2.0)
+			]
+		);
+
+		let instances: Vec<_> = map.instances().map(|(id, _)| id).collect();
+		assert_eq!(instances, [&TestAttribute::Agility]);
+
+		let modifiers: Vec<_> = map
+			.modifiers(&TestAttribute::Agility)
+			.map(|(key, modifier, origin)| (key, modifier.value.clone(), origin))
+			.collect();
+		assert_eq!(
+			modifiers,
+			[(&TestModifier::Rage, 1.0.into(), ModifierOrigin::Runtime)]
+		);
+		// Not materialised, so only the template modifier of the supplier.
+		let modifiers: Vec<_> = map
+			.modifiers(&TestAttribute::Strength)
+			.map(|(key, _, origin)| (key, origin))
+			.collect();
+		assert_eq!(modifiers, [(&TestModifier::Buff, ModifierOrigin::Template)]);
+
+		let raging: Vec<_> = map.with_modifier(&TestModifier::Rage).collect();
+		assert_eq!(raging, [&TestAttribute::Agility]);
+		assert_eq!(map.with_modifier(&TestModifier::Buff).count(), 0);
+	}
+
+	#[test]
+	fn test_tag_modifiers() {
+		let physical = Tag::new("physical");
+		let supplier = Arc::new(
+			MockSupplier::builder()
+				.add(TestAttribute::Strength, Attribute::Value(1.0))
+				.add(TestAttribute::Agility, Attribute::Value(2.0))
+				.tag(TestAttribute::Strength, physical.clone())
+				.build(),
+		);
+		let mut map: MockMap = AttributeMap::new(supplier.clone());
+		assert_eq!(
+			map.tagged(&physical).collect::<Vec<_>>(),
+			[&TestAttribute::Strength]
+		);
+
+		map.add_tag_modifier(
+			&physical,
+			TestModifier::Rage,
+			AttributeModifier::new(1.0, Operation::Add),
+		);
+		assert_eq!(map.value(&TestAttribute::Strength), Some(2.0));
+		assert_eq!(map.value(&TestAttribute::Agility), Some(2.0));
+
+		// Attributes tagged by a reloaded supplier receive the modifier as well.
+		let reloaded = Arc::new(
+			MockSupplier::extend(supplier)
+				.tag(TestAttribute::Agility, physical.clone())
+				.build(),
+		);
+		map.rebase(reloaded);
+		assert_eq!(map.value(&TestAttribute::Agility), Some(3.0));
+
+		map.set_raw_value(&TestAttribute::Strength, 5.0);
+		map.reset(&TestAttribute::Strength);
+		assert_eq!(map.value(&TestAttribute::Strength), Some(2.0));
+
+		map.remove_modifiers(&TestModifier::Rage);
+		assert_eq!(map.value(&TestAttribute::Strength), Some(1.0));
+		assert_eq!(map.value(&TestAttribute::Agility), Some(2.0));
+	}
+
+	#[test]
+	fn test_breakdown() {
+		let mut map: MockMap = AttributeMap::new(mock_supplier());
+		let attr = TestAttribute::Strength;
+		assert_eq!(map.breakdown(&attr).map(|b| b.value), Some(2.0));
+
+		map.add_modifier(
+			&attr,
+			TestModifier::Rage,
+			AttributeModifier::new(Value::Attribute(TestAttribute::Agility), Operation::Sub),
+		);
+		assert_eq!(
+			map.breakdown(&attr),
+			Some(Breakdown {
+				raw_value: 1.0,
+				steps: vec![
+					BreakdownStep {
+						key: TestModifier::Buff,
+						origin: ModifierOrigin::Template,
+						amount: 1.0,
+						value: 2.0,
+						cut: 0.0,
+					},
+					BreakdownStep {
+						key: TestModifier::Rage,
+						origin: ModifierOrigin::Runtime,
+						amount: 2.0,
+						value: 0.0,
+						cut: 0.0,
+					},
+				],
+				value: 0.0,
+			})
+		);
+		assert_eq!(map.breakdown(&attr).map(|b| b.value), map.value(&attr));
+	}
+
+	#[test]
+	fn test_undo_redo() {
+		let mut map: MockMap = AttributeMap::new(mock_supplier());
+		map.enable_journal();
+		let attr = TestAttribute::Strength;
+		let rage = || AttributeModifier::new(3.0, Operation::Add);
+
+		map.add_modifier(&attr, TestModifier::Rage, rage());
+		map.add_modifier(&attr, TestModifier::Buff, rage());
+		map.set_raw_value(&attr, 4.0);
+		map.remove_modifier(&attr, &TestModifier::Buff);
+		assert_eq!(map.value(&attr), Some(8.0));
+		map.remove_template_modifier(&attr, &TestModifier::Buff);
+		assert_eq!(map.value(&attr), Some(7.0));
+
+		// Both `Buff`s come back at their original positions.
+		assert!(map.undo());
+		assert!(map.undo());
+		let keys: Vec<_> = map
+			.modifiers(&attr)
+			.map(|(key, _, origin)| (key, origin))
+			.collect();
+		assert_eq!(
+			keys,
+			[
+				(&TestModifier::Buff, ModifierOrigin::Template),
+				(&TestModifier::Rage, ModifierOrigin::Runtime),
+				(&TestModifier::Buff, ModifierOrigin::Runtime),
+			]
+		);
+		assert_eq!(map.value(&attr), Some(11.0));
+
+		assert!(map.undo());
+		assert!(map.undo());
+		assert!(map.undo());
+		assert!(!map.undo());
+		assert!(!map.has_attribute(&attr));
+		assert_eq!(map.value(&attr), Some(2.0));
+
+		while map.redo() {}
+		assert_eq!(map.value(&attr), Some(7.0));
+		assert_eq!(map.modifiers(&attr).count(), 1);
+	}
+
+	#[test]
+	fn test_undo_remove_modifiers() {
+		let mut map: MockMap = AttributeMap::new(mock_supplier());
+		map.add_modifier(
+			&TestAttribute::Agility,
+			TestModifier::Rage,
+			AttributeModifier::new(1.0, Operation::Add),
+		);
+		map.expire_modifiers(TestModifier::Rage, Duration::from_secs(3));
+		map.enable_journal();
+
+		map.remove_modifiers(&TestModifier::Rage);
+		assert_eq!(map.value(&TestAttribute::Agility), Some(2.0));
+		assert_eq!(map.remaining(&TestModifier::Rage), None);
+
+		assert!(map.undo());
+		assert_eq!(map.value(&TestAttribute::Agility), Some(3.0));
+		assert_eq!(
+			map.remaining(&TestModifier::Rage),
+			Some(Duration::from_secs(3))
+		);
+
+		// Operations that cannot be undone clear the journal.
+		map.reset(&TestAttribute::Agility);
+		assert!(!map.can_undo() && !map.can_redo());
+	}
+
+	#[test]
+	fn test_undo_tick() {
+		let mut map: MockMap = AttributeMap::new(mock_supplier());
+		map.enable_journal();
+		map.add_modifier(
+			&TestAttribute::Agility,
+			TestModifier::Rage,
+			AttributeModifier::new(1.0, Operation::Add),
+		);
+		map.expire_modifiers(TestModifier::Rage, Duration::from_secs(3));
+		map.expire_modifiers(TestModifier::Buff, Duration::from_secs(5));
+
+		map.tick(Duration::from_secs(1));
+		assert!(map.undo());
+		assert_eq!(
+			map.remaining(&TestModifier::Buff),
+			Some(Duration::from_secs(5))
+		);
+
+		// Expiring a modifier is undone together with the tick that ran its timer out.
+		assert_eq!(map.tick(Duration::from_secs(3)), [TestModifier::Rage]);
+		assert_eq!(map.value(&TestAttribute::Agility), Some(2.0));
+		assert!(map.undo());
+		assert_eq!(map.value(&TestAttribute::Agility), Some(3.0));
+		assert_eq!(
+			map.remaining(&TestModifier::Rage),
+			Some(Duration::from_secs(3))
+		);
+
+		assert!(map.redo());
+		assert_eq!(map.value(&TestAttribute::Agility), Some(2.0));
+		assert_eq!(
+			map.remaining(&TestModifier::Buff),
+			Some(Duration::from_secs(2))
+		);
+
+		// Undoing the timers leaves the modifier they were set for.
+		assert!(map.undo());
+		assert!(map.undo());
+		assert!(map.undo());
+		assert_eq!(map.remaining(&TestModifier::Rage), None);
+		assert_eq!(map.value(&TestAttribute::Agility), Some(3.0));
+	}
+
+	#[test]
+	fn test_discrete_and_conditional_modifiers() {
+		#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+		enum Fact {
+			Form,
+			Movement,
+			Speed,
+		}
+		const HUMAN: Tag = Tag::new("human");
+		const WOLF: Tag = Tag::new("wolf");
+		const BAT: Tag = Tag::new("bat");
+
+		let supplier = AttributeSupplier::builder()
+			.discrete(Fact::Form, HUMAN)
+			.discrete(Fact::Movement, "grounded")
+			.add(
+				Fact::Speed,
+				AttributeInstance::builder(Attribute::Value(30)).modifier(
+					"stride",
+					AttributeModifier::new(20, Operation::Add)
+						.when(Condition::State(Fact::Form, WOLF)),
+				),
+			)
+			.build();
+		let mut map: AttributeMap<Fact, &str, i32> = AttributeMap::new(Arc::new(supplier));
+		assert_eq!(map.state(&Fact::Form), Some(&HUMAN));
+		assert_eq!(map.value(&Fact::Form), None);
+		assert_eq!(map.value(&Fact::Speed), Some(30));
+
+		map.override_state(&Fact::Form, "shapechange", 1, WOLF);
+		assert_eq!(map.state(&Fact::Form), Some(&WOLF));
+		assert_eq!(map.raw_state(&Fact::Form), Some(&HUMAN));
+		assert_eq!(map.value(&Fact::Speed), Some(50));
+
+		map.override_state(&Fact::Form, "polymorph", 5, BAT);
+		map.override_state(&Fact::Form, "curse", 5, HUMAN);
+		// The later of two equal overrides wins.
+		assert_eq!(map.state(&Fact::Form), Some(&HUMAN));
+		map.remove_modifier(&Fact::Form, &"curse");
+		assert_eq!(map.state(&Fact::Form), Some(&BAT));
+		assert_eq!(map.value(&Fact::Speed), Some(30));
+		map.remove_modifiers(&"polymorph");
+		assert_eq!(map.value(&Fact::Speed), Some(50));
+
+		map.set_raw_state(&Fact::Movement, "flying");
+		map.add_modifier(
+			&Fact::Speed,
+			"wings",
+			AttributeModifier::new(60, Operation::Add)
+				.overriding(0)
+				.when(Condition::State(Fact::Movement, Tag::new("flying"))),
+		);
+		map.add_modifier(
+			&Fact::Speed,
+			"haste",
+			AttributeModifier::new(10, Operation::Add),
+		);
+		assert_eq!(map.value(&Fact::Speed), Some(60));
+		let breakdown = map.breakdown(&Fact::Speed).unwrap();
+		let keys: Vec<_> = breakdown.steps.iter().map(|step| step.key).collect();
+		assert_eq!(keys, ["stride", "haste", "wings"]);
+
+		map.set_raw_state(&Fact::Movement, "grounded");
+		assert_eq!(map.value(&Fact::Speed), Some(60));
+		map.reset(&Fact::Form);
+		assert_eq!(map.state(&Fact::Form), Some(&HUMAN));
+		assert_eq!(map.value(&Fact::Speed), Some(40));
+	}
+
+	#[test]
+	fn test_undo_discrete() {
+		let supplier = AttributeSupplier::builder()
+			.discrete("form", "human")
+			.build();
+		let mut map: AttributeMap<&str, &str, i32> = AttributeMap::new(Arc::new(supplier));
+		map.enable_journal();
+
+		map.set_raw_state(&"form", "wolf");
+		map.override_state(&"form", "curse", 0, "toad");
+		map.remove_modifiers(&"curse");
+		assert_eq!(map.state(&"form"), Some(&Tag::new("wolf")));
+
+		assert!(map.undo());
+		assert_eq!(map.state(&"form"), Some(&Tag::new("toad")));
+		assert!(map.undo());
+		assert!(map.undo());
+		assert_eq!(map.state(&"form"), Some(&Tag::new("human")));
+		assert!(map.discrete(&"form").is_none());
+
+		assert!(map.redo() && map.redo());
+		assert_eq!(map.state(&"form"), Some(&Tag::new("toad")));
+	}
 }
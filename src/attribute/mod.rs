@@ -1,11 +1,23 @@
 use std::cmp::Ordering;
 
-use crate::util_traits::Number;
-
+use crate::{
+	attribute::modifier::{Op, Operation},
+	util_traits::Number,
+};
+
+pub(crate) mod cache;
+pub mod discrete;
+pub mod event;
 pub mod instance;
+mod journal;
 pub mod map;
 pub mod modifier;
+#[cfg(not(feature = "single-threaded"))]
+pub mod shared;
+pub mod storage;
 pub mod supplier;
+pub mod tag;
+pub mod visibility;
 
 pub fn clamp<T: PartialOrd>(value: T, min: T, max: T) -> T {
 	// value is NaN or less than min
@@ -28,6 +40,7 @@ where
 	V: Number + 'static,
 {
 	Value(V),
+	/// A default, minimum and maximum, clamped with [`Op::clamp`] of the attribute's operation.
 	Ranged(V, V, V),
 	Derived,
 }
@@ -43,9 +56,15 @@ where
 		}
 	}
 
+	/// Clamps `value` if the attribute is ranged, comparing whole values.
 	pub fn sanitize_value(&self, value: V) -> V {
+		self.sanitize_with::<Operation>(value)
+	}
+
+	/// Clamps `value` if the attribute is ranged, as the operation `O` does.
+	pub fn sanitize_with<O: Op<V>>(&self, value: V) -> V {
 		match self {
-			Self::Ranged(_, min, max) => clamp(value, *min, *max),
+			Self::Ranged(_, min, max) => O::clamp(value, *min, *max),
 			_ => value,
 		}
 	}
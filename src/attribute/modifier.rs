@@ -1,5 +1,15 @@
-use crate::util_traits::Number;
+use std::hash::Hash;
 
+use crate::{
+	attribute::{
+		clamp, instance::AttributeInstance, map::AttributeMap, storage::AttributeStorage, tag::Tag,
+	},
+	number::{Arithmetic, Rounding},
+	util_traits::{Key, Number},
+};
+
+/// The default [`Op`], adding a modifier's value to or subtracting it from the attribute's
+/// running value.
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, Debug, PartialEq)]
 pub enum Operation {
@@ -7,17 +17,70 @@ pub enum Operation {
 	Sub,
 }
 
-impl<V: Number + 'static> Op<V> for Operation {
+impl<V: Number> Op<V> for Operation {
+	fn apply(&self, a: V, b: V) -> V {
+		match self {
+			Self::Add => a + b,
+			Self::Sub => a - b,
+		}
+	}
+}
+
+/// An [`Operation`] that can also multiply, divide and scale, for [`Arithmetic`] values.
+///
+/// `Mul` and `Div` round like `V` does: integer division truncates towards zero, while
+/// [`Fixed`](crate::number::Fixed) rounds to the nearest value and saturates. Dividing by zero,
+/// or into a quotient `V` cannot hold, leaves the value unchanged rather than panicking, so a
+/// divisor taken from an attribute that drops to zero is harmless.
+/// Attributes with a [rounding mode](crate::attribute::instance::AttributeBuilder::rounding)
+/// compute in [`f64`] instead and round once at the end.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub enum ArithmeticOp {
+	Add,
+	Sub,
+	Mul,
+	Div,
+	/// Scales the value to the given percentage, e.g. `150` for a 50% bonus, see
+	/// [`Arithmetic::percent`].
+	Percent,
+}
+
+impl From<Operation> for ArithmeticOp {
+	fn from(op: Operation) -> Self {
+		match op {
+			Operation::Add => Self::Add,
+			Operation::Sub => Self::Sub,
+		}
+	}
+}
+
+impl<V: Arithmetic> Op<V> for ArithmeticOp {
 	fn apply(&self, a: V, b: V) -> V {
 		match self {
 			Self::Add => a + b,
 			Self::Sub => a - b,
+			Self::Mul => a * b,
+			Self::Div => a.checked_div(b).unwrap_or(a),
+			Self::Percent => a.percent(b),
 		}
 	}
 }
 
 pub trait Op<V>: Clone {
 	fn apply(&self, a: V, b: V) -> V;
+
+	/// Bounds a value computed with this operation to an [`Attribute::Ranged`] range, by the
+	/// value's order unless overridden. [`VectorOp`](crate::number::VectorOp) clamps each
+	/// component instead.
+	///
+	/// [`Attribute::Ranged`]: crate::attribute::Attribute::Ranged
+	fn clamp(value: V, min: V, max: V) -> V
+	where
+		V: PartialOrd,
+	{
+		clamp(value, min, max)
+	}
 }
 
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -43,21 +106,98 @@ impl<A, V: Number> From<V> for Value<A, V> {
 	}
 }
 
+/// Where a modifier on an [`AttributeInstance`](crate::attribute::instance::AttributeInstance)
+/// came from.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ModifierOrigin {
+	/// Copied from the [`AttributeSupplier`](crate::attribute::supplier::AttributeSupplier)
+	/// template, e.g. a derivation from another attribute.
+	Template,
+	/// Added to the instance at runtime, e.g. a buff.
+	Runtime,
+}
+
+/// A test on another attribute that decides whether a modifier applies.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Condition<A, V> {
+	Is(A, V),
+	IsNot(A, V),
+	AtLeast(A, V),
+	Below(A, V),
+	/// The [discrete](crate::attribute::discrete) attribute is in the state.
+	State(A, Tag),
+	/// The discrete attribute is in another state.
+	NotState(A, Tag),
+}
+
+impl<A, V> Condition<A, V> {
+	pub fn attribute(&self) -> &A {
+		match self {
+			Self::Is(a, _)
+			| Self::IsNot(a, _)
+			| Self::AtLeast(a, _)
+			| Self::Below(a, _)
+			| Self::State(a, _)
+			| Self::NotState(a, _) => a,
+		}
+	}
+}
+
+impl<A: Key + Hash, V: Number> Condition<A, V> {
+	/// Whether the condition holds for the attributes of `map`. A missing attribute fails every
+	/// test.
+	pub fn holds<M, O, S>(&self, map: &AttributeMap<A, M, V, O, S>) -> bool
+	where
+		M: Key,
+		O: Op<V>,
+		S: AttributeStorage<A, AttributeInstance<A, M, V, O>>,
+	{
+		let value = || map.value(self.attribute());
+		match self {
+			Self::Is(_, v) => value() == Some(*v),
+			Self::IsNot(_, v) => value().is_some_and(|value| value != *v),
+			Self::AtLeast(_, v) => value().is_some_and(|value| value >= *v),
+			Self::Below(_, v) => value().is_some_and(|value| value < *v),
+			Self::State(a, state) => map.state(a) == Some(state),
+			Self::NotState(a, state) => map.state(a).is_some_and(|current| current != state),
+		}
+	}
+}
+
+/// A change to an attribute's value.
+///
+/// New settings may be added over time, so outside this crate modifiers are built with
+/// [`new`](Self::new) and the builder methods rather than struct literals.
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
 pub struct AttributeModifier<A, V: 'static, O: Op<V> = Operation> {
 	pub value: Value<A, V>,
 	pub op: O,
 	pub base: bool,
+	/// If set, the modifier replaces the value instead of applying `op`. Of the overriding
+	/// modifiers the one with the highest priority wins, and of equal ones the last applied.
+	#[cfg_attr(feature = "serde", serde(default = "Option::default"))]
+	pub priority: Option<i32>,
+	/// If set, the modifier only applies while the condition holds.
+	#[cfg_attr(feature = "serde", serde(default = "Option::default"))]
+	pub condition: Option<Condition<A, V>>,
+	/// If set, the running value is rounded right after this modifier, e.g. down after halving.
+	/// Only attributes computing in [`f64`] have fractions to round; on any other attribute,
+	/// including one rebased onto a template without a rounding mode, it has no effect.
+	#[cfg_attr(feature = "serde", serde(default = "Option::default"))]
+	pub rounding: Option<Rounding>,
+	/// The kind of bonus, e.g. "equipment" or "magic", whose total an attribute may
+	/// [cap](crate::attribute::instance::AttributeBuilder::cap).
+	#[cfg_attr(feature = "serde", serde(default = "Option::default"))]
+	pub category: Option<Tag>,
 }
 
 impl<A, V, O: Op<V>> AttributeModifier<A, V, O> {
 	pub fn new<I: Into<Value<A, V>>>(value: I, op: O) -> Self {
-		Self {
-			value: value.into(),
-			op,
-			base: false,
-		}
+		Self::new_const(value.into(), op)
 	}
 
 	pub const fn new_const(value: Value<A, V>, op: O) -> Self {
@@ -65,6 +205,10 @@ impl<A, V, O: Op<V>> AttributeModifier<A, V, O> {
 			value,
 			op,
 			base: false,
+			priority: None,
+			condition: None,
+			rounding: None,
+			category: None,
 		}
 	}
 
@@ -73,11 +217,56 @@ impl<A, V, O: Op<V>> AttributeModifier<A, V, O> {
 		self.base = true;
 		self
 	}
+
+	/// Makes the modifier override the value with the given priority.
+	#[must_use]
+	pub fn overriding(mut self, priority: i32) -> Self {
+		self.priority = Some(priority);
+		self
+	}
+
+	/// Makes the modifier apply only while `condition` holds.
+	#[must_use]
+	pub fn when(mut self, condition: Condition<A, V>) -> Self {
+		self.condition = Some(condition);
+		self
+	}
+
+	/// Makes the running value round with `rounding` right after the modifier. Has no effect
+	/// unless the attribute has a
+	/// [rounding mode](crate::attribute::instance::AttributeBuilder::rounding).
+	#[must_use]
+	pub fn rounded(mut self, rounding: Rounding) -> Self {
+		self.rounding = Some(rounding);
+		self
+	}
+
+	/// Counts the modifier's bonus towards `category`.
+	#[must_use]
+	pub fn category<T: Into<Tag>>(mut self, category: T) -> Self {
+		self.category = Some(category.into());
+		self
+	}
+
+	/// The attributes the modifier reads, through its value or its condition.
+	pub fn sources(&self) -> impl Iterator<Item = &A> {
+		let value = match &self.value {
+			Value::Attribute(attribute) => Some(attribute),
+			Value::Value(_) => None,
+		};
+		value
+			.into_iter()
+			.chain(self.condition.as_ref().map(Condition::attribute))
+	}
 }
 
 #[cfg(test)]
+#[allow(clippy::float_cmp)]
 mod tests {
+	use std::sync::Arc;
+
 	use super::*;
+	use crate::attribute::{Attribute, supplier::AttributeSupplier};
 
 	#[test]
 	fn test_value_enum() {
@@ -106,6 +295,31 @@ mod tests {
 		assert_eq!(mod2.value, Value::Value(5));
 	}
 
+	#[test]
+	fn test_condition() {
+		let modifier: AttributeModifier<&str, i32> =
+			AttributeModifier::new(Value::Attribute("strength"), Operation::Add)
+				.when(Condition::AtLeast("level", 3));
+		let sources: Vec<_> = modifier.sources().collect();
+		assert_eq!(sources, [&"strength", &"level"]);
+
+		let supplier = AttributeSupplier::builder()
+			.add("level", Attribute::Value(3))
+			.add("rank", Attribute::Value(2))
+			.discrete("form", "human")
+			.build();
+		let map: AttributeMap<&str, (), i32> = AttributeMap::new(Arc::new(supplier));
+
+		let condition = modifier.condition.unwrap();
+		assert!(condition.holds(&map));
+		assert!(!Condition::AtLeast("rank", 3).holds(&map));
+		assert!(!Condition::AtLeast("missing", 3).holds(&map));
+		assert!(Condition::IsNot("level", 1).holds(&map));
+		assert!(Condition::State("form", Tag::new("human")).holds(&map));
+		assert!(Condition::NotState("form", Tag::new("wolf")).holds(&map));
+		assert!(!Condition::NotState("missing", Tag::new("wolf")).holds(&map));
+	}
+
 	#[test]
 	fn test_operations() {
 		let add = Operation::Add;
@@ -113,6 +327,41 @@ mod tests {
 
 		assert_eq!(add.apply(2, 8), 10);
 		assert_eq!(sub.apply(5, 3), 2);
+		assert_eq!(ArithmeticOp::Mul.apply(4, 3), 12);
+		assert_eq!(ArithmeticOp::Div.apply(7, 2), 3);
+		assert_eq!(ArithmeticOp::Div.apply(7.0, 2.0), 3.5);
+		assert_eq!(ArithmeticOp::Div.apply(7, 0), 7);
+		assert_eq!(ArithmeticOp::Div.apply(i8::MIN, -1), i8::MIN);
+		assert_eq!(ArithmeticOp::Div.apply(7.0, 0.0), 7.0);
+		assert_eq!(ArithmeticOp::Percent.apply(200u8, 150), u8::MAX);
+		assert_eq!(ArithmeticOp::Percent.apply(7, 150), 10);
+		assert_eq!(ArithmeticOp::Percent.apply(7.0, 150.0), 10.5);
+	}
+
+	#[test]
+	fn test_number_operations() {
+		// Values that can only be added and subtracted still get `Operation`.
+		#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+		struct Points(i32);
+
+		impl std::ops::Add for Points {
+			type Output = Self;
+
+			fn add(self, rhs: Self) -> Self {
+				Self(self.0 + rhs.0)
+			}
+		}
+
+		impl std::ops::Sub for Points {
+			type Output = Self;
+
+			fn sub(self, rhs: Self) -> Self {
+				Self(self.0 - rhs.0)
+			}
+		}
+
+		assert_eq!(Operation::Add.apply(Points(2), Points(3)), Points(5));
+		assert_eq!(Operation::Sub.apply(Points(2), Points(3)), Points(-1));
 	}
 
 	#[test]
@@ -0,0 +1,150 @@
+//! Many-readers/single-writer access to an [`AttributeMap`] shared between threads.
+//!
+//! Reads take a shared lock and may run in parallel; every `value()` call fills the instance
+//! caches without any further locking. Writes take the exclusive lock, so readers never observe
+//! a half-applied mutation.
+
+use std::{collections::HashMap, hash::Hash, sync::Arc};
+
+use parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::{
+	attribute::{
+		instance::AttributeInstance, map::AttributeMap, modifier::Op, storage::AttributeStorage,
+	},
+	prelude::Operation,
+	util_traits::{Key, Number},
+};
+
+#[derive(derive_more::Debug)]
+pub struct SharedAttributeMap<
+	A,
+	M,
+	V = f32,
+	O = Operation,
+	S = HashMap<A, AttributeInstance<A, M, V, O>>,
+> where
+	A: Key + Hash + 'static,
+	M: Key + 'static,
+	V: Number + 'static,
+	O: Op<V>,
+	S: AttributeStorage<A, AttributeInstance<A, M, V, O>>,
+{
+	#[allow(clippy::type_complexity)]
+	inner: Arc<RwLock<AttributeMap<A, M, V, O, S>>>,
+}
+
+impl<A, M, V, O, S> SharedAttributeMap<A, M, V, O, S>
+where
+	A: Key + Hash,
+	M: Key,
+	V: Number + 'static,
+	O: Op<V>,
+	S: AttributeStorage<A, AttributeInstance<A, M, V, O>>,
+{
+	#[must_use]
+	pub fn new(map: AttributeMap<A, M, V, O, S>) -> Self {
+		Self {
+			inner: Arc::new(RwLock::new(map)),
+		}
+	}
+
+	/// Shared access for evaluating attributes. Any number of readers may hold this at once.
+	pub fn read(&self) -> RwLockReadGuard<'_, AttributeMap<A, M, V, O, S>> {
+		self.inner.read()
+	}
+
+	/// Exclusive access for mutating attributes. Blocks until all readers are done.
+	pub fn write(&self) -> RwLockWriteGuard<'_, AttributeMap<A, M, V, O, S>> {
+		self.inner.write()
+	}
+
+	pub fn value(&self, attribute: &A) -> Option<V> {
+		self.read().value(attribute)
+	}
+
+	pub fn base_value(&self, attribute: &A) -> Option<V> {
+		self.read().base_value(attribute)
+	}
+}
+
+impl<A, M, V, O, S> Clone for SharedAttributeMap<A, M, V, O, S>
+where
+	A: Key + Hash,
+	M: Key,
+	V: Number + 'static,
+	O: Op<V>,
+	S: AttributeStorage<A, AttributeInstance<A, M, V, O>>,
+{
+	fn clone(&self) -> Self {
+		Self {
+			inner: self.inner.clone(),
+		}
+	}
+}
+
+impl<A, M, V, O, S> From<AttributeMap<A, M, V, O, S>> for SharedAttributeMap<A, M, V, O, S>
+where
+	A: Key + Hash,
+	M: Key,
+	V: Number + 'static,
+	O: Op<V>,
+	S: AttributeStorage<A, AttributeInstance<A, M, V, O>>,
+{
+	fn from(value: AttributeMap<A, M, V, O, S>) -> Self {
+		Self::new(value)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::prelude::{Attribute, AttributeModifier, AttributeSupplier, Value};
+
+	#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+	enum TestAttribute {
+		Strength,
+		Carry,
+	}
+
+	#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+	enum TestModifier {
+		Strength,
+	}
+
+	type MockMap = AttributeMap<TestAttribute, TestModifier, i32>;
+
+	fn assert_send_sync<T: Send + Sync>() {}
+
+	#[test]
+	fn test_send_sync() {
+		assert_send_sync::<MockMap>();
+		assert_send_sync::<AttributeSupplier<TestAttribute, TestModifier, i32>>();
+		assert_send_sync::<SharedAttributeMap<TestAttribute, TestModifier, i32>>();
+	}
+
+	#[test]
+	fn test_read_sees_write() {
+		let supplier = Arc::new(
+			AttributeSupplier::builder()
+				.add(TestAttribute::Strength, Attribute::Value(2))
+				.add(
+					TestAttribute::Carry,
+					AttributeInstance::builder(Attribute::Derived).modifier(
+						TestModifier::Strength,
+						AttributeModifier::new(
+							Value::Attribute(TestAttribute::Strength),
+							Operation::Add,
+						),
+					),
+				)
+				.build(),
+		);
+		let shared = SharedAttributeMap::new(MockMap::new(supplier));
+		let reader = shared.clone();
+
+		assert_eq!(reader.value(&TestAttribute::Carry), Some(2));
+		shared.write().set_raw_value(&TestAttribute::Strength, 5);
+		assert_eq!(reader.value(&TestAttribute::Carry), Some(5));
+	}
+}
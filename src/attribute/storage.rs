@@ -0,0 +1,409 @@
+//! Backends for the materialised instances of an [`AttributeMap`](super::map::AttributeMap).
+//!
+//! The default backend is a [`HashMap`]. Systems whose attribute set is known up front can
+//! implement [`AttributeIndex`] for their key and use [`DenseStorage`] instead, which resolves
+//! every lookup to a slice index without hashing and keeps a list of what reads each attribute,
+//! so a change only invalidates the instances depending on it.
+
+use std::{
+	collections::{HashMap, hash_map::Entry},
+	hash::{BuildHasher, Hash},
+};
+
+use crate::util_traits::Key;
+
+/// An attribute key that maps onto a dense `0..COUNT` index range.
+pub trait AttributeIndex: Key {
+	/// The number of distinct keys. [`DenseStorage`] reserves this many slots, and grows for
+	/// keys indexed beyond them.
+	const COUNT: usize;
+
+	fn index(&self) -> usize;
+}
+
+pub trait AttributeStorage<A, T>: Default {
+	fn get(&self, key: &A) -> Option<&T>;
+	fn get_mut(&mut self, key: &A) -> Option<&mut T>;
+	/// Inserts `value` under `key`, replacing any previous value, and returns it.
+	fn insert(&mut self, key: A, value: T) -> &mut T;
+	fn remove(&mut self, key: &A) -> Option<T>;
+
+	/// The value under `key`, inserting the one `make` returns if there is none, and whether it
+	/// was inserted. Looks `key` up once.
+	fn get_or_insert_with(
+		&mut self,
+		key: &A,
+		make: impl FnOnce() -> Option<T>,
+	) -> Option<(&mut T, bool)>;
+
+	/// Notes that the value under `dependent` reads `dependencies`, so that
+	/// [`dependents`](Self::dependents) finds it. Notes are never dropped; stale ones only cost
+	/// a spurious lookup. Storage keeping no notes ignores them.
+	fn link<'a>(&mut self, dependent: &A, dependencies: impl IntoIterator<Item = &'a A>)
+	where
+		A: 'a,
+	{
+		let _ = (dependent, dependencies);
+	}
+
+	/// The values that may read `key`: every one [linked](Self::link) to it, possibly more.
+	/// Storage keeping no notes yields every value.
+	fn dependents<'a>(&'a self, key: &A) -> impl Iterator<Item = (&'a A, &'a T)>
+	where
+		A: 'a,
+		T: 'a,
+	{
+		let _ = key;
+		self.iter()
+	}
+
+	fn iter<'a>(&'a self) -> impl Iterator<Item = (&'a A, &'a T)>
+	where
+		A: 'a,
+		T: 'a;
+	fn iter_mut<'a>(&'a mut self) -> impl Iterator<Item = (&'a A, &'a mut T)>
+	where
+		A: 'a,
+		T: 'a;
+
+	fn len(&self) -> usize;
+
+	fn contains_key(&self, key: &A) -> bool {
+		self.get(key).is_some()
+	}
+	fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+}
+
+impl<K, T, H> AttributeStorage<K, T> for HashMap<K, T, H>
+where
+	K: Key + Hash,
+	H: BuildHasher + Default,
+{
+	fn get(&self, key: &K) -> Option<&T> {
+		HashMap::get(self, key)
+	}
+
+	fn get_mut(&mut self, key: &K) -> Option<&mut T> {
+		HashMap::get_mut(self, key)
+	}
+
+	fn insert(&mut self, key: K, value: T) -> &mut T {
+		match self.entry(key) {
+			Entry::Occupied(mut e) => {
+				e.insert(value);
+				e.into_mut()
+			}
+			Entry::Vacant(e) => e.insert(value),
+		}
+	}
+
+	fn remove(&mut self, key: &K) -> Option<T> {
+		HashMap::remove(self, key)
+	}
+
+	fn get_or_insert_with(
+		&mut self,
+		key: &K,
+		make: impl FnOnce() -> Option<T>,
+	) -> Option<(&mut T, bool)> {
+		match self.entry(key.clone()) {
+			Entry::Occupied(e) => Some((e.into_mut(), false)),
+			Entry::Vacant(e) => Some((e.insert(make()?), true)),
+		}
+	}
+
+	fn iter<'a>(&'a self) -> impl Iterator<Item = (&'a K, &'a T)>
+	where
+		K: 'a,
+		T: 'a,
+	{
+		HashMap::iter(self)
+	}
+
+	fn iter_mut<'a>(&'a mut self) -> impl Iterator<Item = (&'a K, &'a mut T)>
+	where
+		K: 'a,
+		T: 'a,
+	{
+		HashMap::iter_mut(self)
+	}
+
+	fn len(&self) -> usize {
+		HashMap::len(self)
+	}
+
+	fn contains_key(&self, key: &K) -> bool {
+		HashMap::contains_key(self, key)
+	}
+}
+
+/// Slice-backed storage with one slot per [`AttributeIndex`] key.
+#[derive(Clone, Debug)]
+pub struct DenseStorage<A, T> {
+	slots: Vec<Option<(A, T)>>,
+	/// The indices of the slots [linked](AttributeStorage::link) to each index.
+	dependents: Vec<Vec<usize>>,
+	len: usize,
+}
+
+impl<A: AttributeIndex, T> DenseStorage<A, T> {
+	fn slot(&self, key: &A) -> Option<&(A, T)> {
+		self.slots.get(key.index()).and_then(Option::as_ref)
+	}
+
+	/// The slot of `key`, growing the slots to reach it.
+	fn slot_mut(&mut self, key: &A) -> &mut Option<(A, T)> {
+		let index = key.index();
+		if index >= self.slots.len() {
+			self.slots.resize_with(index + 1, || None);
+		}
+		&mut self.slots[index]
+	}
+}
+
+impl<A: AttributeIndex, T> Default for DenseStorage<A, T> {
+	fn default() -> Self {
+		Self {
+			slots: std::iter::repeat_with(|| None).take(A::COUNT).collect(),
+			dependents: std::iter::repeat_with(Vec::new).take(A::COUNT).collect(),
+			len: 0,
+		}
+	}
+}
+
+impl<A: AttributeIndex, T> AttributeStorage<A, T> for DenseStorage<A, T> {
+	fn get(&self, key: &A) -> Option<&T> {
+		self.slot(key).map(|(_, v)| v)
+	}
+
+	fn get_mut(&mut self, key: &A) -> Option<&mut T> {
+		self.slots
+			.get_mut(key.index())
+			.and_then(Option::as_mut)
+			.map(|(_, v)| v)
+	}
+
+	fn insert(&mut self, key: A, value: T) -> &mut T {
+		let index = key.index();
+		if self.slot_mut(&key).is_none() {
+			self.len += 1;
+		}
+		&mut self.slots[index].insert((key, value)).1
+	}
+
+	fn remove(&mut self, key: &A) -> Option<T> {
+		let removed = self.slots.get_mut(key.index()).and_then(Option::take);
+		if removed.is_some() {
+			self.len -= 1;
+		}
+		removed.map(|(_, v)| v)
+	}
+
+	fn get_or_insert_with(
+		&mut self,
+		key: &A,
+		make: impl FnOnce() -> Option<T>,
+	) -> Option<(&mut T, bool)> {
+		let slot = self.slot_mut(key);
+		let inserted = slot.is_none();
+		if inserted {
+			*slot = Some((key.clone(), make()?));
+			self.len += 1;
+		}
+		self.slots[key.index()].as_mut().map(|(_, v)| (v, inserted))
+	}
+
+	fn link<'a>(&mut self, dependent: &A, dependencies: impl IntoIterator<Item = &'a A>)
+	where
+		A: 'a,
+	{
+		let index = dependent.index();
+		for dependency in dependencies {
+			let at = dependency.index();
+			if at >= self.dependents.len() {
+				self.dependents.resize_with(at + 1, Vec::new);
+			}
+			if !self.dependents[at].contains(&index) {
+				self.dependents[at].push(index);
+			}
+		}
+	}
+
+	fn dependents<'a>(&'a self, key: &A) -> impl Iterator<Item = (&'a A, &'a T)>
+	where
+		A: 'a,
+		T: 'a,
+	{
+		self.dependents
+			.get(key.index())
+			.into_iter()
+			.flatten()
+			.filter_map(|&index| self.slots.get(index)?.as_ref())
+			.map(|(k, v)| (k, v))
+	}
+
+	fn iter<'a>(&'a self) -> impl Iterator<Item = (&'a A, &'a T)>
+	where
+		A: 'a,
+		T: 'a,
+	{
+		self.slots.iter().flatten().map(|(k, v)| (k, v))
+	}
+
+	fn iter_mut<'a>(&'a mut self) -> impl Iterator<Item = (&'a A, &'a mut T)>
+	where
+		A: 'a,
+		T: 'a,
+	{
+		self.slots.iter_mut().flatten().map(|(k, v)| (&*k, v))
+	}
+
+	fn len(&self) -> usize {
+		self.len
+	}
+}
+
+#[cfg(feature = "serde")]
+impl<A, T> serde::Serialize for DenseStorage<A, T>
+where
+	A: serde::Serialize,
+	T: serde::Serialize,
+{
+	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_seq(self.slots.iter().flatten())
+	}
+}
+
+#[cfg(feature = "serde")]
+impl<'de, A, T> serde::Deserialize<'de> for DenseStorage<A, T>
+where
+	A: AttributeIndex + serde::Deserialize<'de>,
+	T: serde::Deserialize<'de>,
+{
+	fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let entries = Vec::<(A, T)>::deserialize(deserializer)?;
+		let mut storage = Self::default();
+		// Keys beyond `COUNT` grow the storage, as they did before it was serialised.
+		for (key, value) in entries {
+			storage.insert(key, value);
+		}
+		Ok(storage)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+	enum TestAttribute {
+		Strength,
+		Agility,
+		Stamina,
+	}
+
+	impl AttributeIndex for TestAttribute {
+		const COUNT: usize = 3;
+
+		fn index(&self) -> usize {
+			*self as usize
+		}
+	}
+
+	#[test]
+	fn test_dense_insert_and_get() {
+		let mut storage = DenseStorage::<TestAttribute, u8>::default();
+		assert!(storage.is_empty());
+
+		storage.insert(TestAttribute::Agility, 3);
+		assert_eq!(storage.get(&TestAttribute::Agility), Some(&3));
+		assert_eq!(storage.get(&TestAttribute::Strength), None);
+		assert_eq!(storage.len(), 1);
+
+		*storage.insert(TestAttribute::Agility, 4) += 1;
+		assert_eq!(storage.get(&TestAttribute::Agility), Some(&5));
+		assert_eq!(storage.len(), 1);
+	}
+
+	#[test]
+	fn test_dense_remove() {
+		let mut storage = DenseStorage::<TestAttribute, u8>::default();
+		storage.insert(TestAttribute::Stamina, 1);
+
+		assert_eq!(storage.remove(&TestAttribute::Stamina), Some(1));
+		assert_eq!(storage.remove(&TestAttribute::Stamina), None);
+		assert!(storage.is_empty());
+	}
+
+	#[test]
+	fn test_dense_iter_in_index_order() {
+		let mut storage = DenseStorage::<TestAttribute, u8>::default();
+		storage.insert(TestAttribute::Stamina, 3);
+		storage.insert(TestAttribute::Strength, 1);
+
+		let keys: Vec<_> = storage.iter().map(|(k, _)| *k).collect();
+		assert_eq!(keys, [TestAttribute::Strength, TestAttribute::Stamina]);
+
+		for (_, v) in storage.iter_mut() {
+			*v *= 2;
+		}
+		assert_eq!(storage.get(&TestAttribute::Stamina), Some(&6));
+	}
+
+	#[test]
+	fn test_dense_links_and_growth() {
+		let mut storage = DenseStorage::<TestAttribute, u8>::default();
+		assert_eq!(
+			storage.get_or_insert_with(&TestAttribute::Stamina, || Some(1)),
+			Some((&mut 1, true))
+		);
+		assert_eq!(
+			storage.get_or_insert_with(&TestAttribute::Stamina, || Some(2)),
+			Some((&mut 1, false))
+		);
+		assert_eq!(
+			storage.get_or_insert_with(&TestAttribute::Agility, || None),
+			None
+		);
+
+		storage.link(&TestAttribute::Stamina, [&TestAttribute::Strength]);
+		storage.link(&TestAttribute::Stamina, [&TestAttribute::Strength]);
+		let dependents: Vec<_> = storage.dependents(&TestAttribute::Strength).collect();
+		assert_eq!(dependents, [(&TestAttribute::Stamina, &1)]);
+		assert_eq!(storage.dependents(&TestAttribute::Agility).count(), 0);
+
+		// Keys beyond `COUNT` grow the storage instead of panicking.
+		let mut storage = DenseStorage::<Beyond, u8>::default();
+		storage.insert(Beyond(5), 3);
+		assert_eq!(storage.get(&Beyond(5)), Some(&3));
+		assert_eq!(storage.len(), 1);
+	}
+
+	#[cfg(feature = "serde")]
+	#[test]
+	fn test_dense_serde_round_trip() {
+		let mut storage = DenseStorage::<Beyond, u8>::default();
+		storage.insert(Beyond(1), 2);
+		storage.insert(Beyond(5), 3);
+
+		let json = serde_json::to_string(&storage).unwrap();
+		let storage: DenseStorage<Beyond, u8> = serde_json::from_str(&json).unwrap();
+		assert_eq!(storage.get(&Beyond(1)), Some(&2));
+		assert_eq!(storage.get(&Beyond(5)), Some(&3));
+		assert_eq!(storage.len(), 2);
+	}
+
+	#[derive(Debug, Clone, PartialEq, Eq)]
+	#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+	struct Beyond(usize);
+
+	impl AttributeIndex for Beyond {
+		const COUNT: usize = 2;
+
+		fn index(&self) -> usize {
+			self.0
+		}
+	}
+}
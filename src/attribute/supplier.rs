@@ -1,11 +1,23 @@
-use std::{collections::HashMap, hash::Hash};
+use std::{collections::HashMap, hash::Hash, sync::Arc};
 
 use crate::{
-	attribute::{instance::AttributeInstance, map::AttributeMap, modifier::Op},
+	advancement::CostCurve,
+	attribute::{
+		instance::AttributeInstance,
+		map::AttributeMap,
+		modifier::{AttributeModifier, Op},
+		storage::{AttributeIndex, AttributeStorage},
+		tag::Tag,
+		visibility::Visibility,
+	},
+	number::{Arithmetic, Curve, Shaping},
 	prelude::Operation,
 	util_traits::{Key, Number},
 };
 
+/// Template modifiers a supplier adds on top of its own or inherited attributes.
+type TemplateModifiers<A, M, V, O> = HashMap<A, Vec<(M, AttributeModifier<A, V, O>)>>;
+
 #[must_use]
 pub struct AttributeSupplierBuilder<A, M, V, O = Operation>
 where
@@ -14,7 +26,18 @@ where
 	V: Number + 'static,
 	O: Op<V>,
 {
+	parent: Option<Arc<AttributeSupplier<A, M, V, O>>>,
 	instances: HashMap<A, AttributeInstance<A, M, V, O>>,
+	/// The keys of `instances`, in the order they were first added.
+	order: Vec<A>,
+	modifiers: TemplateModifiers<A, M, V, O>,
+	tags: HashMap<A, Vec<Tag>>,
+	costs: HashMap<A, CostCurve<V>>,
+	currency: Option<A>,
+	visibility: HashMap<A, Visibility>,
+	modifier_visibility: Vec<(M, Visibility)>,
+	curves: HashMap<A, Shaping<V>>,
+	discrete: HashMap<A, Tag>,
 }
 
 impl<A, M, V, O> AttributeSupplierBuilder<A, M, V, O>
@@ -26,21 +49,144 @@ where
 {
 	#[must_use]
 	pub fn build(self) -> AttributeSupplier<A, M, V, O> {
-		AttributeSupplier {
+		let mut supplier = AttributeSupplier {
+			parent: self.parent,
 			instances: self.instances,
-		}
+			order: self.order,
+			tags: self.tags,
+			costs: self.costs,
+			currency: self.currency,
+			visibility: self.visibility,
+			modifier_visibility: self.modifier_visibility,
+			curves: self.curves,
+			discrete: self.discrete,
+			inherited: HashMap::new(),
+		};
+		supplier.merge(self.modifiers);
+		supplier
 	}
 
+	/// Defines `id`, replacing any definition inherited from the parent.
 	pub fn add<I: Into<A>, AI: Into<AttributeInstance<A, M, V, O>>>(
 		mut self,
 		id: I,
 		attribute: AI,
 	) -> Self {
-		self.instances.insert(id.into(), attribute.into());
+		let id = id.into();
+		if self
+			.instances
+			.insert(id.clone(), attribute.into())
+			.is_none()
+		{
+			self.order.push(id);
+		}
+		self
+	}
+
+	/// Adds a template modifier to `id`, on top of its own or inherited definition.
+	pub fn modifier<I: Into<A>>(
+		mut self,
+		id: I,
+		key: M,
+		modifier: AttributeModifier<A, V, O>,
+	) -> Self {
+		self.modifiers
+			.entry(id.into())
+			.or_default()
+			.push((key, modifier));
+		self
+	}
+
+	/// Tags `id`, in addition to any tags it inherits from the parent.
+	pub fn tag<I: Into<A>, T: Into<Tag>>(mut self, id: I, tag: T) -> Self {
+		let tags = self.tags.entry(id.into()).or_default();
+		let tag = tag.into();
+		if !tags.contains(&tag) {
+			tags.push(tag);
+		}
+		self
+	}
+
+	/// Lets `id` be [advanced](AttributeMap::advance) at the price of `curve`.
+	pub fn cost<I: Into<A>>(mut self, id: I, curve: CostCurve<V>) -> Self {
+		self.costs.insert(id.into(), curve);
+		self
+	}
+
+	/// Declares the attribute [advancement](AttributeMap::advance) is paid with.
+	pub fn currency<I: Into<A>>(mut self, id: I) -> Self {
+		self.currency = Some(id.into());
+		self
+	}
+
+	/// Restricts who may see `id` in a [redacted](AttributeMap::redact) export.
+	pub fn visibility<I: Into<A>>(mut self, id: I, visibility: Visibility) -> Self {
+		self.visibility.insert(id.into(), visibility);
+		self
+	}
+
+	/// Restricts who may see the modifiers keyed `key` in a [redacted](AttributeMap::redact)
+	/// export.
+	pub fn modifier_visibility(mut self, key: M, visibility: Visibility) -> Self {
+		match self.modifier_visibility.iter_mut().find(|(m, _)| key.eq(m)) {
+			Some((_, v)) => *v = visibility,
+			None => self.modifier_visibility.push((key, visibility)),
+		}
+		self
+	}
+
+	/// Shapes the value of `id` with `curve` after its modifiers, replacing any curve it inherits.
+	///
+	/// # Panics
+	///
+	/// If `curve` is not [valid](Curve::is_valid).
+	pub fn curve<I: Into<A>>(mut self, id: I, curve: Curve) -> Self
+	where
+		V: Arithmetic,
+	{
+		assert!(curve.is_valid(), "invalid curve {curve:?}");
+		self.curves.insert(id.into(), Shaping::new(curve));
+		self
+	}
+
+	/// Defines `id` as a [discrete](crate::attribute::discrete) attribute starting in `state`,
+	/// replacing any discrete definition inherited from the parent.
+	pub fn discrete<I: Into<A>, T: Into<Tag>>(mut self, id: I, state: T) -> Self {
+		self.discrete.insert(id.into(), state.into());
+		self
+	}
+
+	/// Copies every attribute `parent` defines or inherits that this builder does not define,
+	/// with its template modifiers and curve. Unlike [`parent`](Self::parent), the built supplier
+	/// does not keep `parent`, so its tags, costs, visibility and discrete definitions are not
+	/// inherited.
+	pub fn inherit(mut self, parent: &AttributeSupplier<A, M, V, O>) -> Self {
+		for id in parent.keys() {
+			if !self.instances.contains_key(id)
+				&& let Some(instance) = parent.resolve(id)
+			{
+				self = self.add(id.clone(), instance.clone());
+			}
+		}
+		self
+	}
+
+	/// Makes this supplier extend `parent`: attributes it does not define are looked up there.
+	pub fn parent(mut self, parent: Arc<AttributeSupplier<A, M, V, O>>) -> Self {
+		self.parent = Some(parent);
 		self
 	}
 }
 
+/// Template attributes shared by every [`AttributeMap`] created from it.
+///
+/// A supplier may extend a parent supplier. Definitions are resolved along the parent chain when
+/// an instance is created or a value computed: the nearest definition wins, and template
+/// modifiers added by each supplier in between are applied on top of it.
+///
+/// A supplier is immutable once built and, unless the `single-threaded` feature is enabled, is
+/// `Send + Sync` whenever its key, value and operation types are, so one `Arc` can back maps on
+/// any number of threads.
 pub struct AttributeSupplier<A, M, V = f32, O = Operation>
 where
 	A: Key + Hash,
@@ -48,7 +194,20 @@ where
 	V: Number + 'static,
 	O: Op<V>,
 {
+	parent: Option<Arc<AttributeSupplier<A, M, V, O>>>,
 	instances: HashMap<A, AttributeInstance<A, M, V, O>>,
+	/// The keys of `instances`, in the order they were first added.
+	order: Vec<A>,
+	tags: HashMap<A, Vec<Tag>>,
+	costs: HashMap<A, CostCurve<V>>,
+	currency: Option<A>,
+	visibility: HashMap<A, Visibility>,
+	modifier_visibility: Vec<(M, Visibility)>,
+	curves: HashMap<A, Shaping<V>>,
+	discrete: HashMap<A, Tag>,
+	/// Inherited definitions this supplier gives a curve or template modifiers, resolved when it
+	/// is built.
+	inherited: HashMap<A, AttributeInstance<A, M, V, O>>,
 }
 
 impl<A, M, V, O> AttributeSupplier<A, M, V, O>
@@ -60,36 +219,221 @@ where
 {
 	pub fn builder() -> AttributeSupplierBuilder<A, M, V, O> {
 		AttributeSupplierBuilder {
+			parent: None,
 			instances: HashMap::new(),
+			order: Vec::new(),
+			modifiers: HashMap::new(),
+			tags: HashMap::new(),
+			costs: HashMap::new(),
+			currency: None,
+			visibility: HashMap::new(),
+			modifier_visibility: Vec::new(),
+			curves: HashMap::new(),
+			discrete: HashMap::new(),
 		}
 	}
 
+	/// A builder for a supplier extending `parent`.
+	pub fn extend(parent: Arc<Self>) -> AttributeSupplierBuilder<A, M, V, O> {
+		AttributeSupplierBuilder {
+			parent: Some(parent),
+			instances: HashMap::new(),
+			order: Vec::new(),
+			modifiers: HashMap::new(),
+			tags: HashMap::new(),
+			costs: HashMap::new(),
+			currency: None,
+			visibility: HashMap::new(),
+			modifier_visibility: Vec::new(),
+			curves: HashMap::new(),
+			discrete: HashMap::new(),
+		}
+	}
+
+	#[must_use]
+	pub fn parent(&self) -> Option<&Arc<Self>> {
+		self.parent.as_ref()
+	}
+
 	pub fn create_instance(&self, attribute: &A) -> Option<AttributeInstance<A, M, V, O>> {
-		self.instances.get(attribute).cloned()
+		self.resolve(attribute).cloned()
 	}
 
-	// pub(crate) fn has_attribute(&self, attribute: &A) -> bool {
-	// 	self.instances.contains_key(attribute)
-	// }
+	pub fn has_attribute(&self, attribute: &A) -> bool {
+		self.instances.contains_key(attribute)
+			|| self
+				.parent
+				.as_ref()
+				.is_some_and(|p| p.has_attribute(attribute))
+	}
+
+	/// Whether `attribute` carries `tag`, here or in any parent.
+	pub fn has_tag(&self, attribute: &A, tag: &Tag) -> bool {
+		self.tags
+			.get(attribute)
+			.is_some_and(|tags| tags.contains(tag))
+			|| self
+				.parent
+				.as_ref()
+				.is_some_and(|p| p.has_tag(attribute, tag))
+	}
+
+	/// Every tag of `attribute`, its own first.
+	pub fn tags(&self, attribute: &A) -> Vec<&Tag> {
+		let mut tags: Vec<&Tag> = self.tags.get(attribute).into_iter().flatten().collect();
+		if let Some(parent) = &self.parent {
+			for tag in parent.tags(attribute) {
+				if !tags.contains(&tag) {
+					tags.push(tag);
+				}
+			}
+		}
+		tags
+	}
 
-	pub(crate) fn value(&self, attribute: &A, attributes: &AttributeMap<A, M, V, O>) -> Option<V> {
+	/// The initial state of the discrete `attribute`, here or in the nearest parent defining it.
+	pub fn discrete_state(&self, attribute: &A) -> Option<&Tag> {
+		self.discrete
+			.get(attribute)
+			.or_else(|| self.parent.as_ref()?.discrete_state(attribute))
+	}
+
+	/// The cost curve of `attribute`, here or in the nearest parent declaring one.
+	pub fn cost(&self, attribute: &A) -> Option<&CostCurve<V>> {
+		self.costs
+			.get(attribute)
+			.or_else(|| self.parent.as_ref()?.cost(attribute))
+	}
+
+	/// The currency attribute, here or in the nearest parent declaring one.
+	pub fn currency(&self) -> Option<&A> {
+		self.currency
+			.as_ref()
+			.or_else(|| self.parent.as_ref()?.currency())
+	}
+
+	/// The visibility of `attribute`, here or in the nearest parent declaring one.
+	pub fn visibility(&self, attribute: &A) -> Visibility {
+		match self.visibility.get(attribute) {
+			Some(visibility) => *visibility,
+			None => self
+				.parent
+				.as_ref()
+				.map(|p| p.visibility(attribute))
+				.unwrap_or_default(),
+		}
+	}
+
+	/// The visibility of modifiers keyed `key`, here or in the nearest parent declaring one.
+	pub fn modifier_visibility(&self, key: &M) -> Visibility {
+		match self.modifier_visibility.iter().find(|(m, _)| key.eq(m)) {
+			Some((_, visibility)) => *visibility,
+			None => self
+				.parent
+				.as_ref()
+				.map(|p| p.modifier_visibility(key))
+				.unwrap_or_default(),
+		}
+	}
+
+	/// The curve of `attribute`, here or in the nearest parent declaring one.
+	pub fn curve(&self, attribute: &A) -> Option<&Curve> {
+		self.shaping(attribute).map(Shaping::curve)
+	}
+
+	fn shaping(&self, attribute: &A) -> Option<&Shaping<V>> {
+		self.curves
+			.get(attribute)
+			.or_else(|| self.parent.as_ref()?.shaping(attribute))
+	}
+
+	/// Every attribute defined by this supplier or any of its parents, in the order they were
+	/// first added, the parents' first.
+	pub(crate) fn keys(&self) -> Vec<&A> {
+		let Some(parent) = &self.parent else {
+			return self.order.iter().collect();
+		};
+		let mut keys = parent.keys();
+		keys.extend(self.order.iter().filter(|id| !parent.has_attribute(id)));
+		keys
+	}
+
+	/// Gives every definition the curve shaping it and the template modifiers added on top of it,
+	/// so resolving one need not copy it.
+	fn merge(&mut self, modifiers: TemplateModifiers<A, M, V, O>) {
+		// Definitions of this supplier take the curve declared here or by the nearest parent.
+		let shaped: Vec<_> = self
+			.instances
+			.keys()
+			.filter_map(|id| Some((id.clone(), self.shaping(id)?.clone())))
+			.collect();
+		for (id, curve) in shaped {
+			if let Some(instance) = self.instances.get_mut(&id) {
+				instance.set_curve(curve);
+			}
+		}
+
+		// Inherited definitions already carry the curves and template modifiers of the parents.
+		if let Some(parent) = &self.parent {
+			let changed = self.curves.keys().chain(modifiers.keys());
+			for id in changed {
+				if self.instances.contains_key(id) || self.inherited.contains_key(id) {
+					continue;
+				}
+				if let Some(instance) = parent.resolve(id) {
+					let mut instance = instance.clone();
+					if let Some(curve) = self.curves.get(id) {
+						instance.set_curve(curve.clone());
+					}
+					self.inherited.insert(id.clone(), instance);
+				}
+			}
+		}
+
+		for (id, modifiers) in modifiers {
+			let instance = self
+				.instances
+				.get_mut(&id)
+				.or_else(|| self.inherited.get_mut(&id));
+			if let Some(instance) = instance {
+				for (key, modifier) in modifiers {
+					instance.add_template_modifier(key, modifier);
+				}
+			}
+		}
+	}
+
+	/// The definition of `attribute` along the parent chain, with every template modifier.
+	pub(crate) fn resolve(&self, attribute: &A) -> Option<&AttributeInstance<A, M, V, O>> {
 		self.instances
 			.get(attribute)
+			.or_else(|| self.inherited.get(attribute))
+			.or_else(|| self.parent.as_ref()?.resolve(attribute))
+	}
+
+	pub(crate) fn value<S: AttributeStorage<A, AttributeInstance<A, M, V, O>>>(
+		&self,
+		attribute: &A,
+		attributes: &AttributeMap<A, M, V, O, S>,
+	) -> Option<V> {
+		self.resolve(attribute)
 			.map(|attr| attr.compute_value(attributes, false))
 	}
 
-	pub(crate) fn base_value(
+	pub(crate) fn base_value<S: AttributeStorage<A, AttributeInstance<A, M, V, O>>>(
 		&self,
 		attribute: &A,
-		attributes: &AttributeMap<A, M, V, O>,
+		attributes: &AttributeMap<A, M, V, O, S>,
 	) -> Option<V> {
-		self.instances
-			.get(attribute)
+		self.resolve(attribute)
 			.map(|attr| attr.compute_value(attributes, true))
 	}
-	// pub(crate) fn raw_value(&self, attribute: &A) -> Option<V> {
-	// 	self.instances.get(attribute).map(|attr| attr.raw_value())
-	// }
+	pub(crate) fn raw_value(&self, attribute: &A) -> Option<V> {
+		match self.instances.get(attribute) {
+			Some(instance) => Some(instance.raw_value()),
+			None => self.parent.as_ref()?.raw_value(attribute),
+		}
+	}
 }
 
 impl<A, M, V, O> Default for AttributeSupplier<A, M, V, O>
@@ -101,33 +445,74 @@ where
 {
 	fn default() -> Self {
 		Self {
+			parent: None,
 			instances: HashMap::new(),
+			order: Vec::new(),
+			tags: HashMap::new(),
+			costs: HashMap::new(),
+			currency: None,
+			visibility: HashMap::new(),
+			modifier_visibility: Vec::new(),
+			curves: HashMap::new(),
+			discrete: HashMap::new(),
+			inherited: HashMap::new(),
 		}
 	}
 }
 
+/// An attribute key enum that declares its own template supplier, implemented by
+/// `#[derive(AttributeKey)]`.
+pub trait AttributeTemplate: AttributeIndex + Hash + 'static {
+	type Value: Number + 'static;
+	type Operation: Op<Self::Value>;
+
+	/// Every key, in index order.
+	const ALL: &'static [Self];
+
+	/// The template supplier, unbuilt so it can be extended or handed to a
+	/// [`KindRegistry`](crate::kind::KindRegistry).
+	fn builder<M>() -> AttributeSupplierBuilder<Self, M, Self::Value, Self::Operation>
+	where
+		M: Key + From<Self> + From<(Self, usize)> + 'static;
+
+	/// Builds the template supplier.
+	#[must_use]
+	fn supplier<M>() -> AttributeSupplier<Self, M, Self::Value, Self::Operation>
+	where
+		M: Key + From<Self> + From<(Self, usize)> + 'static,
+	{
+		Self::builder::<M>().build()
+	}
+}
+
 #[cfg(test)]
 #[allow(clippy::float_cmp)]
 mod tests {
 	use std::sync::Arc;
 
 	use super::*;
-	use crate::prelude::{Attribute, AttributeModifier, Operation};
+	use crate::{
+		number::Rounding,
+		prelude::{Attribute, AttributeModifier, Operation},
+	};
 
 	#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 	#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 	enum TestAttribute {
 		Strength,
 		Agility,
+		Cunning,
 	}
 
 	#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 	#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 	enum TestModifier {
 		Buff,
+		Chief,
 	}
 
 	type MockSupplier = AttributeSupplier<TestAttribute, TestModifier, f32>;
+	type MockMap = AttributeMap<TestAttribute, TestModifier, f32>;
 
 	fn mock_supplier() -> Arc<MockSupplier> {
 		Arc::new(
@@ -164,9 +549,7 @@ mod tests {
 		let instance = supplier.create_instance(&TestAttribute::Strength);
 		assert!(instance.is_some());
 		assert_eq!(
-			instance
-				.unwrap()
-				.compute_value(&AttributeMap::default(), false),
+			instance.unwrap().compute_value(&MockMap::default(), false),
 			1.0
 		);
 
@@ -178,10 +561,10 @@ mod tests {
 	fn test_value_computation() {
 		let supplier = mock_supplier();
 
-		let value = supplier.value(&TestAttribute::Strength, &AttributeMap::default());
+		let value = supplier.value(&TestAttribute::Strength, &MockMap::default());
 		assert_eq!(value, Some(2.0));
 
-		let base_value = supplier.base_value(&TestAttribute::Strength, &AttributeMap::default());
+		let base_value = supplier.base_value(&TestAttribute::Strength, &MockMap::default());
 		assert_eq!(base_value, Some(1.0));
 	}
 
@@ -190,7 +573,7 @@ mod tests {
 		let supplier = MockSupplier::default();
 
 		// Testing for an attribute not in the supplier
-		let non_existent_value = supplier.value(&TestAttribute::Agility, &AttributeMap::default());
+		let non_existent_value = supplier.value(&TestAttribute::Agility, &MockMap::default());
 		assert_eq!(non_existent_value, None);
 	}
 
@@ -199,4 +582,163 @@ mod tests {
 		let supplier: MockSupplier = AttributeSupplier::default();
 		assert!(supplier.instances.is_empty());
 	}
+
+	#[test]
+	fn test_parent_chain() {
+		let goblin = mock_supplier();
+		let chief = Arc::new(
+			MockSupplier::extend(goblin.clone())
+				.add(TestAttribute::Agility, Attribute::Value(3.0))
+				.add(TestAttribute::Cunning, Attribute::Value(4.0))
+				.modifier(
+					TestAttribute::Strength,
+					TestModifier::Chief,
+					AttributeModifier::new(2.0, Operation::Add),
+				)
+				.build(),
+		);
+		let map = MockMap::default();
+
+		// Inherited, with the parent's and the child's template modifiers.
+		assert_eq!(chief.value(&TestAttribute::Strength, &map), Some(4.0));
+		assert_eq!(chief.raw_value(&TestAttribute::Strength), Some(1.0));
+		// Overridden and added by the child.
+		assert_eq!(chief.value(&TestAttribute::Agility, &map), Some(3.0));
+		assert_eq!(chief.value(&TestAttribute::Cunning, &map), Some(4.0));
+		// The merged definition is built once and borrowed from then on.
+		assert!(std::ptr::eq(
+			chief.resolve(&TestAttribute::Strength).unwrap(),
+			chief.inherited.get(&TestAttribute::Strength).unwrap(),
+		));
+		// The parent is unaffected.
+		assert_eq!(goblin.value(&TestAttribute::Strength, &map), Some(2.0));
+		assert_eq!(goblin.value(&TestAttribute::Cunning, &map), None);
+
+		let mut keys = chief.keys();
+		keys.sort_by_key(|id| format!("{id:?}"));
+		assert_eq!(
+			keys,
+			[
+				&TestAttribute::Agility,
+				&TestAttribute::Cunning,
+				&TestAttribute::Strength
+			]
+		);
+	}
+
+	#[test]
+	fn test_inherit() {
+		let chief = MockSupplier::builder()
+			.add(TestAttribute::Agility, Attribute::Value(3.0))
+			.inherit(&mock_supplier())
+			.build();
+		let map = MockMap::default();
+
+		assert!(chief.parent().is_none());
+		assert_eq!(chief.value(&TestAttribute::Strength, &map), Some(2.0));
+		assert_eq!(chief.value(&TestAttribute::Agility, &map), Some(3.0));
+		assert_eq!(
+			chief.keys(),
+			[&TestAttribute::Agility, &TestAttribute::Strength]
+		);
+	}
+
+	#[test]
+	fn test_map_materialises_from_parent() {
+		let chief = Arc::new(
+			MockSupplier::extend(mock_supplier())
+				.modifier(
+					TestAttribute::Strength,
+					TestModifier::Chief,
+					AttributeModifier::new(2.0, Operation::Add),
+				)
+				.build(),
+		);
+		let mut map = MockMap::new(chief);
+
+		map.set_raw_value(&TestAttribute::Strength, 1.0);
+		// Removing by key only touches runtime modifiers.
+		map.remove_modifier(&TestAttribute::Strength, &TestModifier::Buff);
+		assert_eq!(map.value(&TestAttribute::Strength), Some(4.0));
+		map.remove_template_modifier(&TestAttribute::Strength, &TestModifier::Buff);
+		assert_eq!(map.value(&TestAttribute::Strength), Some(3.0));
+		assert_eq!(map.snapshot().len(), 2);
+	}
+
+	#[test]
+	fn test_tags() {
+		let physical = Tag::new("physical");
+		let swift = Tag::new("swift");
+		let goblin = Arc::new(
+			MockSupplier::builder()
+				.add(TestAttribute::Agility, Attribute::Value(1.0))
+				.tag(TestAttribute::Agility, physical.clone())
+				.build(),
+		);
+		let scout = MockSupplier::extend(goblin)
+			.tag(TestAttribute::Agility, swift.clone())
+			.tag(TestAttribute::Agility, physical.clone())
+			.build();
+
+		assert!(scout.has_tag(&TestAttribute::Agility, &physical));
+		assert!(scout.has_tag(&TestAttribute::Agility, &swift));
+		assert!(!scout.has_tag(&TestAttribute::Strength, &physical));
+		assert_eq!(scout.tags(&TestAttribute::Agility), [&swift, &physical]);
+	}
+
+	#[test]
+	fn test_curves() {
+		let armour = Curve::Rational {
+			scale: 100.0,
+			k: 50.0,
+		};
+		let crit = Curve::SoftCap {
+			threshold: 30.0,
+			factor: 0.5,
+		};
+		let base = Arc::new(
+			AttributeSupplier::<TestAttribute, TestModifier, i32>::builder()
+				.add(TestAttribute::Strength, Attribute::Ranged(0, 0, 60))
+				.add(TestAttribute::Agility, Attribute::Value(20))
+				.curve(TestAttribute::Strength, armour.clone())
+				.curve(TestAttribute::Agility, crit.clone())
+				.build(),
+		);
+		let mut map: AttributeMap<_, _, i32> = AttributeMap::new(base.clone());
+		assert_eq!(map.value(&TestAttribute::Agility), Some(20));
+
+		let bonus = |amount| AttributeModifier::new(amount, Operation::Add);
+		map.add_modifier(&TestAttribute::Agility, TestModifier::Buff, bonus(30));
+		assert_eq!(map.value(&TestAttribute::Agility), Some(40));
+		// The curve applies before the range.
+		map.add_modifier(&TestAttribute::Strength, TestModifier::Buff, bonus(50));
+		assert_eq!(map.value(&TestAttribute::Strength), Some(50));
+		map.add_modifier(&TestAttribute::Strength, TestModifier::Chief, bonus(100));
+		assert_eq!(map.value(&TestAttribute::Strength), Some(60));
+
+		// A redefinition keeps the inherited curve unless it declares its own.
+		let steep = Curve::PiecewiseLinear(vec![(0.0, 0.0), (10.0, 20.0)]);
+		let child = AttributeSupplier::extend(base)
+			.add(
+				TestAttribute::Agility,
+				AttributeInstance::builder(Attribute::Value(35)).rounding(Rounding::Nearest),
+			)
+			.curve(TestAttribute::Strength, steep.clone())
+			.build();
+		assert_eq!(child.curve(&TestAttribute::Agility), Some(&crit));
+		assert_eq!(child.curve(&TestAttribute::Strength), Some(&steep));
+		let map: AttributeMap<_, _, i32> = AttributeMap::new(Arc::new(child));
+		// 32.5 is rounded instead of truncated.
+		assert_eq!(map.value(&TestAttribute::Agility), Some(33));
+		assert_eq!(map.value(&TestAttribute::Strength), Some(0));
+	}
+
+	#[test]
+	#[should_panic = "invalid curve"]
+	fn test_unsorted_curve() {
+		let _ = AttributeSupplier::<TestAttribute, TestModifier, i32>::builder().curve(
+			TestAttribute::Strength,
+			Curve::PiecewiseLinear(vec![(10.0, 0.0), (0.0, 5.0)]),
+		);
+	}
 }
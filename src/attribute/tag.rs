@@ -0,0 +1,65 @@
+//! Tags grouping attributes, e.g. "physical" or "resistance".
+
+use std::{borrow::Cow, fmt};
+
+/// A label an [`AttributeSupplier`](crate::attribute::supplier::AttributeSupplier) attaches to
+/// attributes so they can be queried and modified as a group.
+#[cfg_attr(
+	feature = "serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(transparent)
+)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Tag(Cow<'static, str>);
+
+impl Tag {
+	#[must_use]
+	pub const fn new(tag: &'static str) -> Self {
+		Self(Cow::Borrowed(tag))
+	}
+
+	#[must_use]
+	pub fn as_str(&self) -> &str {
+		&self.0
+	}
+}
+
+impl From<&'static str> for Tag {
+	fn from(tag: &'static str) -> Self {
+		Self::new(tag)
+	}
+}
+
+impl From<String> for Tag {
+	fn from(tag: String) -> Self {
+		Self(Cow::Owned(tag))
+	}
+}
+
+impl fmt::Display for Tag {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(&self.0)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const PHYSICAL: Tag = Tag::new("physical");
+
+	#[test]
+	fn test_tag_equality() {
+		assert_eq!(PHYSICAL, Tag::from(String::from("physical")));
+		assert_ne!(PHYSICAL, Tag::from("social"));
+		assert_eq!(PHYSICAL.to_string(), "physical");
+	}
+
+	#[cfg(feature = "serde")]
+	#[test]
+	fn test_tag_serde() {
+		let json = serde_json::to_string(&PHYSICAL).unwrap();
+		assert_eq!(json, "\"physical\"");
+		assert_eq!(serde_json::from_str::<Tag>(&json).unwrap(), PHYSICAL);
+	}
+}
@@ -0,0 +1,236 @@
+//! Hiding attributes and modifiers from players, e.g. hidden stats or GM-only modifiers.
+//!
+//! The [`AttributeSupplier`](crate::attribute::supplier::AttributeSupplier) declares a
+//! [`Visibility`] per attribute and per modifier key. Redacted exports leave out what the audience
+//! may not see, but values are always computed from the full map, so a visible attribute derived
+//! from a hidden one still shows its true value.
+
+use std::{borrow::Cow, collections::HashMap, hash::Hash};
+
+use crate::{
+	attribute::{
+		instance::AttributeInstance,
+		map::AttributeMap,
+		modifier::{AttributeModifier, Op},
+		storage::AttributeStorage,
+	},
+	util_traits::{Key, Number},
+};
+
+/// Who may see an attribute or modifier. An audience sees everything up to its own level.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Visibility {
+	/// Everyone, including other players.
+	#[default]
+	Public,
+	/// The player controlling the actor.
+	Owner,
+	/// The game master only.
+	GameMaster,
+}
+
+/// An attribute as seen by an audience, from [`AttributeMap::redact`].
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RedactedAttribute<A, M, V, O>
+where
+	V: 'static,
+	O: Op<V>,
+{
+	pub attribute: A,
+	pub raw_value: V,
+	/// The effective value, including the effect of modifiers left out.
+	pub value: V,
+	/// The visible modifiers, template ones first.
+	pub modifiers: Vec<(M, AttributeModifier<A, V, O>)>,
+}
+
+impl<A, M, V, O, S> AttributeMap<A, M, V, O, S>
+where
+	A: Key + Hash,
+	M: Key,
+	V: Number + 'static,
+	O: Op<V>,
+	S: AttributeStorage<A, AttributeInstance<A, M, V, O>>,
+{
+	/// Whether `audience` may see `attribute`. Without a supplier everything is public.
+	pub fn is_visible(&self, attribute: &A, audience: Visibility) -> bool {
+		self.visibility(attribute) <= audience
+	}
+
+	/// The [`snapshot`](Self::snapshot) of the attributes `audience` may see.
+	pub fn snapshot_for(&self, audience: Visibility) -> HashMap<A, V> {
+		self.iter()
+			.filter(|(id, _)| self.is_visible(id, audience))
+			.map(|(id, value)| (id.clone(), value))
+			.collect()
+	}
+
+	/// The attributes `audience` may see, with their visible modifiers.
+	///
+	/// Modifiers are left out if their key is hidden or their value or condition refers to a
+	/// hidden attribute.
+	pub fn redact(&self, audience: Visibility) -> Vec<RedactedAttribute<A, M, V, O>> {
+		self.keys()
+			.filter(|id| self.is_visible(id, audience))
+			.filter_map(|id| {
+				let instance = match self.instance(id) {
+					Some(instance) => Cow::Borrowed(instance),
+					None => Cow::Owned(self.supplier()?.create_instance(id)?),
+				};
+				let modifiers = instance
+					.modifiers()
+					.filter(|(key, modifier, _)| {
+						self.modifier_visibility(key) <= audience
+							&& modifier
+								.sources()
+								.all(|source| self.is_visible(source, audience))
+					})
+					.map(|(key, modifier, _)| (key.clone(), modifier.clone()))
+					.collect();
+				Some(RedactedAttribute {
+					attribute: id.clone(),
+					raw_value: instance.raw_value(),
+					value: self.value(id)?,
+					modifiers,
+				})
+			})
+			.collect()
+	}
+
+	fn visibility(&self, attribute: &A) -> Visibility {
+		self.supplier()
+			.map(|s| s.visibility(attribute))
+			.unwrap_or_default()
+	}
+
+	fn modifier_visibility(&self, key: &M) -> Visibility {
+		self.supplier()
+			.map(|s| s.modifier_visibility(key))
+			.unwrap_or_default()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use super::*;
+	use crate::{
+		attribute::{
+			Attribute,
+			modifier::{Condition, Value},
+			supplier::AttributeSupplier,
+		},
+		prelude::Operation,
+	};
+
+	#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+	#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+	enum TestAttribute {
+		Luck,
+		Wits,
+		Initiative,
+	}
+
+	#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+	#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+	enum TestModifier {
+		Luck,
+		Wits,
+		Curse,
+		Haste,
+		Omen,
+	}
+
+	type MockMap = AttributeMap<TestAttribute, TestModifier, i32>;
+
+	fn mock_map() -> MockMap {
+		let mut map = AttributeMap::new(Arc::new(
+			AttributeSupplier::builder()
+				.add(TestAttribute::Luck, Attribute::Value(3))
+				.add(TestAttribute::Wits, Attribute::Value(2))
+				.add(
+					TestAttribute::Initiative,
+					AttributeInstance::builder(Attribute::Derived)
+						.modifier(
+							TestModifier::Wits,
+							AttributeModifier::new(
+								Value::Attribute(TestAttribute::Wits),
+								Operation::Add,
+							),
+						)
+						.modifier(
+							TestModifier::Luck,
+							AttributeModifier::new(
+								Value::Attribute(TestAttribute::Luck),
+								Operation::Add,
+							),
+						),
+				)
+				.visibility(TestAttribute::Luck, Visibility::GameMaster)
+				.visibility(TestAttribute::Wits, Visibility::Owner)
+				.modifier_visibility(TestModifier::Curse, Visibility::GameMaster)
+				.build(),
+		));
+		map.add_modifier(
+			&TestAttribute::Initiative,
+			TestModifier::Curse,
+			AttributeModifier::new(4, Operation::Sub),
+		);
+		map.add_modifier(
+			&TestAttribute::Initiative,
+			TestModifier::Haste,
+			AttributeModifier::new(1, Operation::Add),
+		);
+		map
+	}
+
+	#[test]
+	fn test_snapshot_for() {
+		let map = mock_map();
+		assert_eq!(
+			map.snapshot_for(Visibility::Public),
+			HashMap::from([(TestAttribute::Initiative, 2)])
+		);
+		assert_eq!(map.snapshot_for(Visibility::Owner).len(), 2);
+		assert_eq!(map.snapshot_for(Visibility::GameMaster), map.snapshot());
+	}
+
+	#[test]
+	fn test_redact() {
+		let mut map = mock_map();
+		// Public, but its condition gives away the hidden luck.
+		map.add_modifier(
+			&TestAttribute::Initiative,
+			TestModifier::Omen,
+			AttributeModifier::new(0, Operation::Add)
+				.when(Condition::AtLeast(TestAttribute::Luck, 3)),
+		);
+
+		let public = map.redact(Visibility::Public);
+		assert_eq!(public.len(), 1);
+		let initiative = &public[0];
+		// Hidden luck and curse still count towards the value.
+		assert_eq!(initiative.value, 2);
+		let keys: Vec<_> = initiative.modifiers.iter().map(|(key, _)| key).collect();
+		assert_eq!(keys, [&TestModifier::Haste]);
+
+		let mut owner = map.redact(Visibility::Owner);
+		owner.sort_by_key(|a| format!("{:?}", a.attribute));
+		assert_eq!(owner[0].attribute, TestAttribute::Initiative);
+		let keys: Vec<_> = owner[0].modifiers.iter().map(|(key, _)| key).collect();
+		assert_eq!(keys, [&TestModifier::Wits, &TestModifier::Haste]);
+		assert_eq!(owner[1].attribute, TestAttribute::Wits);
+		assert_eq!(owner[1].value, 2);
+	}
+
+	#[cfg(feature = "serde")]
+	#[test]
+	fn test_serialised_export_is_redacted() {
+		let json = serde_json::to_string(&mock_map().redact(Visibility::Public)).unwrap();
+		assert!(json.contains("Haste"));
+		assert!(!json.contains("Luck") && !json.contains("Curse"), "{json}");
+	}
+}
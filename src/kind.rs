@@ -0,0 +1,256 @@
+//! Actor kinds (archetypes) and the template supplier each of them starts from.
+
+use std::{
+	collections::{HashMap, HashSet},
+	sync::Arc,
+};
+
+use crate::{
+	attribute::supplier::AttributeSupplierBuilder,
+	system::{SupplierOf, System},
+};
+
+type BuilderOf<S> = AttributeSupplierBuilder<
+	<S as System>::AttributeKey,
+	<S as System>::ModifierKey,
+	<S as System>::AttributeValue,
+	<S as System>::Operation,
+>;
+
+/// A declared kind, its parent kind and its own attributes.
+type Declaration<S> = (
+	<S as System>::Kind,
+	Option<<S as System>::Kind>,
+	BuilderOf<S>,
+);
+
+/// Shares one registry between every call of [`System::kinds`], building it with `$build` on
+/// first use:
+///
+/// ```ignore
+/// fn kinds() -> Option<Arc<KindRegistry<Self>>> {
+///     systema::shared_kinds!(Game, registry())
+/// }
+/// ```
+///
+/// The registry lives in a process-wide `static`, or in a `thread_local!` with the
+/// `single-threaded` feature since it is not `Sync` then.
+#[cfg(not(feature = "single-threaded"))]
+#[macro_export]
+macro_rules! shared_kinds {
+	($system:ty, $build:expr) => {{
+		static KINDS: ::std::sync::LazyLock<::std::sync::Arc<$crate::kind::KindRegistry<$system>>> =
+			::std::sync::LazyLock::new(|| ::std::sync::Arc::new($build));
+		::core::option::Option::Some(::std::sync::Arc::clone(&KINDS))
+	}};
+}
+
+#[cfg(feature = "single-threaded")]
+#[macro_export]
+macro_rules! shared_kinds {
+	($system:ty, $build:expr) => {{
+		::std::thread_local! {
+			static KINDS: ::std::sync::Arc<$crate::kind::KindRegistry<$system>> =
+				::std::sync::Arc::new($build);
+		}
+		::core::option::Option::Some(KINDS.with(::std::sync::Arc::clone))
+	}};
+}
+
+/// Maps every [`System::Kind`] to the [`AttributeSupplier`](crate::prelude::AttributeSupplier)
+/// its actors are created from.
+pub struct KindRegistry<S: System> {
+	suppliers: HashMap<S::Kind, Arc<SupplierOf<S>>>,
+}
+
+impl<S: System> KindRegistry<S> {
+	pub fn builder() -> KindRegistryBuilder<S> {
+		KindRegistryBuilder { kinds: Vec::new() }
+	}
+
+	pub fn supplier(&self, kind: &S::Kind) -> Option<&Arc<SupplierOf<S>>> {
+		self.suppliers.get(kind)
+	}
+
+	pub fn kinds(&self) -> impl Iterator<Item = &S::Kind> {
+		self.suppliers.keys()
+	}
+}
+
+#[must_use]
+pub struct KindRegistryBuilder<S: System> {
+	kinds: Vec<Declaration<S>>,
+}
+
+impl<S: System> KindRegistryBuilder<S> {
+	pub fn kind(mut self, kind: S::Kind, supplier: BuilderOf<S>) -> Self {
+		self.kinds.push((kind, None, supplier));
+		self
+	}
+
+	/// Declares `kind` as extending `parent`: its supplier uses `parent`'s as its
+	/// [parent](crate::attribute::supplier::AttributeSupplierBuilder::parent), so the attributes
+	/// added to `supplier` override or extend those of `parent`.
+	pub fn extends(mut self, kind: S::Kind, parent: S::Kind, supplier: BuilderOf<S>) -> Self {
+		self.kinds.push((kind, Some(parent), supplier));
+		self
+	}
+
+	/// # Panics
+	///
+	/// Panics if a kind is declared twice, if a kind extends a kind that was never declared, or if
+	/// kinds extend each other in a cycle.
+	#[must_use]
+	pub fn build(self) -> KindRegistry<S> {
+		let mut declared = HashSet::new();
+		for (kind, _, _) in &self.kinds {
+			assert!(declared.insert(kind), "kind registry declares a kind twice");
+		}
+
+		let mut pending = self.kinds;
+		let mut suppliers: HashMap<S::Kind, Arc<SupplierOf<S>>> = HashMap::new();
+
+		// Resolve kinds whose parent is already built until nothing is left.
+		while !pending.is_empty() {
+			let before = pending.len();
+			for (kind, parent, builder) in std::mem::take(&mut pending) {
+				match parent {
+					None => {
+						suppliers.insert(kind, Arc::new(builder.build()));
+					}
+					Some(parent) => match suppliers.get(&parent) {
+						Some(base) => {
+							let supplier = builder.parent(base.clone()).build();
+							suppliers.insert(kind, Arc::new(supplier));
+						}
+						None => pending.push((kind, Some(parent), builder)),
+					},
+				}
+			}
+			assert!(
+				pending.len() < before,
+				"kind registry has an undeclared or cyclic parent"
+			);
+		}
+
+		KindRegistry { suppliers }
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		actor::{Actor, BasicActor},
+		prelude::{Attribute, AttributeSupplier, Operation},
+	};
+
+	#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+	enum TestAttribute {
+		Strength,
+		Renown,
+	}
+
+	#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+	enum TestKind {
+		Human,
+		Werewolf,
+		Alpha,
+	}
+
+	struct TestSystem;
+
+	impl System for TestSystem {
+		type AttributeKey = TestAttribute;
+		type ModifierKey = ();
+		type AttributeValue = i32;
+		type Operation = Operation;
+
+		type Kind = TestKind;
+		type Actor = BasicActor<Self>;
+
+		fn kinds() -> Option<Arc<KindRegistry<Self>>> {
+			crate::shared_kinds!(
+				TestSystem,
+				// Declared child-first to exercise out-of-order resolution.
+				KindRegistry::builder()
+					.extends(
+						TestKind::Alpha,
+						TestKind::Werewolf,
+						AttributeSupplier::builder()
+							.add(TestAttribute::Renown, Attribute::Value(3)),
+					)
+					.extends(
+						TestKind::Werewolf,
+						TestKind::Human,
+						AttributeSupplier::builder()
+							.add(TestAttribute::Strength, Attribute::Value(2))
+							.add(TestAttribute::Renown, Attribute::Value(1)),
+					)
+					.kind(
+						TestKind::Human,
+						AttributeSupplier::builder()
+							.add(TestAttribute::Strength, Attribute::Value(1)),
+					)
+					.build()
+			)
+		}
+	}
+
+	#[test]
+	fn test_kind_selects_supplier() {
+		let human = BasicActor::<TestSystem>::new(TestKind::Human);
+		assert_eq!(human.value(&TestAttribute::Strength), Some(1));
+		assert_eq!(human.value(&TestAttribute::Renown), None);
+	}
+
+	#[test]
+	fn test_kind_inheritance() {
+		let werewolf = BasicActor::<TestSystem>::new(TestKind::Werewolf);
+		assert_eq!(werewolf.value(&TestAttribute::Strength), Some(2));
+		assert_eq!(werewolf.value(&TestAttribute::Renown), Some(1));
+
+		let alpha = BasicActor::<TestSystem>::new(TestKind::Alpha);
+		assert_eq!(alpha.value(&TestAttribute::Strength), Some(2));
+		assert_eq!(alpha.value(&TestAttribute::Renown), Some(3));
+	}
+
+	#[test]
+	#[should_panic = "undeclared or cyclic parent"]
+	fn test_undeclared_parent() {
+		let _ = KindRegistry::<TestSystem>::builder()
+			.extends(
+				TestKind::Werewolf,
+				TestKind::Human,
+				AttributeSupplier::builder(),
+			)
+			.build();
+	}
+
+	#[test]
+	#[should_panic = "declares a kind twice"]
+	fn test_duplicate_kind() {
+		let _ = KindRegistry::<TestSystem>::builder()
+			.kind(TestKind::Human, AttributeSupplier::builder())
+			.kind(TestKind::Human, AttributeSupplier::builder())
+			.build();
+	}
+
+	#[test]
+	fn test_no_kinds() {
+		struct KindlessSystem;
+
+		impl System for KindlessSystem {
+			type AttributeKey = TestAttribute;
+			type ModifierKey = ();
+			type AttributeValue = i32;
+			type Operation = Operation;
+
+			type Kind = ();
+			type Actor = BasicActor<Self>;
+		}
+
+		let actor = BasicActor::<KindlessSystem>::new(());
+		assert_eq!(actor.value(&TestAttribute::Strength), None);
+	}
+}
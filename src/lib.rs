@@ -1,20 +1,51 @@
+//! # Features
+//!
+//! - `serde`: serialisation of maps, suppliers and modifiers.
+//! - `replication`: compact binary deltas for keeping client copies of a map in sync.
+//! - `derive`: `#[derive(AttributeKey, ModifierKey, System)]`.
+//! - `single-threaded`: replaces the atomic value caches with plain cells. This is **not
+//!   additive**: maps, suppliers and kind registries stop being `Sync`, so
+//!   `SharedAttributeMap`, `static` registries and anything else that shares them between
+//!   threads no longer compiles. Only the final binary should enable it, never a library
+//!   that other crates depend on.
+
 #![warn(clippy::pedantic)]
+// Suppliers are still shared through `Arc`, even when the instance caches are not `Sync`.
+#![cfg_attr(feature = "single-threaded", allow(clippy::arc_with_non_send_sync))]
 
 pub mod actor;
+pub mod advancement;
 pub mod attribute;
+pub mod kind;
+pub mod number;
+pub mod prediction;
+#[cfg(feature = "replication")]
+pub mod replication;
+pub mod roll;
 pub mod system;
 mod util_traits;
+pub mod validation;
+
+#[cfg(feature = "derive")]
+pub use systema_derive::{AttributeKey, ModifierKey, System};
 
 pub mod prelude {
 	pub use crate::{
-		actor::Actor,
+		actor::{Actor, BasicActor},
 		attribute::{
 			Attribute,
 			instance::AttributeInstance,
-			modifier::{AttributeModifier, Operation, Value},
-			supplier::{AttributeSupplier, AttributeSupplierBuilder},
+			modifier::{
+				ArithmeticOp, AttributeModifier, Condition, ModifierOrigin, Operation, Value,
+			},
+			storage::AttributeIndex,
+			supplier::{AttributeSupplier, AttributeSupplierBuilder, AttributeTemplate},
+			tag::Tag,
+			visibility::Visibility,
 		},
-		system::System,
+		kind::KindRegistry,
+		number::{Arithmetic, Curve, Fixed, Rounding, Vector, VectorOp},
+		system::{SupplierOf, System},
 	};
 
 	pub type AttributeMap<S> = crate::attribute::map::AttributeMap<
@@ -24,34 +55,40 @@ pub mod prelude {
 		<S as System>::Operation,
 	>;
 
-	// pub type AttributeSupplier<S> = crate::attribute::supplier::AttributeSupplier<
-	// 	<S as System>::AttributeKey,
-	// 	<S as System>::ModifierKey,
-	// 	<S as System>::AttributeValue,
-	// >;
+	pub type DenseAttributeMap<S> = crate::attribute::map::DenseAttributeMap<
+		<S as System>::AttributeKey,
+		<S as System>::ModifierKey,
+		<S as System>::AttributeValue,
+		<S as System>::Operation,
+	>;
+
+	#[cfg(not(feature = "single-threaded"))]
+	pub type SharedAttributeMap<S> = crate::attribute::shared::SharedAttributeMap<
+		<S as System>::AttributeKey,
+		<S as System>::ModifierKey,
+		<S as System>::AttributeValue,
+		<S as System>::Operation,
+	>;
 }
 
 #[cfg(test)]
 mod tests {
-	use std::collections::HashMap;
+	use std::mem::size_of;
 
 	use super::prelude::*;
+	use crate::attribute::cache::ValueCache;
 
-	macro_rules! psize_of {
-		($t:ty) => {
-			eprintln!("{}: {} bytes", stringify!($t), std::mem::size_of::<$t>());
-		};
+	#[test]
+	fn test_value_cache_is_no_larger_than_an_option() {
+		assert_eq!(size_of::<ValueCache<u8>>(), size_of::<Option<u8>>());
+		assert_eq!(size_of::<ValueCache<f32>>(), size_of::<Option<f32>>());
+		assert_eq!(size_of::<ValueCache<f64>>(), size_of::<Option<f64>>());
 	}
 
 	#[test]
-	fn wwww() {
-		// psize_of!(attribute::map::AttributeMap<&str, &str, u8>);
-		//
-		// psize_of!(AttributeInstance<&str, &str, u8>);
-
-		psize_of!(HashMap<&str, AttributeModifier<&str, u8>>);
-		psize_of!(Vec<(&str, AttributeModifier<&str, u8>)>);
-
-		psize_of!(Attribute<u8>);
+	#[cfg(target_pointer_width = "64")]
+	fn test_instance_size() {
+		assert_eq!(size_of::<AttributeInstance<&str, &str, u8>>(), 152);
+		assert_eq!(size_of::<AttributeInstance<&str, &str, f32>>(), 176);
 	}
 }
@@ -0,0 +1,146 @@
+use crate::number::Arithmetic;
+
+/// A transform applied to an attribute's value after its modifiers and before its range, e.g.
+/// diminishing returns on armour rating or a soft cap on critical chance.
+///
+/// Curves are evaluated in [`f64`]. Attributes that compute in `f64` anyway, see
+/// [`AttributeBuilder::rounding`](crate::attribute::instance::AttributeBuilder::rounding), are
+/// shaped before they are rounded; others convert like [`Arithmetic::from_f64`].
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Curve {
+	/// Straight segments through points sorted by distinct inputs, continued along the first and
+	/// last segment beyond the ends. A single point maps everything to its output.
+	PiecewiseLinear(Vec<(f64, f64)>),
+	/// `scale * x / (x + k)`, approaching `scale` and reaching half of it at `x = k`. Negative
+	/// `x` counts as zero.
+	Rational { scale: f64, k: f64 },
+	/// Unchanged up to `threshold`; above it only `factor` of the excess counts.
+	SoftCap { threshold: f64, factor: f64 },
+}
+
+impl Curve {
+	/// Whether every parameter is finite, `k` is positive and the points have strictly
+	/// increasing inputs. Other curves give meaningless values.
+	#[must_use]
+	pub fn is_valid(&self) -> bool {
+		match self {
+			Self::PiecewiseLinear(points) => {
+				points.iter().all(|(x, y)| x.is_finite() && y.is_finite())
+					&& points.windows(2).all(|pair| pair[0].0 < pair[1].0)
+			}
+			Self::Rational { scale, k } => scale.is_finite() && k.is_finite() && *k > 0.0,
+			Self::SoftCap { threshold, factor } => threshold.is_finite() && factor.is_finite(),
+		}
+	}
+
+	#[must_use]
+	pub fn apply(&self, x: f64) -> f64 {
+		match self {
+			Self::PiecewiseLinear(points) => match points.as_slice() {
+				[] => x,
+				[(_, y)] => *y,
+				_ => {
+					let i = points
+						.partition_point(|(px, _)| *px <= x)
+						.clamp(1, points.len() - 1);
+					let ((x0, y0), (x1, y1)) = (points[i - 1], points[i]);
+					y0 + (x - x0) * (y1 - y0) / (x1 - x0)
+				}
+			},
+			Self::Rational { scale, k } => {
+				let x = x.max(0.0);
+				scale * x / (x + k)
+			}
+			Self::SoftCap { threshold, factor } => {
+				if x > *threshold {
+					threshold + (x - threshold) * factor
+				} else {
+					x
+				}
+			}
+		}
+	}
+}
+
+/// A [`Curve`] with the conversions to apply it to `V`, see [`Widening`](super::Widening).
+pub(crate) struct Shaping<V> {
+	curve: Curve,
+	to_f64: fn(V) -> f64,
+	from_f64: fn(f64) -> V,
+}
+
+impl<V> Shaping<V> {
+	pub(crate) fn new(curve: Curve) -> Self
+	where
+		V: Arithmetic,
+	{
+		Self {
+			curve,
+			to_f64: V::to_f64,
+			from_f64: V::from_f64,
+		}
+	}
+
+	pub(crate) fn curve(&self) -> &Curve {
+		&self.curve
+	}
+
+	pub(crate) fn apply(&self, value: V) -> V {
+		(self.from_f64)(self.curve.apply((self.to_f64)(value)))
+	}
+}
+
+impl<V> Clone for Shaping<V> {
+	fn clone(&self) -> Self {
+		Self {
+			curve: self.curve.clone(),
+			to_f64: self.to_f64,
+			from_f64: self.from_f64,
+		}
+	}
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_piecewise_linear() {
+		let curve = Curve::PiecewiseLinear(vec![(0.0, 0.0), (100.0, 50.0), (200.0, 75.0)]);
+		assert_eq!(curve.apply(50.0), 25.0);
+		assert_eq!(curve.apply(100.0), 50.0);
+		assert_eq!(curve.apply(150.0), 62.5);
+		assert_eq!(curve.apply(300.0), 100.0);
+		assert_eq!(curve.apply(-10.0), -5.0);
+
+		assert_eq!(Curve::PiecewiseLinear(vec![]).apply(3.0), 3.0);
+		assert_eq!(Curve::PiecewiseLinear(vec![(1.0, 7.0)]).apply(3.0), 7.0);
+
+		assert!(curve.is_valid());
+		assert!(!Curve::PiecewiseLinear(vec![(1.0, 0.0), (1.0, 2.0)]).is_valid());
+		assert!(!Curve::PiecewiseLinear(vec![(0.0, f64::NAN)]).is_valid());
+		assert!(!Curve::Rational { scale: 1.0, k: 0.0 }.is_valid());
+	}
+
+	#[test]
+	fn test_rational_and_soft_cap() {
+		let curve = Curve::Rational {
+			scale: 100.0,
+			k: 50.0,
+		};
+		assert_eq!(curve.apply(0.0), 0.0);
+		assert_eq!(curve.apply(50.0), 50.0);
+		assert_eq!(curve.apply(150.0), 75.0);
+		assert_eq!(curve.apply(-50.0), 0.0);
+		assert_eq!(curve.apply(-80.0), 0.0);
+
+		let curve = Curve::SoftCap {
+			threshold: 30.0,
+			factor: 0.5,
+		};
+		assert_eq!(curve.apply(20.0), 20.0);
+		assert_eq!(curve.apply(50.0), 40.0);
+	}
+}
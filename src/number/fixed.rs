@@ -0,0 +1,327 @@
+use std::{
+	fmt,
+	ops::{Add, Div, Mul, Neg, Sub},
+};
+
+use crate::number::Arithmetic;
+
+/// A signed fixed-point number with 32 integer and 32 fractional bits.
+///
+/// All arithmetic is done on integers, so results are bit-for-bit identical on every platform,
+/// as deterministic lockstep requires.
+///
+/// # Rounding
+///
+/// Addition and subtraction are exact. Multiplication and division round to the nearest
+/// representable value, halfway cases away from zero, so a percentage modifier of `×1.5` on
+/// `Fixed::from_bits(1)` gives `2` bits. Every operation saturates at [`MIN`](Self::MIN) and
+/// [`MAX`](Self::MAX) instead of overflowing. Dividing by zero gives the bound matching the sign
+/// of the dividend, or zero for `0 / 0`.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fixed(i64);
+
+impl Fixed {
+	pub const FRAC_BITS: u32 = 32;
+	pub const ZERO: Self = Self(0);
+	pub const ONE: Self = Self(1 << Self::FRAC_BITS);
+	pub const MIN: Self = Self(i64::MIN);
+	pub const MAX: Self = Self(i64::MAX);
+
+	/// The number whose raw representation is `bits`, i.e. `bits / 2^32`.
+	#[must_use]
+	pub const fn from_bits(bits: i64) -> Self {
+		Self(bits)
+	}
+
+	#[must_use]
+	pub const fn to_bits(self) -> i64 {
+		self.0
+	}
+
+	#[must_use]
+	pub const fn from_int(value: i32) -> Self {
+		Self((value as i64) << Self::FRAC_BITS)
+	}
+
+	/// `numerator / denominator`, rounded like [division](Self#rounding).
+	#[must_use]
+	pub fn from_ratio(numerator: i32, denominator: i32) -> Self {
+		Self::from_int(numerator) / Self::from_int(denominator)
+	}
+
+	/// The largest integer not above `self`.
+	#[must_use]
+	pub const fn floor(self) -> Self {
+		Self(self.0 & !((1 << Self::FRAC_BITS) - 1))
+	}
+
+	/// The nearest integer, halfway cases away from zero.
+	#[must_use]
+	pub fn round(self) -> Self {
+		let half = 1 << (Self::FRAC_BITS - 1);
+		if self.0 < 0 {
+			-(-self).round()
+		} else {
+			Self(self.0.saturating_add(half)).floor()
+		}
+	}
+
+	/// The integer part, rounded towards negative infinity.
+	#[must_use]
+	pub const fn to_int(self) -> i64 {
+		self.0 >> Self::FRAC_BITS
+	}
+}
+
+/// `n / d` rounded to the nearest integer, halfway cases away from zero.
+fn div_round(n: i128, d: i128) -> i128 {
+	let (q, r) = (n / d, n % d);
+	if 2 * r.abs() >= d.abs() {
+		if (n < 0) == (d < 0) { q + 1 } else { q - 1 }
+	} else {
+		q
+	}
+}
+
+fn saturate(bits: i128) -> Fixed {
+	Fixed(i64::try_from(bits).unwrap_or(if bits < 0 { i64::MIN } else { i64::MAX }))
+}
+
+impl Add for Fixed {
+	type Output = Self;
+
+	fn add(self, rhs: Self) -> Self {
+		Self(self.0.saturating_add(rhs.0))
+	}
+}
+
+impl Sub for Fixed {
+	type Output = Self;
+
+	fn sub(self, rhs: Self) -> Self {
+		Self(self.0.saturating_sub(rhs.0))
+	}
+}
+
+impl Mul for Fixed {
+	type Output = Self;
+
+	fn mul(self, rhs: Self) -> Self {
+		saturate(div_round(
+			i128::from(self.0) * i128::from(rhs.0),
+			1 << Self::FRAC_BITS,
+		))
+	}
+}
+
+impl Div for Fixed {
+	type Output = Self;
+
+	fn div(self, rhs: Self) -> Self {
+		match (self.0, rhs.0) {
+			(0, 0) => Self::ZERO,
+			(n, 0) if n < 0 => Self::MIN,
+			(_, 0) => Self::MAX,
+			(n, d) => saturate(div_round(i128::from(n) << Self::FRAC_BITS, i128::from(d))),
+		}
+	}
+}
+
+impl Neg for Fixed {
+	type Output = Self;
+
+	fn neg(self) -> Self {
+		Self(self.0.saturating_neg())
+	}
+}
+
+macro_rules! impl_from_int {
+	($($t:ty),*) => {$(
+		impl From<$t> for Fixed {
+			fn from(value: $t) -> Self {
+				Self::from_int(value.into())
+			}
+		}
+	)*};
+}
+
+impl_from_int!(u8, u16, i8, i16, i32);
+
+impl Arithmetic for Fixed {
+	#[allow(clippy::cast_precision_loss)]
+	fn to_f64(self) -> f64 {
+		self.0 as f64 / (1u64 << Self::FRAC_BITS) as f64
+	}
+
+	/// Rounds `value` to the nearest representable number, halfway cases away from zero.
+	#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+	fn from_f64(value: f64) -> Self {
+		// Scaling by a power of two is exact, and `as` saturates and maps NaN to zero.
+		Self((value * (1u64 << Self::FRAC_BITS) as f64).round() as i64)
+	}
+
+	fn checked_add(self, rhs: Self) -> Option<Self> {
+		self.0.checked_add(rhs.0).map(Self)
+	}
+
+	fn checked_sub(self, rhs: Self) -> Option<Self> {
+		self.0.checked_sub(rhs.0).map(Self)
+	}
+
+	fn checked_mul(self, rhs: Self) -> Option<Self> {
+		let bits = div_round(i128::from(self.0) * i128::from(rhs.0), 1 << Self::FRAC_BITS);
+		i64::try_from(bits).ok().map(Self)
+	}
+
+	fn checked_div(self, rhs: Self) -> Option<Self> {
+		if rhs.0 == 0 {
+			return None;
+		}
+		let bits = div_round(i128::from(self.0) << Self::FRAC_BITS, i128::from(rhs.0));
+		i64::try_from(bits).ok().map(Self)
+	}
+
+	/// Computed on the raw bits and rounded once, like [multiplication](Self#rounding).
+	fn percent(self, percent: Self) -> Self {
+		saturate(div_round(
+			i128::from(self.0) * i128::from(percent.0),
+			100 << Self::FRAC_BITS,
+		))
+	}
+}
+
+impl fmt::Debug for Fixed {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "Fixed({})", self.to_f64())
+	}
+}
+
+impl fmt::Display for Fixed {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		fmt::Display::fmt(&self.to_f64(), f)
+	}
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+	use std::sync::Arc;
+
+	use super::*;
+	use crate::{
+		attribute::{
+			Attribute, instance::AttributeInstance, map::AttributeMap, modifier::AttributeModifier,
+			supplier::AttributeSupplier,
+		},
+		prelude::{ArithmeticOp, Value},
+	};
+
+	#[test]
+	fn test_arithmetic() {
+		let half = Fixed::from_ratio(1, 2);
+		assert_eq!(half.to_f64(), 0.5);
+		assert_eq!(Fixed::from(3) * half, Fixed::from_ratio(3, 2));
+		assert_eq!(Fixed::from(3) / Fixed::from(4), Fixed::from_f64(0.75));
+		assert_eq!(Fixed::from(-7).to_int(), -7);
+		assert_eq!(Fixed::from_f64(-2.5).floor(), Fixed::from(-3));
+		assert_eq!(Fixed::from_f64(-2.5).round(), Fixed::from(-3));
+		assert_eq!(Fixed::from_f64(2.5).round(), Fixed::from(3));
+		assert_eq!(format!("{}", Fixed::from_f64(1.25)), "1.25");
+	}
+
+	#[test]
+	fn test_rounding_and_saturation() {
+		let bit = Fixed::from_bits(1);
+		assert_eq!(bit * Fixed::from_f64(1.5), Fixed::from_bits(2));
+		assert_eq!(-bit * Fixed::from_f64(1.5), Fixed::from_bits(-2));
+		assert_eq!(bit * half(), Fixed::from_bits(1));
+		assert_eq!(bit * Fixed::from_f64(0.25), Fixed::ZERO);
+		// A third is not representable; thirds still add up to one within a bit.
+		let third = Fixed::ONE / Fixed::from(3);
+		assert_eq!(
+			third + third + third,
+			Fixed::from_bits(Fixed::ONE.to_bits() - 1)
+		);
+
+		assert_eq!(Fixed::MAX + Fixed::ONE, Fixed::MAX);
+		assert_eq!(Fixed::MIN - Fixed::ONE, Fixed::MIN);
+		assert_eq!(Fixed::MAX * Fixed::from(2), Fixed::MAX);
+		assert_eq!(Fixed::from(-1) / Fixed::ZERO, Fixed::MIN);
+		assert_eq!(Fixed::ZERO / Fixed::ZERO, Fixed::ZERO);
+		assert_eq!(-Fixed::MIN, Fixed::MAX);
+
+		assert_eq!(Fixed::MAX.checked_add(Fixed::ONE), None);
+		assert_eq!(Fixed::MAX.checked_mul(Fixed::from(2)), None);
+		assert_eq!(Fixed::ONE.checked_div(Fixed::ZERO), None);
+		assert_eq!(
+			Fixed::from(3).checked_div(Fixed::from(4)),
+			Some(Fixed::from_f64(0.75))
+		);
+
+		assert_eq!(bit.percent(Fixed::from(150)), Fixed::from_bits(2));
+		assert_eq!(Fixed::MAX.percent(Fixed::from(200)), Fixed::MAX);
+	}
+
+	fn half() -> Fixed {
+		Fixed::from_ratio(1, 2)
+	}
+
+	/// A long chain of operations must give the same bits on every run and platform.
+	#[test]
+	fn test_determinism() {
+		let rate = Fixed::from_ratio(107, 100);
+		let mut value = Fixed::from(1000);
+		for i in 1..=200 {
+			value =
+				value * rate / Fixed::from_ratio(i % 7 + 1, i % 5 + 1) + Fixed::from_ratio(i, 9);
+			value = value - value / Fixed::from(3);
+		}
+		assert_eq!(value.to_bits(), 110_572_541_742);
+	}
+
+	#[test]
+	fn test_fixed_attributes() {
+		#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+		enum TestAttribute {
+			Gold,
+			Income,
+		}
+
+		let supplier = AttributeSupplier::builder()
+			.add(TestAttribute::Gold, Attribute::Value(Fixed::from(40)))
+			.add(
+				TestAttribute::Income,
+				AttributeInstance::builder(Attribute::Derived)
+					.modifier(
+						"gold",
+						AttributeModifier::new(
+							Value::Attribute(TestAttribute::Gold),
+							ArithmeticOp::Add,
+						),
+					)
+					.modifier(
+						"interest",
+						AttributeModifier::new(Fixed::from_ratio(3, 100), ArithmeticOp::Mul),
+					),
+			)
+			.build();
+		let mut map: AttributeMap<_, &str, Fixed, ArithmeticOp> =
+			AttributeMap::new(Arc::new(supplier));
+		// 3/100 is rounded to the nearest bit before it is multiplied.
+		let income = Fixed::from(40) * Fixed::from_ratio(3, 100);
+		assert_eq!(map.value(&TestAttribute::Income), Some(income));
+		assert!((income.to_f64() - 1.2).abs() < 1e-8);
+
+		map.add_modifier(
+			&TestAttribute::Income,
+			"split",
+			AttributeModifier::new(Fixed::from(4), ArithmeticOp::Div),
+		);
+		assert_eq!(
+			map.value(&TestAttribute::Income),
+			Some(income / Fixed::from(4))
+		);
+	}
+}
@@ -0,0 +1,153 @@
+//! Value types beyond the primitive numbers, and the arithmetic [`ArithmeticOp`]s need.
+//!
+//! [`ArithmeticOp::Mul`] and [`ArithmeticOp::Div`] require an [`Arithmetic`] value type. It is
+//! implemented for the primitive numbers and for [`Fixed`], a fixed-point type computing
+//! identically on every platform. Values must be `Copy`, so arbitrary-precision types need a
+//! fixed-size representation, e.g. a mantissa and exponent, to implement [`Arithmetic`].
+//!
+//! [`ArithmeticOp`]: crate::attribute::modifier::ArithmeticOp
+//! [`ArithmeticOp::Mul`]: crate::attribute::modifier::ArithmeticOp::Mul
+//! [`ArithmeticOp::Div`]: crate::attribute::modifier::ArithmeticOp::Div
+
+use std::ops::{Div, Mul};
+
+use crate::util_traits::Number;
+
+mod curve;
+mod fixed;
+mod rounding;
+mod vector;
+
+pub use curve::Curve;
+pub(crate) use curve::Shaping;
+pub use fixed::Fixed;
+pub use rounding::Rounding;
+pub(crate) use rounding::Widening;
+pub use vector::{Vector, VectorOp};
+
+/// A `Number` that can also be multiplied, divided and converted to and from [`f64`].
+pub trait Arithmetic: Number + Mul<Output = Self> + Div<Output = Self> {
+	fn to_f64(self) -> f64;
+
+	/// Converts `value`, saturating at the bounds of `Self`. Integers truncate towards zero, and
+	/// NaN becomes zero.
+	fn from_f64(value: f64) -> Self;
+
+	/// `self + rhs`, or `None` if it overflows or, for floats, is not finite.
+	fn checked_add(self, rhs: Self) -> Option<Self>;
+
+	/// `self - rhs`, or `None` if it overflows or, for floats, is not finite.
+	fn checked_sub(self, rhs: Self) -> Option<Self>;
+
+	/// `self * rhs`, or `None` if it overflows or, for floats, is not finite.
+	fn checked_mul(self, rhs: Self) -> Option<Self>;
+
+	/// `self / rhs`, or `None` if `rhs` is zero or the result overflows or, for floats, is not
+	/// finite.
+	fn checked_div(self, rhs: Self) -> Option<Self>;
+
+	/// `self` scaled to `percent` percent, e.g. `150` for a 50% bonus. By default computed in
+	/// [`f64`] so small integer types do not overflow, then converted like
+	/// [`from_f64`](Self::from_f64).
+	#[must_use]
+	fn percent(self, percent: Self) -> Self {
+		Self::from_f64(self.to_f64() * percent.to_f64() / 100.0)
+	}
+}
+
+macro_rules! impl_conversions {
+	($t:ty) => {
+		#[allow(clippy::cast_precision_loss, clippy::cast_lossless)]
+		fn to_f64(self) -> f64 {
+			self as f64
+		}
+
+		#[allow(
+			clippy::cast_possible_truncation,
+			clippy::cast_sign_loss,
+			clippy::cast_precision_loss
+		)]
+		fn from_f64(value: f64) -> Self {
+			value as $t
+		}
+	};
+}
+
+macro_rules! impl_integer {
+	($($t:ty),*) => {$(
+		impl Arithmetic for $t {
+			impl_conversions!($t);
+
+			fn checked_add(self, rhs: Self) -> Option<Self> {
+				<$t>::checked_add(self, rhs)
+			}
+
+			fn checked_sub(self, rhs: Self) -> Option<Self> {
+				<$t>::checked_sub(self, rhs)
+			}
+
+			fn checked_mul(self, rhs: Self) -> Option<Self> {
+				<$t>::checked_mul(self, rhs)
+			}
+
+			fn checked_div(self, rhs: Self) -> Option<Self> {
+				<$t>::checked_div(self, rhs)
+			}
+		}
+	)*};
+}
+
+macro_rules! impl_float {
+	($($t:ty),*) => {$(
+		impl Arithmetic for $t {
+			impl_conversions!($t);
+
+			fn checked_add(self, rhs: Self) -> Option<Self> {
+				Some(self + rhs).filter(|v| v.is_finite())
+			}
+
+			fn checked_sub(self, rhs: Self) -> Option<Self> {
+				Some(self - rhs).filter(|v| v.is_finite())
+			}
+
+			fn checked_mul(self, rhs: Self) -> Option<Self> {
+				Some(self * rhs).filter(|v| v.is_finite())
+			}
+
+			fn checked_div(self, rhs: Self) -> Option<Self> {
+				Some(self / rhs).filter(|v| v.is_finite())
+			}
+		}
+	)*};
+}
+
+impl_integer!(
+	u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize
+);
+impl_float!(f32, f64);
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_f64_conversions() {
+		assert_eq!(u8::from_f64(300.0), u8::MAX);
+		assert_eq!(i32::from_f64(-2.7), -2);
+		assert_eq!(i32::from_f64(f64::NAN), 0);
+		assert_eq!(Arithmetic::to_f64(3u8), 3.0);
+		assert_eq!(f32::from_f64(0.5), 0.5);
+	}
+
+	#[test]
+	fn test_checked() {
+		assert_eq!(Arithmetic::checked_mul(200u8, 2), None);
+		assert_eq!(Arithmetic::checked_sub(0u8, 1), None);
+		assert_eq!(Arithmetic::checked_div(7, 0), None);
+		assert_eq!(Arithmetic::checked_add(2, 3), Some(5));
+		assert_eq!(Arithmetic::checked_mul(f32::MAX, 2.0), None);
+		assert_eq!(Arithmetic::checked_div(1.0, 0.0), None);
+		assert_eq!(Arithmetic::checked_div(1.0, 4.0), Some(0.25));
+	}
+}
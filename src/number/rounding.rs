@@ -0,0 +1,96 @@
+use crate::{attribute::modifier::Op, number::Arithmetic};
+
+/// How a value computed in [`f64`] is rounded back to an integer.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Rounding {
+	/// Towards negative infinity, e.g. halving rounds down.
+	#[default]
+	Floor,
+	/// Towards positive infinity.
+	Ceil,
+	/// To the nearest integer, halfway cases away from zero.
+	Nearest,
+	/// To the nearest integer, halfway cases to the even one.
+	Banker,
+}
+
+impl Rounding {
+	#[must_use]
+	pub fn apply(self, value: f64) -> f64 {
+		match self {
+			Self::Floor => value.floor(),
+			Self::Ceil => value.ceil(),
+			Self::Nearest => value.round(),
+			Self::Banker => value.round_ties_even(),
+		}
+	}
+}
+
+/// Computes an attribute in [`f64`] and rounds the result back to `V`.
+///
+/// Holds the conversions as function pointers, so that instances of any `V` can carry one and
+/// only [`AttributeBuilder::rounding`](crate::attribute::instance::AttributeBuilder::rounding)
+/// needs `V` to be [`Arithmetic`].
+pub(crate) struct Widening<V, O> {
+	rounding: Rounding,
+	to_f64: fn(V) -> f64,
+	from_f64: fn(f64) -> V,
+	apply: fn(&O, f64, f64) -> f64,
+}
+
+impl<V, O> Widening<V, O> {
+	pub(crate) fn new(rounding: Rounding) -> Self
+	where
+		V: Arithmetic,
+		O: Op<f64>,
+	{
+		Self {
+			rounding,
+			to_f64: V::to_f64,
+			from_f64: V::from_f64,
+			apply: O::apply,
+		}
+	}
+
+	pub(crate) fn widen(&self, value: V) -> f64 {
+		(self.to_f64)(value)
+	}
+
+	pub(crate) fn narrow(&self, value: f64) -> V {
+		(self.from_f64)(self.rounding.apply(value))
+	}
+
+	pub(crate) fn apply(&self, op: &O, a: f64, b: f64) -> f64 {
+		(self.apply)(op, a, b)
+	}
+}
+
+impl<V, O> Clone for Widening<V, O> {
+	fn clone(&self) -> Self {
+		*self
+	}
+}
+
+impl<V, O> Copy for Widening<V, O> {}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_rounding() {
+		let modes = [
+			Rounding::Floor,
+			Rounding::Ceil,
+			Rounding::Nearest,
+			Rounding::Banker,
+		];
+		let round = |value| modes.map(|mode| mode.apply(value));
+		assert_eq!(round(2.5), [2.0, 3.0, 3.0, 2.0]);
+		assert_eq!(round(3.5), [3.0, 4.0, 4.0, 4.0]);
+		assert_eq!(round(-2.5), [-3.0, -2.0, -3.0, -2.0]);
+		assert_eq!(round(1.2), [1.0, 2.0, 1.0, 1.0]);
+	}
+}
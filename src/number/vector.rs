@@ -0,0 +1,303 @@
+use std::{
+	cmp::Ordering,
+	ops::{Add, Index, IndexMut, Sub},
+};
+
+use crate::{
+	attribute::{clamp, modifier::Op},
+	util_traits::Number,
+};
+
+/// A fixed number of components computed side by side, e.g. a resistance per damage type, an RGB
+/// tint or a 2D speed.
+///
+/// Addition and subtraction are component-wise. Vectors are only partially ordered: one vector is
+/// less than another if none of its components is greater and at least one is less, so
+/// `[1, 3]` and `[2, 2]` are not comparable. With [`VectorOp`],
+/// [`Attribute::Ranged`](crate::attribute::Attribute::Ranged) therefore [clamps](Self::clamp) each
+/// component rather than comparing whole vectors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Vector<V, const N: usize>(pub [V; N]);
+
+impl<V: Number, const N: usize> Vector<V, N> {
+	#[must_use]
+	pub const fn new(components: [V; N]) -> Self {
+		Self(components)
+	}
+
+	/// A vector with every component set to `value`.
+	#[must_use]
+	pub fn splat(value: V) -> Self {
+		Self([value; N])
+	}
+
+	#[must_use]
+	pub const fn as_array(&self) -> &[V; N] {
+		&self.0
+	}
+
+	/// Clamps each component between the matching components of `min` and `max`.
+	#[must_use]
+	pub fn clamp(mut self, min: Self, max: Self) -> Self {
+		for (value, (min, max)) in self.0.iter_mut().zip(min.0.into_iter().zip(max.0)) {
+			*value = clamp(*value, min, max);
+		}
+		self
+	}
+
+	fn zip(mut self, other: Self, f: impl Fn(V, V) -> V) -> Self {
+		for (a, b) in self.0.iter_mut().zip(other.0) {
+			*a = f(*a, b);
+		}
+		self
+	}
+}
+
+impl<V: Number, const N: usize> Default for Vector<V, N> {
+	fn default() -> Self {
+		Self::splat(V::default())
+	}
+}
+
+impl<V: Number, const N: usize> From<[V; N]> for Vector<V, N> {
+	fn from(components: [V; N]) -> Self {
+		Self(components)
+	}
+}
+
+impl<V, const N: usize> Index<usize> for Vector<V, N> {
+	type Output = V;
+
+	fn index(&self, index: usize) -> &V {
+		&self.0[index]
+	}
+}
+
+impl<V, const N: usize> IndexMut<usize> for Vector<V, N> {
+	fn index_mut(&mut self, index: usize) -> &mut V {
+		&mut self.0[index]
+	}
+}
+
+impl<V: Number, const N: usize> Add for Vector<V, N> {
+	type Output = Self;
+
+	fn add(self, rhs: Self) -> Self {
+		self.zip(rhs, |a, b| a + b)
+	}
+}
+
+impl<V: Number, const N: usize> Sub for Vector<V, N> {
+	type Output = Self;
+
+	fn sub(self, rhs: Self) -> Self {
+		self.zip(rhs, |a, b| a - b)
+	}
+}
+
+impl<V: Number, const N: usize> PartialOrd for Vector<V, N> {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		self.0
+			.iter()
+			.zip(&other.0)
+			.try_fold(Ordering::Equal, |ordering, (a, b)| {
+				match (ordering, a.partial_cmp(b)?) {
+					(ordering, Ordering::Equal) => Some(ordering),
+					(Ordering::Equal, component) => Some(component),
+					(ordering, component) if ordering == component => Some(ordering),
+					_ => None,
+				}
+			})
+	}
+}
+
+#[cfg(feature = "serde")]
+impl<V: serde::Serialize, const N: usize> serde::Serialize for Vector<V, N> {
+	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_seq(&self.0)
+	}
+}
+
+#[cfg(feature = "serde")]
+impl<'de, V: serde::Deserialize<'de>, const N: usize> serde::Deserialize<'de> for Vector<V, N> {
+	fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let components = Vec::<V>::deserialize(deserializer)?;
+		let len = components.len();
+		components
+			.try_into()
+			.map(Self)
+			.map_err(|_| serde::de::Error::invalid_length(len, &N.to_string().as_str()))
+	}
+}
+
+/// An [`Op`] on [`Vector`] values, applying a scalar operation to every component or to one.
+///
+/// The modifier's value is a vector either way, and a component operation only reads the
+/// matching component of it, so the same derived vector can feed several component modifiers.
+/// Operations on a component past the end leave the value unchanged.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum VectorOp<O = crate::attribute::modifier::Operation> {
+	All(O),
+	Component(usize, O),
+}
+
+impl<V: Number, O: Op<V>, const N: usize> Op<Vector<V, N>> for VectorOp<O> {
+	fn apply(&self, mut a: Vector<V, N>, b: Vector<V, N>) -> Vector<V, N> {
+		match self {
+			Self::All(op) => a.zip(b, |a, b| op.apply(a, b)),
+			Self::Component(index, op) => {
+				if let Some(component) = a.0.get_mut(*index) {
+					*component = op.apply(*component, b.0[*index]);
+				}
+				a
+			}
+		}
+	}
+
+	fn clamp(value: Vector<V, N>, min: Vector<V, N>, max: Vector<V, N>) -> Vector<V, N> {
+		value.clamp(min, max)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use super::*;
+	use crate::{
+		attribute::{
+			Attribute, instance::AttributeInstance, map::AttributeMap, modifier::AttributeModifier,
+			supplier::AttributeSupplier,
+		},
+		prelude::{ArithmeticOp, Value},
+		validation::RuleSet,
+	};
+
+	type Resistances = Vector<i32, 3>;
+
+	const FIRE: usize = 0;
+	const COLD: usize = 1;
+
+	#[test]
+	fn test_partial_order() {
+		let v = Vector::new([1, 2]);
+		assert!(v < Vector::new([1, 3]));
+		assert!(v <= Vector::new([1, 2]));
+		assert!(v > Vector::new([0, 2]));
+		assert_eq!(v.partial_cmp(&Vector::new([2, 1])), None);
+		assert_eq!(
+			Vector::new([5, -1, 2]).clamp(Vector::splat(0), Vector::splat(3)),
+			Vector::new([3, 0, 2])
+		);
+	}
+
+	#[test]
+	fn test_vector_ops() {
+		let v = Vector::new([1, 2, 3]);
+		let op = VectorOp::All(ArithmeticOp::Mul);
+		assert_eq!(op.apply(v, Vector::splat(2)), Vector::new([2, 4, 6]));
+		let op = VectorOp::Component(2, ArithmeticOp::Sub);
+		assert_eq!(op.apply(v, Vector::new([9, 9, 1])), Vector::new([1, 2, 2]));
+		let op = VectorOp::Component(3, ArithmeticOp::Add);
+		assert_eq!(op.apply(v, Vector::splat(1)), v);
+	}
+
+	#[test]
+	fn test_vector_attributes() {
+		#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+		enum TestAttribute {
+			Innate,
+			Resistance,
+		}
+
+		let supplier = AttributeSupplier::builder()
+			.add(
+				TestAttribute::Innate,
+				Attribute::Value(Vector::new([10, 0, 5])),
+			)
+			.add(
+				TestAttribute::Resistance,
+				AttributeInstance::builder(Attribute::Derived).modifier(
+					"innate",
+					AttributeModifier::new(
+						Value::Attribute(TestAttribute::Innate),
+						VectorOp::All(ArithmeticOp::Add),
+					),
+				),
+			)
+			.build();
+		let mut map: AttributeMap<_, &str, Resistances, VectorOp<ArithmeticOp>> =
+			AttributeMap::new(Arc::new(supplier));
+
+		map.add_modifier(
+			&TestAttribute::Resistance,
+			"ring",
+			AttributeModifier::new(Vector::splat(2), VectorOp::All(ArithmeticOp::Add)),
+		);
+		map.add_modifier(
+			&TestAttribute::Resistance,
+			"frost ward",
+			AttributeModifier::new(
+				Vector::new([0, 15, 0]),
+				VectorOp::Component(COLD, ArithmeticOp::Add),
+			),
+		);
+		map.add_modifier(
+			&TestAttribute::Resistance,
+			"wet",
+			AttributeModifier::new(
+				Vector::splat(2),
+				VectorOp::Component(FIRE, ArithmeticOp::Mul),
+			),
+		);
+		assert_eq!(
+			map.value(&TestAttribute::Resistance),
+			Some(Vector::new([24, 17, 7]))
+		);
+
+		map.set_raw_value(&TestAttribute::Innate, Vector::new([0, 0, 0]));
+		assert_eq!(
+			map.value(&TestAttribute::Resistance),
+			Some(Vector::new([4, 17, 2]))
+		);
+	}
+
+	#[test]
+	fn test_ranged_vectors_clamp_each_component() {
+		#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+		enum TestAttribute {
+			Speed,
+		}
+
+		let supplier = AttributeSupplier::builder()
+			.add(
+				TestAttribute::Speed,
+				Attribute::Ranged(Vector::splat(2), Vector::splat(2), Vector::splat(4)),
+			)
+			.build();
+		let mut map: AttributeMap<_, &str, Vector<i32, 2>, VectorOp> =
+			AttributeMap::new(Arc::new(supplier));
+
+		// Neither above nor below the range as a whole.
+		map.set_raw_value(&TestAttribute::Speed, Vector::new([1, 5]));
+		assert_eq!(map.value(&TestAttribute::Speed), Some(Vector::new([2, 4])));
+		assert_eq!(RuleSet::new().bounds().validate(&map).unwrap_err().len(), 1);
+
+		map.set_raw_value(&TestAttribute::Speed, Vector::new([3, 4]));
+		assert_eq!(map.value(&TestAttribute::Speed), Some(Vector::new([3, 4])));
+		assert!(RuleSet::new().bounds().validate(&map).is_ok());
+	}
+
+	#[cfg(feature = "serde")]
+	#[test]
+	fn test_serde() {
+		let json = serde_json::to_string(&Vector::new([1, 2, 3])).unwrap();
+		assert_eq!(json, "[1,2,3]");
+		assert_eq!(
+			serde_json::from_str::<Resistances>(&json).unwrap(),
+			Vector::new([1, 2, 3])
+		);
+		assert!(serde_json::from_str::<Resistances>("[1,2]").is_err());
+	}
+}
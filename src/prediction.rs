@@ -0,0 +1,311 @@
+//! Client-side prediction of attribute mutations, reconciled with the authoritative state the
+//! server sends back.
+//!
+//! A [`Predictor`] applies each local mutation, expressed as an [`AttributeEvent`], to its
+//! predicted map right away and tags it with a [`Sequence`] number sent to the server along with
+//! it. When the server's state arrives together with the last sequence number it processed, the
+//! predictor adopts that state and re-applies the mutations the server has not seen yet.
+//!
+//! The predicted map journals the pending mutations, so a delta from the server is applied after
+//! undoing them rather than to a second copy of the map. Only mutations the journal cannot
+//! undo, like a [`Reset`](AttributeEvent::Reset), make the predictor keep such a copy while they
+//! are pending.
+
+use std::hash::Hash;
+
+#[cfg(feature = "replication")]
+use crate::replication::Delta;
+use crate::{
+	attribute::{
+		event::AttributeEvent, instance::AttributeInstance, map::AttributeMap, modifier::Op,
+		storage::AttributeStorage,
+	},
+	util_traits::{Key, Number},
+};
+
+/// The number a [`Predictor`] tags a mutation with, counting up from 1.
+pub type Sequence = u64;
+
+/// A mutation and the sequence number it was predicted with.
+pub type Prediction<A, M, V, O> = (Sequence, AttributeEvent<A, M, V, O>);
+
+/// A client's copy of an [`AttributeMap`], with mutations predicted ahead of the server.
+#[derive(Clone, derive_more::Debug)]
+pub struct Predictor<A, M, V, O, S>
+where
+	A: Key + Hash + 'static,
+	M: Key + 'static,
+	V: Number + 'static,
+	O: Op<V>,
+	S: AttributeStorage<A, AttributeInstance<A, M, V, O>>,
+{
+	/// The authoritative state with the pending mutations applied. Its journal holds exactly
+	/// their edits, unless one of them could not be undone.
+	predicted: AttributeMap<A, M, V, O, S>,
+	/// The authoritative state, kept only while a pending mutation cannot be undone.
+	checkpoint: Option<AttributeMap<A, M, V, O, S>>,
+	/// Mutations the server has not acknowledged yet, oldest first.
+	pending: Vec<Prediction<A, M, V, O>>,
+	last: Sequence,
+}
+
+impl<A, M, V, O, S> Predictor<A, M, V, O, S>
+where
+	A: Key + Hash,
+	M: Key,
+	V: Number,
+	O: Op<V>,
+	S: AttributeStorage<A, AttributeInstance<A, M, V, O>> + Clone,
+{
+	/// Starts predicting from `map`, the state shared with the server. Its undo history is
+	/// dropped.
+	pub fn new(mut map: AttributeMap<A, M, V, O, S>) -> Self {
+		map.enable_journal();
+		map.discard_journal();
+		Self {
+			predicted: map,
+			checkpoint: None,
+			pending: Vec::new(),
+			last: 0,
+		}
+	}
+
+	/// The predicted state, to be shown to the player.
+	pub fn map(&self) -> &AttributeMap<A, M, V, O, S> {
+		&self.predicted
+	}
+
+	/// The last state received from the server, rebuilt by undoing the pending mutations on a
+	/// copy of the predicted map.
+	pub fn authoritative(&self) -> AttributeMap<A, M, V, O, S> {
+		self.checkpoint.clone().unwrap_or_else(|| {
+			let mut authoritative = self.predicted.clone();
+			authoritative.rewind(0);
+			authoritative
+		})
+	}
+
+	/// The mutations not yet acknowledged by the server, oldest first.
+	pub fn pending(&self) -> &[Prediction<A, M, V, O>] {
+		&self.pending
+	}
+
+	/// Applies `event` to the predicted map and returns the sequence number to send it with.
+	pub fn predict(&mut self, event: AttributeEvent<A, M, V, O>) -> Sequence {
+		self.last += 1;
+		self.push((self.last, event));
+		self.last
+	}
+
+	/// Adopts `state` from the server, which includes every mutation up to `acknowledged`, and
+	/// re-applies the later ones on top of it.
+	///
+	/// The predicted map is rebuilt from `state`, so values cached for a misprediction never
+	/// survive a reconciliation.
+	pub fn reconcile(&mut self, state: AttributeMap<A, M, V, O, S>, acknowledged: Sequence) {
+		self.predicted = state;
+		self.checkpoint = None;
+		self.replay(acknowledged);
+	}
+
+	/// Like [`reconcile`](Self::reconcile), with the server's state sent as a [`Delta`] from
+	/// the previous one.
+	#[cfg(feature = "replication")]
+	pub fn reconcile_delta(&mut self, delta: Delta<A, M, V, O>, acknowledged: Sequence)
+	where
+		O: PartialEq,
+	{
+		match self.checkpoint.take() {
+			Some(checkpoint) => self.predicted = checkpoint,
+			None => self.predicted.rewind(0),
+		}
+		delta.apply(&mut self.predicted);
+		self.replay(acknowledged);
+	}
+
+	/// Re-applies the mutations after `acknowledged` to the authoritative state in the predicted
+	/// map.
+	fn replay(&mut self, acknowledged: Sequence) {
+		self.predicted.enable_journal();
+		self.predicted.discard_journal();
+		let pending = std::mem::take(&mut self.pending);
+		for prediction in pending {
+			if prediction.0 > acknowledged {
+				self.push(prediction);
+			}
+		}
+	}
+
+	/// Applies a prediction to the predicted map, first copying the authoritative state if the
+	/// journal cannot undo it.
+	fn push(&mut self, (sequence, event): Prediction<A, M, V, O>) {
+		if self.checkpoint.is_none() && !event.is_undoable() {
+			self.checkpoint = Some(self.authoritative());
+		}
+		self.predicted.apply(&event);
+		self.pending.push((sequence, event));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use super::*;
+	use crate::{
+		attribute::{
+			Attribute, map::AttributeMap, modifier::AttributeModifier, supplier::AttributeSupplier,
+		},
+		prelude::{Operation, Value},
+	};
+
+	#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+	#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+	enum TestAttribute {
+		Mana,
+		Power,
+	}
+
+	#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+	#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+	enum TestModifier {
+		Mana,
+		Focus,
+		Silence,
+	}
+
+	type MockMap = AttributeMap<TestAttribute, TestModifier, i32>;
+	type MockEvent = AttributeEvent<TestAttribute, TestModifier, i32, Operation>;
+
+	fn mock_map() -> MockMap {
+		AttributeMap::new(Arc::new(
+			AttributeSupplier::builder()
+				.add(TestAttribute::Mana, Attribute::Value(10))
+				.add(
+					TestAttribute::Power,
+					AttributeInstance::builder(Attribute::Derived).modifier(
+						TestModifier::Mana,
+						AttributeModifier::new(
+							Value::Attribute(TestAttribute::Mana),
+							Operation::Add,
+						),
+					),
+				)
+				.build(),
+		))
+	}
+
+	fn focus() -> MockEvent {
+		AttributeEvent::AddModifier {
+			attribute: TestAttribute::Power,
+			key: TestModifier::Focus,
+			modifier: AttributeModifier::new(5, Operation::Add),
+		}
+	}
+
+	fn spend(mana: i32) -> MockEvent {
+		AttributeEvent::SetRawValue {
+			attribute: TestAttribute::Mana,
+			value: mana,
+		}
+	}
+
+	#[test]
+	fn test_reconcile() {
+		let mut server = mock_map();
+		let mut client = Predictor::new(mock_map());
+
+		let first = client.predict(focus());
+		let second = client.predict(spend(4));
+		assert_eq!((first, second), (1, 2));
+		assert_eq!(client.map().value(&TestAttribute::Power), Some(9));
+
+		// The server processed the first mutation, and silenced the player meanwhile.
+		server.apply(&focus());
+		server.add_modifier(
+			&TestAttribute::Power,
+			TestModifier::Silence,
+			AttributeModifier::new(20, Operation::Sub),
+		);
+		client.reconcile(server.clone(), first);
+
+		assert_eq!(client.pending().len(), 1);
+		assert_eq!(
+			client.authoritative().value(&TestAttribute::Power),
+			Some(-5)
+		);
+		assert_eq!(client.map().value(&TestAttribute::Power), Some(-11));
+
+		server.apply(&spend(4));
+		client.reconcile(server.clone(), second);
+		assert!(client.pending().is_empty());
+		assert_eq!(
+			client.map().value(&TestAttribute::Power),
+			server.value(&TestAttribute::Power)
+		);
+	}
+
+	#[test]
+	fn test_misprediction_is_rolled_back() {
+		let mut client = Predictor::new(mock_map());
+		let sequence = client.predict(spend(0));
+		assert_eq!(client.map().value(&TestAttribute::Power), Some(0));
+
+		// The server rejected the mutation but acknowledges having seen it.
+		client.reconcile(mock_map(), sequence);
+		assert_eq!(client.map().value(&TestAttribute::Power), Some(10));
+		assert_eq!(client.map().raw_value(&TestAttribute::Mana), Some(10));
+	}
+
+	#[test]
+	fn test_rewinds_on_the_journal() {
+		let mut client = Predictor::new(mock_map());
+		client.predict(focus());
+		client.predict(spend(4));
+		assert!(client.checkpoint.is_none());
+		assert_eq!(
+			client.authoritative().value(&TestAttribute::Power),
+			Some(10)
+		);
+
+		// A reset cannot be undone, so the authoritative state is kept aside until it is
+		// acknowledged.
+		let reset = client.predict(AttributeEvent::Reset(TestAttribute::Mana));
+		assert!(client.checkpoint.is_some());
+		assert_eq!(client.map().value(&TestAttribute::Power), Some(15));
+		assert_eq!(
+			client.authoritative().value(&TestAttribute::Power),
+			Some(10)
+		);
+
+		let mut server = mock_map();
+		for (_, event) in client.pending() {
+			server.apply(event);
+		}
+		client.predict(spend(2));
+		client.reconcile(server, reset);
+		assert!(client.checkpoint.is_none());
+		assert_eq!(client.map().value(&TestAttribute::Power), Some(7));
+	}
+
+	#[cfg(feature = "replication")]
+	#[test]
+	fn test_reconcile_delta() {
+		let mut server = mock_map();
+		let mut sent = server.clone();
+		let mut client = Predictor::new(mock_map());
+
+		let sequence = client.predict(focus());
+		client.predict(spend(6));
+
+		server.apply(&focus());
+		client.reconcile_delta(Delta::between(&sent, &server), sequence);
+		sent.clone_from(&server);
+		assert_eq!(client.pending().len(), 1);
+		assert_eq!(client.map().value(&TestAttribute::Power), Some(11));
+
+		server.apply(&spend(6));
+		client.reconcile_delta(Delta::between(&sent, &server), sequence + 1);
+		assert_eq!(client.map().snapshot(), server.snapshot());
+	}
+}
@@ -0,0 +1,679 @@
+//! Keeping client copies of an [`AttributeMap`] in sync with the server's, by sending compact
+//! binary deltas between two states of it.
+//!
+//! A [`Delta`] carries changed raw values and added or removed modifiers, so the client map can
+//! compute every value itself, and the changed [discrete](crate::attribute::discrete) attributes
+//! whole. A [`ValueDelta`] only carries changed effective values, for
+//! clients that merely display them.
+//!
+//! The server keeps the state it last sent to a client, e.g. a clone of the map, and diffs the
+//! current map against it. Timers and tag modifiers are not replicated, only their effect on
+//! instances, so a client map should be changed by applying deltas alone.
+
+use std::{collections::HashMap, hash::Hash};
+
+use bincode::Options;
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{
+	attribute::{
+		discrete::DiscreteInstance,
+		instance::AttributeInstance,
+		map::AttributeMap,
+		modifier::{AttributeModifier, ModifierOrigin, Op},
+		storage::AttributeStorage,
+	},
+	util_traits::{Key, Number},
+};
+
+/// A change to the modifiers of one attribute.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum ModifierChange<A, M, V, O>
+where
+	V: 'static,
+	O: Op<V>,
+{
+	/// Removes the runtime modifiers with this key.
+	Remove(M),
+	/// Appends a runtime modifier.
+	Add(M, AttributeModifier<A, V, O>),
+	/// Replaces all modifiers of an origin, when the change cannot be expressed more compactly.
+	Replace(ModifierOrigin, Vec<(M, AttributeModifier<A, V, O>)>),
+}
+
+/// The changes to a single attribute.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct AttributeDelta<A, M, V, O>
+where
+	V: 'static,
+	O: Op<V>,
+{
+	pub attribute: A,
+	/// The raw value and whether it was [set](AttributeInstance::is_raw_value_set), if either
+	/// changed.
+	pub raw_value: Option<(V, bool)>,
+	pub modifiers: Vec<ModifierChange<A, M, V, O>>,
+}
+
+/// The changes turning one state of an [`AttributeMap`] into another.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Delta<A, M, V, O>
+where
+	V: 'static,
+	O: Op<V>,
+{
+	pub attributes: Vec<AttributeDelta<A, M, V, O>>,
+	/// Discrete attributes whose state or overrides changed, as they are now.
+	pub states: Vec<(A, DiscreteInstance<M>)>,
+	/// Attributes that fell back to the supplier's definition, e.g. after an undo.
+	pub dematerialised: Vec<A>,
+}
+
+impl<A, M, V, O> Delta<A, M, V, O>
+where
+	A: Key + Hash,
+	M: Key,
+	V: Number,
+	O: Op<V> + PartialEq,
+{
+	/// The changes from `old` to `new`, two states of the same map.
+	pub fn between<S>(old: &AttributeMap<A, M, V, O, S>, new: &AttributeMap<A, M, V, O, S>) -> Self
+	where
+		S: AttributeStorage<A, AttributeInstance<A, M, V, O>>,
+	{
+		let mut attributes = Vec::new();
+		for (id, instance) in new.instances() {
+			// An attribute the client has not materialised yet starts out from the supplier.
+			let template = match old.instance(id) {
+				Some(_) => None,
+				None => new.supplier().and_then(|s| s.create_instance(id)),
+			};
+			let Some(old) = old.instance(id).or(template.as_ref()) else {
+				continue;
+			};
+
+			let raw_value = (old.raw_value() != instance.raw_value()
+				|| old.is_raw_value_set() != instance.is_raw_value_set())
+			.then(|| (instance.raw_value(), instance.is_raw_value_set()));
+			let mut modifiers = Vec::new();
+			let old_template: Vec<_> = old.template_modifiers().collect();
+			let new_template: Vec<_> = instance.template_modifiers().collect();
+			if old_template != new_template {
+				modifiers.push(ModifierChange::Replace(
+					ModifierOrigin::Template,
+					owned(&new_template),
+				));
+			}
+			diff_runtime(
+				&old.runtime_modifiers().collect::<Vec<_>>(),
+				&instance.runtime_modifiers().collect::<Vec<_>>(),
+				&mut modifiers,
+			);
+
+			if raw_value.is_some() || !modifiers.is_empty() {
+				attributes.push(AttributeDelta {
+					attribute: id.clone(),
+					raw_value,
+					modifiers,
+				});
+			}
+		}
+
+		let states = new
+			.discrete_instances()
+			.filter(|(id, instance)| match old.discrete(id) {
+				Some(old) => old != *instance,
+				// An attribute the client has not materialised yet starts out from the supplier.
+				None => new
+					.supplier()
+					.and_then(|s| s.discrete_state(id))
+					.is_none_or(|state| *instance != &DiscreteInstance::new(state.clone())),
+			})
+			.map(|(id, instance)| (id.clone(), instance.clone()))
+			.collect();
+
+		let mut dematerialised: Vec<A> = old
+			.instances()
+			.map(|(id, _)| id)
+			.filter(|id| !new.has_attribute(id))
+			.cloned()
+			.collect();
+		for (id, _) in old.discrete_instances() {
+			if new.discrete(id).is_none() && !dematerialised.contains(id) {
+				dematerialised.push(id.clone());
+			}
+		}
+
+		Self {
+			attributes,
+			states,
+			dematerialised,
+		}
+	}
+
+	#[must_use]
+	pub fn is_empty(&self) -> bool {
+		self.attributes.is_empty() && self.states.is_empty() && self.dematerialised.is_empty()
+	}
+
+	/// Patches `map`, which must be in the state this delta was taken from.
+	pub fn apply<S>(self, map: &mut AttributeMap<A, M, V, O, S>)
+	where
+		S: AttributeStorage<A, AttributeInstance<A, M, V, O>>,
+	{
+		for id in &self.dematerialised {
+			map.dematerialise(id);
+		}
+		for delta in self.attributes {
+			map.patch(&delta.attribute, |instance| {
+				if let Some((raw_value, set)) = delta.raw_value {
+					instance.restore_raw_value(raw_value, set);
+				}
+				for change in delta.modifiers {
+					match change {
+						ModifierChange::Remove(key) => {
+							instance.remove_modifier(&key);
+						}
+						ModifierChange::Add(key, modifier) => instance.add_modifier(key, modifier),
+						ModifierChange::Replace(origin, modifiers) => {
+							instance.replace_modifiers(origin, modifiers);
+						}
+					}
+				}
+			});
+		}
+		for (id, instance) in self.states {
+			map.put_discrete(id, instance);
+		}
+	}
+}
+
+/// Appends the changes from the runtime modifiers `old` to `new`: removals of the keys whose
+/// modifiers changed, then additions. Falls back to a replacement if that does not reproduce the
+/// order of `new`.
+fn diff_runtime<A, M, V, O>(
+	old: &[(&M, &AttributeModifier<A, V, O>)],
+	new: &[(&M, &AttributeModifier<A, V, O>)],
+	changes: &mut Vec<ModifierChange<A, M, V, O>>,
+) where
+	A: Key,
+	M: Key,
+	V: Number,
+	O: Op<V> + PartialEq,
+{
+	if old == new {
+		return;
+	}
+
+	let mut removed: Vec<&M> = Vec::new();
+	for (key, _) in old {
+		if removed.contains(key) {
+			continue;
+		}
+		let (before, after) = (keyed(old, key), keyed(new, key));
+		if !after.starts_with(&before) {
+			removed.push(key);
+		}
+	}
+
+	let kept: Vec<_> = old.iter().filter(|(m, _)| !removed.contains(m)).collect();
+	if new.len() >= kept.len() && kept.iter().zip(new).all(|(a, b)| *a == b) {
+		changes.extend(
+			removed
+				.into_iter()
+				.map(|m| ModifierChange::Remove(m.clone())),
+		);
+		changes.extend(
+			new[kept.len()..]
+				.iter()
+				.map(|(m, v)| ModifierChange::Add((*m).clone(), (*v).clone())),
+		);
+	} else {
+		changes.push(ModifierChange::Replace(ModifierOrigin::Runtime, owned(new)));
+	}
+}
+
+/// The modifiers keyed `key` in `list`.
+fn keyed<'a, A, M: PartialEq, V, O: Op<V>>(
+	list: &[(&M, &'a AttributeModifier<A, V, O>)],
+	key: &M,
+) -> Vec<&'a AttributeModifier<A, V, O>> {
+	list.iter()
+		.filter(|(m, _)| *m == key)
+		.map(|(_, v)| *v)
+		.collect()
+}
+
+fn owned<A, M, V, O>(
+	modifiers: &[(&M, &AttributeModifier<A, V, O>)],
+) -> Vec<(M, AttributeModifier<A, V, O>)>
+where
+	A: Clone,
+	M: Clone,
+	V: Clone,
+	O: Op<V>,
+{
+	modifiers
+		.iter()
+		.map(|(m, v)| ((*m).clone(), (*v).clone()))
+		.collect()
+}
+
+/// The changed effective values of an [`AttributeMap`], for clients that only display them.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ValueDelta<A, V> {
+	pub changed: Vec<(A, V)>,
+	pub removed: Vec<A>,
+}
+
+impl<A, V> ValueDelta<A, V>
+where
+	A: Key + Hash,
+	V: Number,
+{
+	/// The values of `new` that differ from those of `old`.
+	pub fn between<M, O, S>(
+		old: &AttributeMap<A, M, V, O, S>,
+		new: &AttributeMap<A, M, V, O, S>,
+	) -> Self
+	where
+		M: Key,
+		O: Op<V>,
+		S: AttributeStorage<A, AttributeInstance<A, M, V, O>>,
+	{
+		let old = old.snapshot();
+		let new = new.snapshot();
+		let changed = new
+			.iter()
+			.filter(|(id, value)| old.get(*id) != Some(*value))
+			.map(|(id, value)| (id.clone(), *value))
+			.collect();
+		let removed = old.into_keys().filter(|id| !new.contains_key(id)).collect();
+		Self { changed, removed }
+	}
+
+	#[must_use]
+	pub fn is_empty(&self) -> bool {
+		self.changed.is_empty() && self.removed.is_empty()
+	}
+
+	/// Patches `values`, e.g. a client's copy of a [`snapshot`](AttributeMap::snapshot).
+	pub fn apply(self, values: &mut HashMap<A, V>) {
+		for id in &self.removed {
+			values.remove(id);
+		}
+		values.extend(self.changed);
+	}
+}
+
+/// The largest delta, in bytes, that `encode` writes and `decode` reads.
+///
+/// Longer input is rejected before it is decoded, and decoding stops once a length prefix claims
+/// more, so a malformed or hostile delta cannot make the client allocate much beyond it. Use
+/// `decode_with_limit` for larger deltas.
+pub const MAX_DELTA_SIZE: u64 = 1 << 20;
+
+/// Deltas are encoded with variable-length integers, so small values and short lists take a byte
+/// or two.
+fn options(limit: u64) -> impl Options {
+	bincode::DefaultOptions::new().with_limit(limit)
+}
+
+/// Bincode does not count variable-length integers towards its limit, so the input is measured
+/// up front.
+fn check_size(bytes: &[u8], limit: u64) -> bincode::Result<()> {
+	if bytes.len() as u64 > limit {
+		return Err(Box::new(bincode::ErrorKind::SizeLimit));
+	}
+	Ok(())
+}
+
+impl<A, M, V, O> Delta<A, M, V, O>
+where
+	A: Serialize + DeserializeOwned,
+	M: Serialize + DeserializeOwned,
+	V: Serialize + DeserializeOwned + 'static,
+	O: Op<V> + Serialize + DeserializeOwned,
+{
+	/// # Errors
+	///
+	/// Fails if a key or value cannot be serialised, or if the delta is larger than
+	/// [`MAX_DELTA_SIZE`].
+	pub fn encode(&self) -> bincode::Result<Vec<u8>> {
+		options(MAX_DELTA_SIZE).serialize(self)
+	}
+
+	/// # Errors
+	///
+	/// Fails if `bytes` are not an encoded delta of these types, or are longer than
+	/// [`MAX_DELTA_SIZE`].
+	pub fn decode(bytes: &[u8]) -> bincode::Result<Self> {
+		Self::decode_with_limit(bytes, MAX_DELTA_SIZE)
+	}
+
+	/// Like [`decode`](Self::decode), reading at most `limit` bytes.
+	///
+	/// # Errors
+	///
+	/// Fails if `bytes` are not an encoded delta of these types, or are longer than `limit`.
+	pub fn decode_with_limit(bytes: &[u8], limit: u64) -> bincode::Result<Self> {
+		check_size(bytes, limit)?;
+		options(limit).deserialize(bytes)
+	}
+}
+
+impl<A, V> ValueDelta<A, V>
+where
+	A: Serialize + DeserializeOwned,
+	V: Serialize + DeserializeOwned,
+{
+	/// # Errors
+	///
+	/// Fails if a key or value cannot be serialised, or if the delta is larger than
+	/// [`MAX_DELTA_SIZE`].
+	pub fn encode(&self) -> bincode::Result<Vec<u8>> {
+		options(MAX_DELTA_SIZE).serialize(self)
+	}
+
+	/// # Errors
+	///
+	/// Fails if `bytes` are not an encoded delta of these types, or are longer than
+	/// [`MAX_DELTA_SIZE`].
+	pub fn decode(bytes: &[u8]) -> bincode::Result<Self> {
+		Self::decode_with_limit(bytes, MAX_DELTA_SIZE)
+	}
+
+	/// Like [`decode`](Self::decode), reading at most `limit` bytes.
+	///
+	/// # Errors
+	///
+	/// Fails if `bytes` are not an encoded delta of these types, or are longer than `limit`.
+	pub fn decode_with_limit(bytes: &[u8], limit: u64) -> bincode::Result<Self> {
+		check_size(bytes, limit)?;
+		options(limit).deserialize(bytes)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::{Arc, mpsc};
+
+	use serde::Deserialize;
+
+	use super::*;
+	use crate::{
+		attribute::{Attribute, supplier::AttributeSupplier, tag::Tag},
+		prelude::{Operation, Value},
+	};
+
+	#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+	enum TestAttribute {
+		Strength,
+		Agility,
+		Speed,
+		Wits,
+	}
+
+	#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+	enum TestModifier {
+		Base,
+		Buff,
+		Slow,
+		Poison,
+	}
+
+	type MockMap = AttributeMap<TestAttribute, TestModifier, i32>;
+	type MockDelta = Delta<TestAttribute, TestModifier, i32, Operation>;
+
+	const PHYSICAL: Tag = Tag::new("physical");
+
+	fn mock_map() -> MockMap {
+		AttributeMap::new(Arc::new(
+			AttributeSupplier::builder()
+				.add(TestAttribute::Strength, Attribute::Value(3))
+				.add(TestAttribute::Agility, Attribute::Value(2))
+				.add(TestAttribute::Wits, Attribute::Value(1))
+				.add(
+					TestAttribute::Speed,
+					AttributeInstance::builder(Attribute::Derived).modifier(
+						TestModifier::Base,
+						AttributeModifier::new(
+							Value::Attribute(TestAttribute::Agility),
+							Operation::Add,
+						),
+					),
+				)
+				.tag(TestAttribute::Strength, PHYSICAL)
+				.tag(TestAttribute::Agility, PHYSICAL)
+				.build(),
+		))
+	}
+
+	fn modifiers(map: &MockMap, id: &TestAttribute) -> Vec<(TestModifier, ModifierOrigin)> {
+		map.modifiers(id)
+			.map(|(key, _, origin)| (key.clone(), origin))
+			.collect()
+	}
+
+	fn assert_synced(server: &MockMap, client: &MockMap) {
+		for id in [
+			TestAttribute::Strength,
+			TestAttribute::Agility,
+			TestAttribute::Speed,
+		] {
+			assert_eq!(client.value(&id), server.value(&id), "{id:?}");
+			assert_eq!(modifiers(client, &id), modifiers(server, &id), "{id:?}");
+		}
+	}
+
+	#[test]
+	fn test_sync_over_channel() {
+		let (tx, rx) = mpsc::channel::<Vec<u8>>();
+		let mut server = mock_map();
+		let mut sent = server.clone();
+		let mut client = mock_map();
+
+		let mut sync = |server: &MockMap, client: &mut MockMap| {
+			let delta = MockDelta::between(&sent, server);
+			tx.send(delta.encode().unwrap()).unwrap();
+			sent = server.clone();
+
+			let bytes = rx.recv().unwrap();
+			MockDelta::decode(&bytes).unwrap().apply(client);
+		};
+
+		server.set_raw_value(&TestAttribute::Agility, 5);
+		server.add_tag_modifier(
+			&PHYSICAL,
+			TestModifier::Buff,
+			AttributeModifier::new(1, Operation::Add),
+		);
+		sync(&server, &mut client);
+		assert_synced(&server, &client);
+		assert_eq!(client.value(&TestAttribute::Speed), Some(6));
+
+		server.add_modifier(
+			&TestAttribute::Speed,
+			TestModifier::Slow,
+			AttributeModifier::new(2, Operation::Sub),
+		);
+		server.remove_modifiers(&TestModifier::Buff);
+		server.remove_template_modifier(&TestAttribute::Speed, &TestModifier::Base);
+		sync(&server, &mut client);
+		assert_synced(&server, &client);
+		assert_eq!(client.value(&TestAttribute::Speed), Some(-2));
+
+		server.enable_journal();
+		server.set_raw_value(&TestAttribute::Wits, 4);
+		sync(&server, &mut client);
+		assert_eq!(client.value(&TestAttribute::Wits), Some(4));
+		server.undo();
+		sync(&server, &mut client);
+		assert!(!client.has_attribute(&TestAttribute::Wits));
+		assert_synced(&server, &client);
+	}
+
+	#[test]
+	fn test_raw_value_set_flag() {
+		let mut server = mock_map();
+		server.set_raw_value(&TestAttribute::Strength, 5);
+		let mut client = mock_map();
+		MockDelta::between(&mock_map(), &server).apply(&mut client);
+		assert!(
+			client
+				.instance(&TestAttribute::Strength)
+				.unwrap()
+				.is_raw_value_set()
+		);
+
+		// Back to the default raw value, which a rebase replaces again.
+		let sent = server.clone();
+		server.reset(&TestAttribute::Strength);
+		MockDelta::between(&sent, &server).apply(&mut client);
+		assert!(
+			!client
+				.instance(&TestAttribute::Strength)
+				.unwrap()
+				.is_raw_value_set()
+		);
+
+		let supplier = Arc::new(
+			AttributeSupplier::builder()
+				.add(TestAttribute::Strength, Attribute::Value(6))
+				.build(),
+		);
+		server.rebase(supplier.clone());
+		client.rebase(supplier);
+		assert_eq!(client.raw_value(&TestAttribute::Strength), Some(6));
+		assert_eq!(
+			client.raw_value(&TestAttribute::Strength),
+			server.raw_value(&TestAttribute::Strength)
+		);
+	}
+
+	#[test]
+	fn test_compact_changes() {
+		let mut old = mock_map();
+		let poison = || AttributeModifier::new(1, Operation::Sub);
+		old.add_modifier(&TestAttribute::Agility, TestModifier::Poison, poison());
+		old.add_modifier(&TestAttribute::Agility, TestModifier::Buff, poison());
+		old.add_modifier(&TestAttribute::Agility, TestModifier::Slow, poison());
+
+		let mut new = old.clone();
+		new.remove_modifier(&TestAttribute::Agility, &TestModifier::Buff);
+		new.add_modifier(&TestAttribute::Agility, TestModifier::Poison, poison());
+		let delta = MockDelta::between(&old, &new);
+		assert_eq!(
+			delta.attributes[0].modifiers,
+			[
+				ModifierChange::Remove(TestModifier::Buff),
+				ModifierChange::Add(TestModifier::Poison, poison()),
+			]
+		);
+
+		// A modifier moving to the front cannot be expressed by appending.
+		let mut moved = mock_map();
+		moved.add_modifier(&TestAttribute::Agility, TestModifier::Slow, poison());
+		moved.add_modifier(&TestAttribute::Agility, TestModifier::Poison, poison());
+		let delta = MockDelta::between(&old, &moved);
+		assert!(matches!(
+			delta.attributes[0].modifiers[..],
+			[ModifierChange::Replace(ModifierOrigin::Runtime, _)]
+		));
+
+		let mut client = old.clone();
+		delta.apply(&mut client);
+		assert_eq!(
+			modifiers(&client, &TestAttribute::Agility),
+			modifiers(&moved, &TestAttribute::Agility)
+		);
+
+		let mut raised = old.clone();
+		raised.set_raw_value(&TestAttribute::Strength, 4);
+		let bytes = MockDelta::between(&old, &raised).encode().unwrap();
+		assert!(bytes.len() <= 8, "{bytes:?}");
+		assert!(MockDelta::between(&raised, &raised).is_empty());
+	}
+
+	#[test]
+	fn test_discrete_states() {
+		let supplier = Arc::new(
+			AttributeSupplier::builder()
+				.discrete(TestAttribute::Wits, "calm")
+				.build(),
+		);
+		let old: MockMap = AttributeMap::new(supplier);
+		let mut new = old.clone();
+		new.enable_journal();
+		new.override_state(&TestAttribute::Wits, TestModifier::Poison, 0, "confused");
+
+		let delta = MockDelta::between(&old, &new);
+		assert_eq!(delta.states.len(), 1);
+		let mut client = old.clone();
+		MockDelta::decode(&delta.encode().unwrap())
+			.unwrap()
+			.apply(&mut client);
+		assert_eq!(
+			client.state(&TestAttribute::Wits),
+			Some(&Tag::new("confused"))
+		);
+
+		let changed = new.clone();
+		new.undo();
+		let delta = MockDelta::between(&changed, &new);
+		assert_eq!(delta.dematerialised, [TestAttribute::Wits]);
+		delta.apply(&mut client);
+		assert!(client.discrete(&TestAttribute::Wits).is_none());
+		assert_eq!(client.state(&TestAttribute::Wits), Some(&Tag::new("calm")));
+	}
+
+	#[test]
+	fn test_value_delta() {
+		let old = mock_map();
+		let mut values = old.snapshot();
+
+		let mut new = old.clone();
+		new.set_raw_value(&TestAttribute::Agility, 4);
+		let delta = ValueDelta::between(&old, &new);
+		let mut changed = delta.changed.clone();
+		changed.sort_by_key(|(id, _)| format!("{id:?}"));
+		assert_eq!(
+			changed,
+			[(TestAttribute::Agility, 4), (TestAttribute::Speed, 4)]
+		);
+
+		let bytes = delta.encode().unwrap();
+		ValueDelta::decode(&bytes).unwrap().apply(&mut values);
+		assert_eq!(values, new.snapshot());
+	}
+
+	#[test]
+	fn test_size_limit() {
+		let delta = ValueDelta {
+			changed: (0..1000u32).map(|i| (i, i)).collect(),
+			removed: Vec::new(),
+		};
+		let bytes = delta.encode().unwrap();
+		let len = bytes.len() as u64;
+		assert_eq!(ValueDelta::decode_with_limit(&bytes, len).unwrap(), delta);
+		assert!(matches!(
+			*ValueDelta::<u32, u32>::decode_with_limit(&bytes, len - 1).unwrap_err(),
+			bincode::ErrorKind::SizeLimit
+		));
+
+		// A length prefix claiming far more entries than the limit allows.
+		let mut bytes = vec![253];
+		bytes.extend(u64::MAX.to_le_bytes());
+		bytes.resize(64, 0);
+		assert!(ValueDelta::<u32, u32>::decode(&bytes).is_err());
+
+		let huge = ValueDelta {
+			changed: (0..200_000u64).map(|i| (i, u64::MAX)).collect(),
+			removed: Vec::new(),
+		};
+		assert!(matches!(
+			*huge.encode().unwrap_err(),
+			bincode::ErrorKind::SizeLimit
+		));
+	}
+}
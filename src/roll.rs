@@ -0,0 +1,609 @@
+//! Dice pools built from attribute values, and rolling them.
+//!
+//! A [`Pool`] sums attributes and constants, e.g. *Strength + Brawl + 1*. Evaluating it against an
+//! [`AttributeMap`] yields an [`Evaluation`] that explains every term and can be rolled as a pool
+//! of dice with a [`SuccessRoll`].
+
+use std::{
+	error::Error,
+	fmt::{self, Debug, Display, Write as _},
+	hash::Hash,
+};
+
+use crate::{
+	attribute::{
+		instance::AttributeInstance,
+		map::AttributeMap,
+		modifier::{ArithmeticOp, Op, Operation, Value},
+		storage::AttributeStorage,
+	},
+	number::Arithmetic,
+	util_traits::Key,
+};
+
+/// A source of randomness for rolls.
+///
+/// Implement it over your engine's generator, or use the seedable [`SplitMix64`] for
+/// reproducible rolls.
+pub trait Rng {
+	fn next_u64(&mut self) -> u64;
+
+	/// A uniformly distributed die result in `1..=sides`, or `0` for a die without sides.
+	fn die(&mut self, sides: u32) -> u32 {
+		if sides == 0 {
+			return 0;
+		}
+		// Maps the upper 32 bits onto `0..sides` without a modulo.
+		let roll = ((self.next_u64() >> 32) * u64::from(sides)) >> 32;
+		u32::try_from(roll).unwrap_or(u32::MAX) + 1
+	}
+}
+
+/// A small, fast generator with a 64 bit state, seeded explicitly so rolls can be replayed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SplitMix64 {
+	state: u64,
+}
+
+impl SplitMix64 {
+	#[must_use]
+	pub const fn new(seed: u64) -> Self {
+		Self { state: seed }
+	}
+}
+
+impl Rng for SplitMix64 {
+	fn next_u64(&mut self) -> u64 {
+		self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+		let mut z = self.state;
+		z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+		z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+		z ^ (z >> 31)
+	}
+}
+
+/// Converts an attribute value into a number of dice. Negative values give no dice.
+pub trait DiceCount {
+	fn dice(&self) -> u32;
+}
+
+macro_rules! impl_dice_count_int {
+	($($t:ty),*) => {$(
+		impl DiceCount for $t {
+			#[allow(unused_comparisons)]
+			fn dice(&self) -> u32 {
+				if *self < 0 {
+					0
+				} else {
+					u32::try_from(*self).unwrap_or(u32::MAX)
+				}
+			}
+		}
+	)*};
+}
+
+macro_rules! impl_dice_count_float {
+	($($t:ty),*) => {$(
+		impl DiceCount for $t {
+			#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+			fn dice(&self) -> u32 {
+				// Saturating: NaN and negative values give no dice.
+				*self as u32
+			}
+		}
+	)*};
+}
+
+impl_dice_count_int!(
+	u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize
+);
+impl_dice_count_float!(f32, f64);
+
+/// A sum of attributes and constants, e.g. *Strength + Brawl - 2*.
+///
+/// Terms are applied left to right with `O`, the [`Operation`] by default.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pool<A, V, O = Operation> {
+	terms: Vec<(O, Value<A, V>)>,
+}
+
+impl<A, V, O> Pool<A, V, O> {
+	#[must_use]
+	pub fn new() -> Self {
+		Self { terms: Vec::new() }
+	}
+
+	/// Applies an attribute or constant to the pool with `op`.
+	#[must_use]
+	pub fn term<I: Into<Value<A, V>>>(mut self, op: O, term: I) -> Self {
+		self.terms.push((op, term.into()));
+		self
+	}
+}
+
+impl<A, V> Pool<A, V> {
+	/// Adds an attribute or constant to the pool.
+	#[must_use]
+	pub fn plus<I: Into<Value<A, V>>>(self, term: I) -> Self {
+		self.term(Operation::Add, term)
+	}
+
+	/// Subtracts an attribute or constant from the pool.
+	#[must_use]
+	pub fn minus<I: Into<Value<A, V>>>(self, term: I) -> Self {
+		self.term(Operation::Sub, term)
+	}
+
+	#[must_use]
+	pub fn attribute(self, attribute: A) -> Self {
+		self.plus(Value::Attribute(attribute))
+	}
+}
+
+impl<A, V, O> Default for Pool<A, V, O> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<A, V, O> Pool<A, V, O>
+where
+	A: Key + Hash + 'static,
+	V: Arithmetic,
+	O: Op<V>,
+{
+	/// Sums the pool against `attributes`. Attributes missing from the map count as the default
+	/// value.
+	pub fn evaluate<M, MO, S>(
+		&self,
+		attributes: &AttributeMap<A, M, V, MO, S>,
+	) -> Evaluation<A, V, O>
+	where
+		M: Key + 'static,
+		MO: Op<V>,
+		S: AttributeStorage<A, AttributeInstance<A, M, V, MO>>,
+	{
+		let mut total = V::default();
+		let terms = self
+			.terms
+			.iter()
+			.map(|(op, term)| {
+				let amount = match term {
+					Value::Value(value) => *value,
+					Value::Attribute(attribute) => attributes.value(attribute).unwrap_or_default(),
+				};
+				total = op.apply(total, amount);
+				EvaluatedTerm {
+					op: op.clone(),
+					term: term.clone(),
+					amount,
+				}
+			})
+			.collect();
+
+		Evaluation { terms, total }
+	}
+}
+
+/// A term of an [`Evaluation`] with the value it contributed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EvaluatedTerm<A, V, O = Operation> {
+	pub op: O,
+	pub term: Value<A, V>,
+	pub amount: V,
+}
+
+/// A [`Pool`] summed against an [`AttributeMap`].
+///
+/// With [`Operation`] or [`ArithmeticOp`], its [`Display`] explains the sum, e.g.
+/// `Strength (3) + Brawl (2) + 1 = 6`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Evaluation<A, V, O = Operation> {
+	pub terms: Vec<EvaluatedTerm<A, V, O>>,
+	pub total: V,
+}
+
+impl<A, V: DiceCount, O> Evaluation<A, V, O> {
+	/// The total as a number of dice.
+	pub fn dice(&self) -> u32 {
+		self.total.dice()
+	}
+
+	/// Rolls [`dice`](Self::dice) dice with `roll`.
+	///
+	/// # Errors
+	///
+	/// See [`SuccessRoll::roll`].
+	pub fn roll<R: Rng>(&self, roll: &SuccessRoll, rng: &mut R) -> Result<RollResult, RollError> {
+		roll.roll(self.dice(), rng)
+	}
+}
+
+impl<A: Debug, V: Display, O: Clone + Into<ArithmeticOp>> Display for Evaluation<A, V, O> {
+	/// Terms are applied left to right, so a product following a sum is written with the sum in
+	/// parentheses, e.g. `(Strength (3) + 1) * 2 = 8`.
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let mut expression = String::new();
+		let mut sum = false;
+		for (i, term) in self.terms.iter().enumerate() {
+			let op: ArithmeticOp = term.op.clone().into();
+			match (i, &op) {
+				(
+					0,
+					ArithmeticOp::Add
+					| ArithmeticOp::Mul
+					| ArithmeticOp::Div
+					| ArithmeticOp::Percent,
+				) => {}
+				(0, ArithmeticOp::Sub) => expression.push('-'),
+				(_, ArithmeticOp::Add | ArithmeticOp::Sub) => {
+					sum = true;
+					expression.push_str(if op == ArithmeticOp::Add {
+						" + "
+					} else {
+						" - "
+					});
+				}
+				(_, ArithmeticOp::Mul | ArithmeticOp::Div | ArithmeticOp::Percent) => {
+					if std::mem::take(&mut sum) {
+						expression = format!("({expression})");
+					}
+					expression.push_str(if op == ArithmeticOp::Div {
+						" / "
+					} else {
+						" * "
+					});
+				}
+			}
+			match &term.term {
+				Value::Value(value) => write!(expression, "{value}")?,
+				Value::Attribute(attribute) => {
+					write!(expression, "{attribute:?} ({})", term.amount)?;
+				}
+			}
+			if op == ArithmeticOp::Percent {
+				expression.push('%');
+			}
+		}
+		if self.terms.is_empty() {
+			expression.push('0');
+		}
+		write!(f, "{expression} = {}", self.total)
+	}
+}
+
+/// Whether a roll is made twice, keeping the better or worse result.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Advantage {
+	#[default]
+	Normal,
+	Advantage,
+	Disadvantage,
+}
+
+/// How a pool of dice is rolled and its successes counted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SuccessRoll {
+	pub sides: u32,
+	/// The lowest die result that counts as a success.
+	pub target: u32,
+	/// The lowest die result that adds another die to the pool. Thresholds below 2 are ignored,
+	/// and a pool stops exploding at [`MAX_DICE`](Self::MAX_DICE), so it cannot explode forever.
+	pub explode: Option<u32>,
+	pub advantage: Advantage,
+}
+
+impl SuccessRoll {
+	/// The most dice rolled at once, explosions included.
+	pub const MAX_DICE: u32 = 10_000;
+
+	/// A pool of `sides`-sided dice, succeeding on `target` or more, without explosions.
+	#[must_use]
+	pub const fn new(sides: u32, target: u32) -> Self {
+		Self {
+			sides,
+			target,
+			explode: None,
+			advantage: Advantage::Normal,
+		}
+	}
+
+	/// The common d10 pool: successes on 8 or more, tens explode.
+	#[must_use]
+	pub const fn d10() -> Self {
+		Self::new(10, 8).explode(10)
+	}
+
+	#[must_use]
+	pub const fn explode(mut self, threshold: u32) -> Self {
+		self.explode = Some(threshold);
+		self
+	}
+
+	#[must_use]
+	pub const fn advantage(mut self, advantage: Advantage) -> Self {
+		self.advantage = advantage;
+		self
+	}
+
+	/// Rolls `dice` dice, twice if the roll has (dis)advantage.
+	///
+	/// # Errors
+	///
+	/// Fails without rolling if the dice have no sides, or if there are more than
+	/// [`MAX_DICE`](Self::MAX_DICE) of them.
+	pub fn roll<R: Rng>(&self, dice: u32, rng: &mut R) -> Result<RollResult, RollError> {
+		if self.sides == 0 {
+			return Err(RollError::NoSides);
+		}
+		if dice > Self::MAX_DICE {
+			return Err(RollError::TooManyDice {
+				dice,
+				max: Self::MAX_DICE,
+			});
+		}
+
+		let first = self.roll_once(dice, rng);
+		if self.advantage == Advantage::Normal {
+			return Ok(first);
+		}
+
+		let second = self.roll_once(dice, rng);
+		let better = second.successes > first.successes;
+		Ok(match (self.advantage, better) {
+			(Advantage::Advantage, true) | (Advantage::Disadvantage, false) => second,
+			_ => first,
+		})
+	}
+
+	fn roll_once<R: Rng>(&self, dice: u32, rng: &mut R) -> RollResult {
+		let explode = self.explode.filter(|threshold| *threshold > 1);
+		let mut results = Vec::with_capacity(dice as usize);
+		let mut remaining = dice;
+		while remaining > 0 {
+			remaining -= 1;
+			let result = rng.die(self.sides);
+			results.push(result);
+			let room = results.len() + (remaining as usize) < Self::MAX_DICE as usize;
+			if room && explode.is_some_and(|threshold| result >= threshold) {
+				remaining += 1;
+			}
+		}
+
+		let successes = results.iter().filter(|r| **r >= self.target).count();
+		RollResult {
+			successes: u32::try_from(successes).unwrap_or(u32::MAX),
+			dice: results,
+		}
+	}
+}
+
+/// The outcome of a [`SuccessRoll`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RollResult {
+	/// Every die rolled, explosions included, in roll order.
+	pub dice: Vec<u32>,
+	pub successes: u32,
+}
+
+/// Why a [`SuccessRoll`] could not be rolled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RollError {
+	/// The dice have no sides.
+	NoSides,
+	/// The pool holds more than [`SuccessRoll::MAX_DICE`] dice.
+	TooManyDice { dice: u32, max: u32 },
+}
+
+impl fmt::Display for RollError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::NoSides => f.write_str("the dice have no sides"),
+			Self::TooManyDice { dice, max } => {
+				write!(f, "a pool of {dice} dice exceeds the maximum of {max}")
+			}
+		}
+	}
+}
+
+impl Error for RollError {}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use super::*;
+	use crate::attribute::{Attribute, supplier::AttributeSupplier};
+
+	#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+	enum TestAttribute {
+		Strength,
+		Brawl,
+	}
+
+	type MockMap = AttributeMap<TestAttribute, (), i32>;
+
+	fn mock_map() -> MockMap {
+		AttributeMap::new(Arc::new(
+			AttributeSupplier::builder()
+				.add(TestAttribute::Strength, Attribute::Value(3))
+				.add(TestAttribute::Brawl, Attribute::Value(2))
+				.build(),
+		))
+	}
+
+	/// Replays a fixed sequence of die results.
+	struct Fixed<'a>(std::slice::Iter<'a, u32>);
+
+	impl Rng for Fixed<'_> {
+		fn next_u64(&mut self) -> u64 {
+			unreachable!()
+		}
+
+		fn die(&mut self, _: u32) -> u32 {
+			*self.0.next().expect("ran out of dice")
+		}
+	}
+
+	fn fixed(dice: &[u32]) -> Fixed<'_> {
+		Fixed(dice.iter())
+	}
+
+	/// Rolls the same result forever.
+	struct Always(u32);
+
+	impl Rng for Always {
+		fn next_u64(&mut self) -> u64 {
+			unreachable!()
+		}
+
+		fn die(&mut self, _: u32) -> u32 {
+			self.0
+		}
+	}
+
+	#[test]
+	fn test_evaluate_and_explain() {
+		let pool = Pool::new()
+			.attribute(TestAttribute::Strength)
+			.attribute(TestAttribute::Brawl)
+			.plus(1)
+			.minus(2);
+		let evaluation = pool.evaluate(&mock_map());
+
+		assert_eq!(evaluation.total, 4);
+		assert_eq!(evaluation.dice(), 4);
+		assert_eq!(
+			evaluation.to_string(),
+			"Strength (3) + Brawl (2) + 1 - 2 = 4"
+		);
+	}
+
+	#[test]
+	fn test_explain_precedence() {
+		let term = |op, term, amount| EvaluatedTerm { op, term, amount };
+		let evaluation = Evaluation {
+			terms: vec![
+				term(
+					ArithmeticOp::Add,
+					Value::Attribute(TestAttribute::Strength),
+					3,
+				),
+				term(ArithmeticOp::Add, Value::Value(1), 1),
+				term(ArithmeticOp::Mul, Value::Value(2), 2),
+				term(ArithmeticOp::Div, Value::Value(4), 4),
+				term(ArithmeticOp::Sub, Value::Value(1), 1),
+				term(ArithmeticOp::Percent, Value::Value(150), 150),
+			],
+			total: 1,
+		};
+		assert_eq!(
+			evaluation.to_string(),
+			"((Strength (3) + 1) * 2 / 4 - 1) * 150% = 1"
+		);
+	}
+
+	#[test]
+	fn test_negative_pool_has_no_dice() {
+		let evaluation = Pool::<TestAttribute, i32>::new()
+			.minus(3)
+			.evaluate(&mock_map());
+		assert_eq!(evaluation.total, -3);
+		assert_eq!(evaluation.dice(), 0);
+		assert_eq!(2.7_f32.dice(), 2);
+		assert_eq!(f32::NAN.dice(), 0);
+	}
+
+	#[test]
+	fn test_successes_and_explosions() {
+		let result = SuccessRoll::d10()
+			.roll(3, &mut fixed(&[10, 4, 8, 7]))
+			.unwrap();
+		assert_eq!(result.dice, [10, 4, 8, 7]);
+		assert_eq!(result.successes, 2);
+
+		let result = SuccessRoll::new(10, 8)
+			.roll(2, &mut fixed(&[10, 9]))
+			.unwrap();
+		assert_eq!(result.successes, 2);
+	}
+
+	#[test]
+	fn test_advantage() {
+		let advantage = SuccessRoll::new(10, 8).advantage(Advantage::Advantage);
+		let result = advantage.roll(2, &mut fixed(&[1, 2, 9, 3])).unwrap();
+		assert_eq!(result.dice, [9, 3]);
+
+		let disadvantage = SuccessRoll::new(10, 8).advantage(Advantage::Disadvantage);
+		let result = disadvantage.roll(2, &mut fixed(&[1, 2, 9, 3])).unwrap();
+		assert_eq!(result.dice, [1, 2]);
+	}
+
+	#[test]
+	fn test_seeded_rolls_are_reproducible() {
+		let roll = SuccessRoll::d10();
+		let evaluation = Pool::new()
+			.attribute(TestAttribute::Strength)
+			.attribute(TestAttribute::Brawl)
+			.evaluate(&mock_map());
+
+		let a = evaluation.roll(&roll, &mut SplitMix64::new(42)).unwrap();
+		let b = evaluation.roll(&roll, &mut SplitMix64::new(42)).unwrap();
+		assert_eq!(a, b);
+		assert!(a.dice.len() >= 5);
+		assert!(a.dice.iter().all(|d| (1..=10).contains(d)));
+	}
+
+	#[test]
+	fn test_roll_limits() {
+		let mut rng = SplitMix64::new(1);
+		assert_eq!(rng.die(0), 0);
+		assert_eq!(
+			SuccessRoll::new(0, 1).roll(1, &mut rng),
+			Err(RollError::NoSides)
+		);
+		assert_eq!(
+			SuccessRoll::d10().roll(u32::MAX, &mut rng),
+			Err(RollError::TooManyDice {
+				dice: u32::MAX,
+				max: SuccessRoll::MAX_DICE
+			})
+		);
+
+		// Every die explodes, until the pool is full.
+		let result = SuccessRoll::new(2, 2)
+			.explode(2)
+			.roll(3, &mut Always(2))
+			.unwrap();
+		assert_eq!(result.dice.len(), SuccessRoll::MAX_DICE as usize);
+	}
+
+	#[test]
+	fn test_custom_operation() {
+		#[derive(Clone, Debug, PartialEq)]
+		struct Max;
+
+		impl Op<i32> for Max {
+			fn apply(&self, a: i32, b: i32) -> i32 {
+				a.max(b)
+			}
+		}
+
+		let evaluation = Pool::new()
+			.term(Max, Value::Attribute(TestAttribute::Strength))
+			.term(Max, Value::Attribute(TestAttribute::Brawl))
+			.evaluate(&mock_map());
+		assert_eq!(evaluation.total, 3);
+		assert_eq!(evaluation.terms[1].op, Max);
+	}
+
+	#[test]
+	fn test_die_distribution() {
+		let mut rng = SplitMix64::new(7);
+		let mut counts = [0u32; 6];
+		for _ in 0..6000 {
+			counts[rng.die(6) as usize - 1] += 1;
+		}
+		assert!(counts.iter().all(|c| (800..1200).contains(c)), "{counts:?}");
+	}
+}
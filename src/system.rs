@@ -1,16 +1,42 @@
-use std::hash::Hash;
+use std::{hash::Hash, sync::Arc};
 
 use crate::{
 	actor::Actor,
-	attribute::modifier::Op,
+	attribute::{modifier::Op, supplier::AttributeSupplier},
+	kind::KindRegistry,
+	prelude::AttributeMap,
 	util_traits::{Key, Number},
 };
 
-pub trait System {
-	type AttributeKey: Key + Hash;
-	type ModifierKey: Key;
+pub trait System: Sized + 'static {
+	type AttributeKey: Key + Hash + 'static;
+	type ModifierKey: Key + 'static;
 	type AttributeValue: Number;
 	type Operation: Op<Self::AttributeValue>;
 
+	type Kind: Key + Hash;
 	type Actor: Actor<System = Self>;
+
+	/// The template supplier of every actor kind, none by default.
+	///
+	/// [`shared_kinds!`](crate::shared_kinds) builds the registry once and shares it between
+	/// calls.
+	#[must_use]
+	fn kinds() -> Option<Arc<KindRegistry<Self>>> {
+		None
+	}
+
+	/// A fresh attribute map for an actor of `kind`, empty if the kind is not registered.
+	fn attributes(kind: &Self::Kind) -> AttributeMap<Self> {
+		Self::kinds()
+			.and_then(|kinds| kinds.supplier(kind).cloned())
+			.map_or_else(AttributeMap::<Self>::default, AttributeMap::<Self>::new)
+	}
 }
+
+pub type SupplierOf<S> = AttributeSupplier<
+	<S as System>::AttributeKey,
+	<S as System>::ModifierKey,
+	<S as System>::AttributeValue,
+	<S as System>::Operation,
+>;
@@ -0,0 +1,365 @@
+//! Checking raw values against character-creation rules, e.g. "distribute 5/4/3 dots among
+//! physical/mental/social, each attribute 1–5".
+//!
+//! Groups are [`Tag`]s declared by the
+//! [`AttributeSupplier`](crate::attribute::supplier::AttributeSupplier), and bounds come from its
+//! [`Attribute::Ranged`] definitions.
+
+use std::{cmp::Ordering, hash::Hash};
+
+use crate::{
+	attribute::{
+		Attribute, instance::AttributeInstance, map::AttributeMap, modifier::Op,
+		storage::AttributeStorage, tag::Tag,
+	},
+	util_traits::{Key, Number},
+};
+
+/// A broken rule and the attributes responsible for it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Violation<A, V> {
+	/// A raw value lies outside the range of its attribute.
+	OutOfBounds {
+		attribute: A,
+		value: V,
+		min: V,
+		max: V,
+	},
+	/// The points spent on a group do not match the budget it was assigned.
+	Budget {
+		tag: Tag,
+		spent: V,
+		budget: V,
+		attributes: Vec<A>,
+	},
+}
+
+#[derive(Clone, Debug)]
+enum Rule<V> {
+	Bounds,
+	Budget(Tag, V),
+	Priorities(Vec<Tag>, Vec<V>),
+}
+
+/// A set of rules raw values are checked against.
+///
+/// Points spent on an attribute are its raw value above the supplier's default, so a free first
+/// dot is not counted.
+#[derive(Clone, Debug)]
+pub struct RuleSet<V> {
+	rules: Vec<Rule<V>>,
+}
+
+impl<V: Number> RuleSet<V> {
+	#[must_use]
+	pub fn new() -> Self {
+		Self { rules: Vec::new() }
+	}
+
+	/// Requires the raw value of every ranged attribute to lie within its range.
+	#[must_use]
+	pub fn bounds(mut self) -> Self {
+		self.rules.push(Rule::Bounds);
+		self
+	}
+
+	/// Requires exactly `budget` points to be spent on the attributes tagged `tag`.
+	#[must_use]
+	pub fn budget<T: Into<Tag>>(mut self, tag: T, budget: V) -> Self {
+		self.rules.push(Rule::Budget(tag.into(), budget));
+		self
+	}
+
+	/// Requires the groups to be assigned the budgets in some order, e.g. 5/4/3 dots over
+	/// physical, mental and social attributes, with every point spent.
+	///
+	/// # Panics
+	///
+	/// Panics if the number of groups and budgets differ.
+	#[must_use]
+	pub fn priorities<T, G, B>(mut self, groups: G, budgets: B) -> Self
+	where
+		T: Into<Tag>,
+		G: IntoIterator<Item = T>,
+		B: IntoIterator<Item = V>,
+	{
+		let groups: Vec<Tag> = groups.into_iter().map(Into::into).collect();
+		let budgets: Vec<V> = budgets.into_iter().collect();
+		assert_eq!(
+			groups.len(),
+			budgets.len(),
+			"every priority group needs a budget"
+		);
+		self.rules.push(Rule::Priorities(groups, budgets));
+		self
+	}
+
+	/// Checks the raw values of `attributes`, returning every violation found.
+	///
+	/// # Errors
+	///
+	/// Returns the violations, in rule order and then the supplier's declaration order, if any
+	/// rule is broken.
+	pub fn validate<A, M, O, S>(
+		&self,
+		attributes: &AttributeMap<A, M, V, O, S>,
+	) -> Result<(), Vec<Violation<A, V>>>
+	where
+		A: Key + Hash + 'static,
+		M: Key + 'static,
+		O: Op<V>,
+		S: AttributeStorage<A, AttributeInstance<A, M, V, O>>,
+	{
+		let mut violations = Vec::new();
+		for rule in &self.rules {
+			match rule {
+				Rule::Bounds => violations.extend(Self::out_of_bounds(attributes)),
+				Rule::Budget(tag, budget) => {
+					let (spent, group) = Self::spent(attributes, tag);
+					if spent != *budget {
+						violations.push(Violation::Budget {
+							tag: tag.clone(),
+							spent,
+							budget: *budget,
+							attributes: group,
+						});
+					}
+				}
+				Rule::Priorities(groups, budgets) => {
+					violations.extend(Self::priorities_violations(attributes, groups, budgets));
+				}
+			}
+		}
+
+		if violations.is_empty() {
+			Ok(())
+		} else {
+			Err(violations)
+		}
+	}
+
+	fn out_of_bounds<A, M, O, S>(
+		attributes: &AttributeMap<A, M, V, O, S>,
+	) -> impl Iterator<Item = Violation<A, V>>
+	where
+		A: Key + Hash + 'static,
+		M: Key + 'static,
+		O: Op<V>,
+		S: AttributeStorage<A, AttributeInstance<A, M, V, O>>,
+	{
+		attributes.keys().filter_map(|id| {
+			let Attribute::Ranged(_, min, max) = attributes.definition(id)? else {
+				return None;
+			};
+			let value = attributes.raw_value(id)?;
+			(O::clamp(value, min, max) != value).then(|| Violation::OutOfBounds {
+				attribute: id.clone(),
+				value,
+				min,
+				max,
+			})
+		})
+	}
+
+	/// Matches the largest budget with the group that spent most, and so on.
+	fn priorities_violations<A, M, O, S>(
+		attributes: &AttributeMap<A, M, V, O, S>,
+		groups: &[Tag],
+		budgets: &[V],
+	) -> Vec<Violation<A, V>>
+	where
+		A: Key + Hash + 'static,
+		M: Key + 'static,
+		O: Op<V>,
+		S: AttributeStorage<A, AttributeInstance<A, M, V, O>>,
+	{
+		let descending = |a: &V, b: &V| b.partial_cmp(a).unwrap_or(Ordering::Equal);
+
+		let mut spent: Vec<(&Tag, V, Vec<A>)> = groups
+			.iter()
+			.map(|tag| {
+				let (spent, group) = Self::spent(attributes, tag);
+				(tag, spent, group)
+			})
+			.collect();
+		spent.sort_by(|(_, a, _), (_, b, _)| descending(a, b));
+		let mut budgets = budgets.to_vec();
+		budgets.sort_by(descending);
+
+		spent
+			.into_iter()
+			.zip(budgets)
+			.filter(|((_, spent, _), budget)| spent != budget)
+			.map(|((tag, spent, group), budget)| Violation::Budget {
+				tag: tag.clone(),
+				spent,
+				budget,
+				attributes: group,
+			})
+			.collect()
+	}
+
+	/// The points spent on the attributes tagged `tag`, and those attributes.
+	fn spent<A, M, O, S>(attributes: &AttributeMap<A, M, V, O, S>, tag: &Tag) -> (V, Vec<A>)
+	where
+		A: Key + Hash + 'static,
+		M: Key + 'static,
+		O: Op<V>,
+		S: AttributeStorage<A, AttributeInstance<A, M, V, O>>,
+	{
+		let mut spent = V::default();
+		let mut group = Vec::new();
+		for id in attributes.tagged(tag) {
+			let raw = attributes.raw_value(id).unwrap_or_default();
+			let base = attributes
+				.supplier()
+				.and_then(|s| s.raw_value(id))
+				.unwrap_or_default();
+			if raw > base {
+				spent = spent + (raw - base);
+			}
+			group.push(id.clone());
+		}
+		(spent, group)
+	}
+}
+
+impl<V: Number> Default for RuleSet<V> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use super::*;
+	use crate::attribute::supplier::AttributeSupplier;
+
+	#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+	enum TestAttribute {
+		Strength,
+		Dexterity,
+		Wits,
+		Intelligence,
+		Presence,
+		Manipulation,
+	}
+
+	type MockMap = AttributeMap<TestAttribute, (), i32>;
+
+	const PHYSICAL: Tag = Tag::new("physical");
+	const MENTAL: Tag = Tag::new("mental");
+	const SOCIAL: Tag = Tag::new("social");
+
+	fn mock_map() -> MockMap {
+		let dot = || Attribute::Ranged(1, 1, 5);
+		AttributeMap::new(Arc::new(
+			AttributeSupplier::builder()
+				.add(TestAttribute::Strength, dot())
+				.add(TestAttribute::Dexterity, dot())
+				.add(TestAttribute::Wits, dot())
+				.add(TestAttribute::Intelligence, dot())
+				.add(TestAttribute::Presence, dot())
+				.add(TestAttribute::Manipulation, dot())
+				.tag(TestAttribute::Strength, PHYSICAL)
+				.tag(TestAttribute::Dexterity, PHYSICAL)
+				.tag(TestAttribute::Wits, MENTAL)
+				.tag(TestAttribute::Intelligence, MENTAL)
+				.tag(TestAttribute::Presence, SOCIAL)
+				.tag(TestAttribute::Manipulation, SOCIAL)
+				.build(),
+		))
+	}
+
+	fn rules() -> RuleSet<i32> {
+		RuleSet::new()
+			.bounds()
+			.priorities([PHYSICAL, MENTAL, SOCIAL], [5, 4, 3])
+	}
+
+	#[test]
+	fn test_valid_character() {
+		let mut map = mock_map();
+		// Mental 5, social 4, physical 3.
+		map.set_raw_value(&TestAttribute::Wits, 4);
+		map.set_raw_value(&TestAttribute::Intelligence, 3);
+		map.set_raw_value(&TestAttribute::Presence, 5);
+		map.set_raw_value(&TestAttribute::Strength, 3);
+		map.set_raw_value(&TestAttribute::Dexterity, 2);
+
+		assert_eq!(rules().validate(&map), Ok(()));
+	}
+
+	#[test]
+	fn test_violations() {
+		let mut map = mock_map();
+		map.set_raw_value(&TestAttribute::Strength, 6);
+		map.set_raw_value(&TestAttribute::Dexterity, 1);
+		map.set_raw_value(&TestAttribute::Wits, 5);
+		map.set_raw_value(&TestAttribute::Presence, 3);
+
+		assert_eq!(
+			rules().validate(&map).unwrap_err(),
+			[
+				Violation::OutOfBounds {
+					attribute: TestAttribute::Strength,
+					value: 6,
+					min: 1,
+					max: 5
+				},
+				Violation::Budget {
+					tag: SOCIAL,
+					spent: 2,
+					budget: 3,
+					attributes: vec![TestAttribute::Presence, TestAttribute::Manipulation],
+				},
+			]
+		);
+	}
+
+	#[test]
+	fn test_declaration_order() {
+		let mut map = mock_map();
+		map.set_raw_value(&TestAttribute::Manipulation, 0);
+		map.set_raw_value(&TestAttribute::Wits, 7);
+		map.set_raw_value(&TestAttribute::Strength, 6);
+
+		let violations = RuleSet::new().bounds().validate(&map).unwrap_err();
+		let attributes: Vec<_> = violations
+			.iter()
+			.map(|violation| match violation {
+				Violation::OutOfBounds { attribute, .. } => attribute.clone(),
+				Violation::Budget { .. } => unreachable!(),
+			})
+			.collect();
+		assert_eq!(
+			attributes,
+			[
+				TestAttribute::Strength,
+				TestAttribute::Wits,
+				TestAttribute::Manipulation
+			]
+		);
+	}
+
+	#[test]
+	fn test_single_budget() {
+		let mut map = mock_map();
+		map.set_raw_value(&TestAttribute::Presence, 2);
+
+		let rules = RuleSet::new().budget(SOCIAL, 1);
+		assert_eq!(rules.validate(&map), Ok(()));
+
+		map.set_raw_value(&TestAttribute::Manipulation, 2);
+		assert!(matches!(
+			rules.validate(&map).unwrap_err()[..],
+			[Violation::Budget {
+				spent: 2,
+				budget: 1,
+				..
+			}]
+		));
+	}
+}
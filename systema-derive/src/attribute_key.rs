@@ -0,0 +1,342 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+	BinOp, Data, DeriveInput, Expr, Fields, Ident, Token, Type, parenthesized,
+	punctuated::Punctuated, spanned::Spanned,
+};
+
+#[derive(Default)]
+struct VariantAttribute {
+	default: Option<Expr>,
+	range: Option<(Expr, Expr)>,
+	derived: Option<Vec<Step>>,
+}
+
+/// One base modifier of a derived attribute: `op` applied with `term`.
+struct Step {
+	op: Ident,
+	term: Expr,
+}
+
+pub(crate) fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+	let Data::Enum(data) = &input.data else {
+		return Err(syn::Error::new(
+			input.span(),
+			"AttributeKey can only be derived for enums",
+		));
+	};
+
+	let name = &input.ident;
+	let (value_type, operation) = container_attribute(input)?;
+
+	let mut variants = Vec::with_capacity(data.variants.len());
+	for variant in &data.variants {
+		if !matches!(variant.fields, Fields::Unit) {
+			return Err(syn::Error::new(
+				variant.span(),
+				"AttributeKey variants cannot have fields",
+			));
+		}
+		let attr = variant_attribute(variant)?;
+		// `Operation` only adds and subtracts.
+		if operation.is_none()
+			&& let Some(step) = attr
+				.derived
+				.iter()
+				.flatten()
+				.find(|step| step.op != "Add" && step.op != "Sub")
+		{
+			return Err(syn::Error::new(
+				step.op.span(),
+				"formulas that multiply or divide need `#[attribute_key(operation = ArithmeticOp)]`",
+			));
+		}
+		variants.push((&variant.ident, attr));
+	}
+	let operation =
+		operation.unwrap_or_else(|| syn::parse_quote!(::systema::attribute::modifier::Operation));
+
+	let count = variants.len();
+	let indices = variants.iter().enumerate().map(|(i, (ident, _))| {
+		quote! { Self::#ident => #i }
+	});
+	let all = variants.iter().map(|(ident, _)| quote! { Self::#ident });
+	let templates = variants
+		.iter()
+		.map(|(ident, attr)| template(ident, attr, &value_type, &operation));
+
+	Ok(quote! {
+		impl ::systema::attribute::storage::AttributeIndex for #name {
+			const COUNT: usize = #count;
+
+			fn index(&self) -> usize {
+				match self {
+					#(#indices,)*
+				}
+			}
+		}
+
+		impl ::systema::attribute::supplier::AttributeTemplate for #name {
+			type Value = #value_type;
+			type Operation = #operation;
+
+			const ALL: &'static [Self] = &[#(#all),*];
+
+			fn builder<M>() -> ::systema::attribute::supplier::AttributeSupplierBuilder<Self, M, #value_type, #operation>
+			where
+				M: ::core::clone::Clone
+					+ ::core::cmp::Eq
+					+ ::core::convert::From<Self>
+					+ ::core::convert::From<(Self, usize)>
+					+ 'static,
+			{
+				::systema::attribute::supplier::AttributeSupplier::builder()
+					#(#templates)*
+			}
+		}
+	})
+}
+
+fn container_attribute(input: &DeriveInput) -> syn::Result<(Type, Option<Type>)> {
+	let mut value_type = None;
+	let mut operation = None;
+	for attr in input
+		.attrs
+		.iter()
+		.filter(|a| a.path().is_ident("attribute_key"))
+	{
+		attr.parse_nested_meta(|meta| {
+			if meta.path.is_ident("value") {
+				value_type = Some(meta.value()?.parse()?);
+				Ok(())
+			} else if meta.path.is_ident("operation") {
+				operation = Some(meta.value()?.parse()?);
+				Ok(())
+			} else {
+				Err(meta.error("unknown attribute_key option"))
+			}
+		})?;
+	}
+	Ok((
+		value_type.unwrap_or_else(|| syn::parse_quote!(f32)),
+		operation,
+	))
+}
+
+fn variant_attribute(variant: &syn::Variant) -> syn::Result<VariantAttribute> {
+	let mut out = VariantAttribute::default();
+	for attr in variant
+		.attrs
+		.iter()
+		.filter(|a| a.path().is_ident("attribute"))
+	{
+		attr.parse_nested_meta(|meta| {
+			if meta.path.is_ident("default") {
+				out.default = Some(meta.value()?.parse()?);
+			} else if meta.path.is_ident("range") {
+				let content;
+				parenthesized!(content in meta.input);
+				let bounds = Punctuated::<Expr, Token![,]>::parse_terminated(&content)?;
+				let [min, max] = <[Expr; 2]>::try_from(bounds.into_iter().collect::<Vec<_>>())
+					.map_err(|_| meta.error("expected `range(min, max)`"))?;
+				out.range = Some((min, max));
+			} else if meta.path.is_ident("derived") {
+				let content;
+				parenthesized!(content in meta.input);
+				let terms = Punctuated::<Expr, Token![,]>::parse_terminated(&content)?;
+				out.derived = Some(formula(terms.into_iter().collect())?);
+			} else {
+				return Err(meta.error("unknown attribute option"));
+			}
+			Ok(())
+		})?;
+	}
+
+	if out.derived.is_some() && (out.default.is_some() || out.range.is_some()) {
+		return Err(syn::Error::new(
+			variant.span(),
+			"derived attributes cannot declare a default or range",
+		));
+	}
+	Ok(out)
+}
+
+/// Flattens `derived(..)` into the steps applied from zero: a list of terms is summed, a single
+/// formula is applied left to right.
+fn formula(terms: Vec<Expr>) -> syn::Result<Vec<Step>> {
+	let add = || Ident::new("Add", proc_macro2::Span::call_site());
+	let mut steps = Vec::new();
+	if let [expr] = &terms[..] {
+		flatten(expr, &mut steps)?;
+		return Ok(steps);
+	}
+	for term in terms {
+		steps.push(Step {
+			op: add(),
+			term: operand(term)?,
+		});
+	}
+	Ok(steps)
+}
+
+fn flatten(expr: &Expr, steps: &mut Vec<Step>) -> syn::Result<()> {
+	match expr {
+		Expr::Paren(paren) => flatten(&paren.expr, steps),
+		Expr::Binary(binary) => {
+			flatten(&binary.left, steps)?;
+			steps.push(Step {
+				op: operation(&binary.op)?,
+				term: operand((*binary.right).clone())?,
+			});
+			Ok(())
+		}
+		_ => {
+			steps.push(Step {
+				op: Ident::new("Add", expr.span()),
+				term: operand(expr.clone())?,
+			});
+			Ok(())
+		}
+	}
+}
+
+/// A right-hand operand, which modifiers can only apply one at a time.
+fn operand(mut expr: Expr) -> syn::Result<Expr> {
+	while let Expr::Paren(paren) = expr {
+		expr = *paren.expr;
+	}
+	match expr {
+		Expr::Binary(_) => Err(syn::Error::new(
+			expr.span(),
+			"formulas apply left to right, so the right of an operator must be a variant or a constant, e.g. `(a + b) * 2`",
+		)),
+		Expr::Unary(unary) if variant_term(strip_parens(&unary.expr)).is_some() => {
+			Err(syn::Error::new(
+				unary.span(),
+				"formulas cannot apply a unary operator to a variant, subtract it instead, e.g. `a - b`",
+			))
+		}
+		expr => Ok(expr),
+	}
+}
+
+fn operation(op: &BinOp) -> syn::Result<Ident> {
+	let name = match op {
+		BinOp::Add(_) => "Add",
+		BinOp::Sub(_) => "Sub",
+		BinOp::Mul(_) => "Mul",
+		BinOp::Div(_) => "Div",
+		_ => {
+			return Err(syn::Error::new(
+				op.span(),
+				"formulas support `+`, `-`, `*` and `/`",
+			));
+		}
+	};
+	Ok(Ident::new(name, op.span()))
+}
+
+fn template(
+	ident: &Ident,
+	attr: &VariantAttribute,
+	value_type: &Type,
+	operation: &Type,
+) -> TokenStream2 {
+	let default = attr.default.as_ref().map_or_else(
+		|| quote! { <#value_type as ::core::default::Default>::default() },
+		|expr| quote! { #expr },
+	);
+
+	let Some(steps) = &attr.derived else {
+		let kind = if let Some((min, max)) = &attr.range {
+			quote! { Ranged(#default, #min, #max) }
+		} else {
+			quote! { Value(#default) }
+		};
+		return quote! {
+			.add(Self::#ident, ::systema::attribute::Attribute::<#value_type>::#kind)
+		};
+	};
+
+	let mut keyed = Vec::new();
+	let modifiers = steps
+		.iter()
+		.enumerate()
+		.map(|(position, Step { op, term })| {
+			let (key, value) = match variant_term(term) {
+				Some(dep) => {
+					let key = if keyed.contains(&dep) {
+						quote! { (Self::#ident, #position) }
+					} else {
+						keyed.push(dep);
+						quote! { Self::#dep }
+					};
+					(
+						key,
+						quote! { ::systema::attribute::modifier::Value::Attribute(Self::#dep) },
+					)
+				}
+				None => (
+					quote! { (Self::#ident, #position) },
+					quote! { ::systema::attribute::modifier::Value::Value(#term) },
+				),
+			};
+			quote! {
+				.modifier(
+					M::from(#key),
+					::systema::attribute::modifier::AttributeModifier::new(
+						#value,
+						<#operation>::#op,
+					)
+					.base(),
+				)
+			}
+		});
+	let modifiers: Vec<_> = modifiers.collect();
+
+	quote! {
+		.add(
+			Self::#ident,
+			::systema::attribute::instance::AttributeInstance::builder(
+				::systema::attribute::Attribute::<#value_type>::Derived,
+			)
+			#(#modifiers)*,
+		)
+	}
+}
+
+fn strip_parens(mut expr: &Expr) -> &Expr {
+	while let Expr::Paren(paren) = expr {
+		expr = &paren.expr;
+	}
+	expr
+}
+
+/// A bare identifier in `derived(..)` names another variant; anything else is a constant.
+fn variant_term(term: &Expr) -> Option<&Ident> {
+	match term {
+		Expr::Path(path) if path.qself.is_none() => path.path.get_ident(),
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_unary_variant_is_rejected() {
+		let input: DeriveInput = syn::parse_quote! {
+			#[attribute_key(value = i32)]
+			enum Stat {
+				Strength,
+				#[attribute(derived(Strength, -1))]
+				Lowered,
+				#[attribute(derived(10 - -(Strength)))]
+				Negated,
+			}
+		};
+		let error = expand(&input).unwrap_err().to_string();
+		assert!(error.contains("unary operator"), "{error}");
+	}
+}
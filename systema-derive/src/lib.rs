@@ -0,0 +1,67 @@
+#![warn(clippy::pedantic)]
+
+use proc_macro::TokenStream;
+use syn::{DeriveInput, parse_macro_input};
+
+mod attribute_key;
+mod modifier_key;
+mod system;
+
+/// Derives `AttributeIndex` and `AttributeTemplate`, whose `supplier()` builds the template
+/// declared below, for a field-less attribute key enum.
+///
+/// The container attribute `#[attribute_key(value = T, operation = O)]` sets the attribute value
+/// type (`f32` if omitted) and the modifier operation (`Operation` if omitted). Each variant may
+/// carry `#[attribute(..)]` with:
+///
+/// - `default = expr`: the default raw value,
+/// - `range(min, max)`: clamp the value, making the attribute `Attribute::Ranged`,
+/// - `derived(..)`: make the attribute `Attribute::Derived`, computed by base modifiers from
+///   either a list of variants and constant expressions to sum, e.g. `derived(Stamina, Size, 1)`,
+///   or a single formula applied left to right, e.g. `derived((Strength + Dexterity) / 2)`.
+///   Formulas support `+`, `-`, `*` and `/`, and every right-hand operand must be a variant or
+///   a constant. Multiplying or dividing needs an operation with `Mul` and `Div` variants, such
+///   as `ArithmeticOp`.
+///
+/// A variant term is keyed `M::from(variant)` the first time it appears in a formula. Constants
+/// and repeated variants are keyed `M::from((Self::Derived, position))` instead, so every
+/// modifier of a formula has its own key. The modifier key type passed to `supplier::<M>()`
+/// must therefore implement `From<Self>` and `From<(Self, usize)>`, see
+/// [`ModifierKey`](derive@ModifierKey).
+#[proc_macro_derive(AttributeKey, attributes(attribute_key, attribute))]
+pub fn derive_attribute_key(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	attribute_key::expand(&input)
+		.unwrap_or_else(syn::Error::into_compile_error)
+		.into()
+}
+
+/// Derives the `From` impls a modifier key enum needs to key the formulas of an
+/// [`AttributeKey`](derive@AttributeKey) enum.
+///
+/// - `#[modifier_key(attribute)]` on a variant `Variant(A)` implements `From<A>`,
+/// - `#[modifier_key(term)]` on a variant `Variant(A, usize)` implements `From<(A, usize)>`.
+#[proc_macro_derive(ModifierKey, attributes(modifier_key))]
+pub fn derive_modifier_key(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	modifier_key::expand(&input)
+		.unwrap_or_else(syn::Error::into_compile_error)
+		.into()
+}
+
+/// Derives `System` for a marker type from `#[system(..)]`:
+///
+/// - `attribute = T`, `modifier = T` and `value = T`: the attribute key, modifier key and
+///   attribute value types,
+/// - `operation = T`: the modifier operation, `Operation` if omitted,
+/// - `kind = T`: the actor kind, `()` if omitted,
+/// - `actor = T`: the actor type, `BasicActor<Self>` if omitted,
+/// - `kinds = path`: a `fn() -> KindRegistry<Self>` building the kind registry, shared through
+///   `shared_kinds!`. Without it the system has no kinds.
+#[proc_macro_derive(System, attributes(system))]
+pub fn derive_system(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	system::expand(&input)
+		.unwrap_or_else(syn::Error::into_compile_error)
+		.into()
+}
@@ -0,0 +1,84 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, spanned::Spanned};
+
+enum Role {
+	Attribute,
+	Term,
+}
+
+pub(crate) fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+	let Data::Enum(data) = &input.data else {
+		return Err(syn::Error::new(
+			input.span(),
+			"ModifierKey can only be derived for enums",
+		));
+	};
+
+	let name = &input.ident;
+	let mut impls = Vec::new();
+	for variant in &data.variants {
+		let Some(role) = variant_role(variant)? else {
+			continue;
+		};
+		let ident = &variant.ident;
+		let fields: Vec<_> = match &variant.fields {
+			Fields::Unnamed(fields) => fields.unnamed.iter().map(|f| &f.ty).collect(),
+			_ => Vec::new(),
+		};
+		impls.push(match (role, &fields[..]) {
+			(Role::Attribute, [attribute]) => quote! {
+				impl ::core::convert::From<#attribute> for #name {
+					fn from(value: #attribute) -> Self {
+						Self::#ident(value)
+					}
+				}
+			},
+			(Role::Term, [attribute, position]) => quote! {
+				impl ::core::convert::From<(#attribute, #position)> for #name {
+					fn from((attribute, position): (#attribute, #position)) -> Self {
+						Self::#ident(attribute, position)
+					}
+				}
+			},
+			(Role::Attribute, _) => {
+				return Err(syn::Error::new(
+					variant.span(),
+					"`attribute` variants must wrap the attribute key, e.g. `Attribute(Stat)`",
+				));
+			}
+			(Role::Term, _) => {
+				return Err(syn::Error::new(
+					variant.span(),
+					"`term` variants must wrap the attribute key and a position, e.g. `Term(Stat, usize)`",
+				));
+			}
+		});
+	}
+
+	Ok(quote! { #(#impls)* })
+}
+
+fn variant_role(variant: &syn::Variant) -> syn::Result<Option<Role>> {
+	let mut role = None;
+	for attr in variant
+		.attrs
+		.iter()
+		.filter(|a| a.path().is_ident("modifier_key"))
+	{
+		attr.parse_nested_meta(|meta| {
+			if role.is_some() {
+				return Err(meta.error("a variant has at most one modifier_key role"));
+			}
+			if meta.path.is_ident("attribute") {
+				role = Some(Role::Attribute);
+			} else if meta.path.is_ident("term") {
+				role = Some(Role::Term);
+			} else {
+				return Err(meta.error("unknown modifier_key option"));
+			}
+			Ok(())
+		})?;
+	}
+	Ok(role)
+}
@@ -0,0 +1,91 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{DeriveInput, Path, Type, spanned::Spanned};
+
+#[derive(Default)]
+struct SystemAttribute {
+	attribute: Option<Type>,
+	modifier: Option<Type>,
+	value: Option<Type>,
+	operation: Option<Type>,
+	kind: Option<Type>,
+	actor: Option<Type>,
+	kinds: Option<Path>,
+}
+
+pub(crate) fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+	let attr = system_attribute(input)?;
+	let required = |ty: Option<Type>, option: &str| {
+		ty.ok_or_else(|| {
+			syn::Error::new(
+				input.span(),
+				format!("System requires `#[system({option} = ..)]`"),
+			)
+		})
+	};
+
+	let name = &input.ident;
+	let attribute = required(attr.attribute, "attribute")?;
+	let modifier = required(attr.modifier, "modifier")?;
+	let value = required(attr.value, "value")?;
+	let operation = attr
+		.operation
+		.unwrap_or_else(|| syn::parse_quote!(::systema::attribute::modifier::Operation));
+	let kind = attr.kind.unwrap_or_else(|| syn::parse_quote!(()));
+	let actor = attr
+		.actor
+		.unwrap_or_else(|| syn::parse_quote!(::systema::actor::BasicActor<Self>));
+
+	let kinds = attr.kinds.map(|kinds| {
+		quote! {
+			fn kinds() -> ::core::option::Option<
+				::std::sync::Arc<::systema::kind::KindRegistry<Self>>,
+			> {
+				::systema::shared_kinds!(#name, #kinds())
+			}
+		}
+	});
+
+	Ok(quote! {
+		impl ::systema::system::System for #name {
+			type AttributeKey = #attribute;
+			type ModifierKey = #modifier;
+			type AttributeValue = #value;
+			type Operation = #operation;
+
+			type Kind = #kind;
+			type Actor = #actor;
+
+			#kinds
+		}
+	})
+}
+
+fn system_attribute(input: &DeriveInput) -> syn::Result<SystemAttribute> {
+	let mut out = SystemAttribute::default();
+	for attr in input.attrs.iter().filter(|a| a.path().is_ident("system")) {
+		attr.parse_nested_meta(|meta| {
+			let slot = if meta.path.is_ident("attribute") {
+				&mut out.attribute
+			} else if meta.path.is_ident("modifier") {
+				&mut out.modifier
+			} else if meta.path.is_ident("value") {
+				&mut out.value
+			} else if meta.path.is_ident("operation") {
+				&mut out.operation
+			} else if meta.path.is_ident("kind") {
+				&mut out.kind
+			} else if meta.path.is_ident("actor") {
+				&mut out.actor
+			} else if meta.path.is_ident("kinds") {
+				out.kinds = Some(meta.value()?.parse()?);
+				return Ok(());
+			} else {
+				return Err(meta.error("unknown system option"));
+			};
+			*slot = Some(meta.value()?.parse()?);
+			Ok(())
+		})?;
+	}
+	Ok(out)
+}
@@ -0,0 +1,151 @@
+use std::sync::Arc;
+
+use systema::{AttributeKey, ModifierKey, attribute::map::DenseAttributeMap, prelude::*};
+
+#[derive(AttributeKey, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[attribute_key(value = u8, operation = ArithmeticOp)]
+enum Stat {
+	#[attribute(derived(Stamina, Size))]
+	MaxHealth,
+	#[attribute(derived(Dexterity, Strength, 5))]
+	Speed,
+	#[attribute(default = 5)]
+	Size,
+	#[attribute(default = 1, range(1, 5))]
+	Stamina,
+	#[attribute(default = 1, range(1, 5))]
+	Strength,
+	#[attribute(default = 1, range(1, 5))]
+	Dexterity,
+	Experience,
+	#[attribute(derived(Strength, 2, 3))]
+	Carry,
+	#[attribute(derived((Strength + Dexterity) * 3 / 2 - Strength))]
+	Initiative,
+}
+
+#[derive(ModifierKey, Debug, Clone, PartialEq, Eq, Hash)]
+enum Modifier {
+	#[modifier_key(attribute)]
+	Stat(Stat),
+	#[modifier_key(term)]
+	Term(Stat, usize),
+	Buff,
+}
+
+#[test]
+fn index_matches_declaration_order() {
+	assert_eq!(Stat::COUNT, 9);
+	for (i, stat) in Stat::ALL.iter().enumerate() {
+		assert_eq!(stat.index(), i);
+	}
+}
+
+#[test]
+#[allow(clippy::arc_with_non_send_sync)]
+fn supplier_from_annotations() {
+	let supplier = Arc::new(Stat::supplier::<Modifier>());
+	let mut map: DenseAttributeMap<Stat, Modifier, u8, ArithmeticOp> =
+		DenseAttributeMap::new(supplier);
+
+	assert_eq!(map.value(&Stat::MaxHealth), Some(6));
+	assert_eq!(map.value(&Stat::Speed), Some(7));
+	assert_eq!(map.value(&Stat::Experience), Some(0));
+
+	map.set_raw_value(&Stat::Stamina, 9);
+	assert_eq!(map.value(&Stat::Stamina), Some(5));
+	assert_eq!(map.value(&Stat::MaxHealth), Some(10));
+
+	map.add_modifier(
+		&Stat::Strength,
+		Modifier::Buff,
+		AttributeModifier::new(2, ArithmeticOp::Add),
+	);
+	assert_eq!(map.base_value(&Stat::Speed), Some(9));
+}
+
+#[test]
+fn constants_have_distinct_keys() {
+	let supplier = Stat::supplier::<Modifier>();
+	let carry = supplier.create_instance(&Stat::Carry).unwrap();
+	let keys: Vec<_> = carry.modifiers().map(|(key, ..)| key.clone()).collect();
+	assert_eq!(
+		keys,
+		[
+			Modifier::Stat(Stat::Strength),
+			Modifier::Term(Stat::Carry, 1),
+			Modifier::Term(Stat::Carry, 2),
+		]
+	);
+}
+
+#[test]
+#[allow(clippy::arc_with_non_send_sync)]
+fn formulas_apply_left_to_right() {
+	let supplier = Arc::new(Stat::supplier::<Modifier>());
+	let mut map: DenseAttributeMap<Stat, Modifier, u8, ArithmeticOp> =
+		DenseAttributeMap::new(supplier);
+	map.set_raw_value(&Stat::Strength, 2);
+	map.set_raw_value(&Stat::Dexterity, 4);
+
+	assert_eq!(map.value(&Stat::Carry), Some(7));
+	// (2 + 4) * 3 / 2 - 2
+	assert_eq!(map.value(&Stat::Initiative), Some(7));
+
+	let supplier = Stat::supplier::<Modifier>();
+	let initiative = supplier.create_instance(&Stat::Initiative).unwrap();
+	let keys: Vec<_> = initiative
+		.modifiers()
+		.map(|(key, ..)| key.clone())
+		.collect();
+	assert_eq!(
+		keys,
+		[
+			Modifier::Stat(Stat::Strength),
+			Modifier::Stat(Stat::Dexterity),
+			Modifier::Term(Stat::Initiative, 2),
+			Modifier::Term(Stat::Initiative, 3),
+			Modifier::Term(Stat::Initiative, 4),
+		]
+	);
+}
+
+#[derive(AttributeKey, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[attribute_key(value = i32)]
+enum Signed {
+	#[attribute(default = 4)]
+	Base,
+	#[attribute(derived(Base, -1))]
+	Lowered,
+}
+
+impl Signed {
+	/// Inherent items of the same names take precedence over the derived template.
+	const ALL: [Self; 1] = [Self::Base];
+
+	fn supplier() -> AttributeSupplier<Self, SignedModifier, i32> {
+		<Self as AttributeTemplate>::supplier()
+	}
+}
+
+#[derive(ModifierKey, Debug, Clone, PartialEq, Eq, Hash)]
+enum SignedModifier {
+	#[modifier_key(attribute)]
+	Stat(Signed),
+	#[modifier_key(term)]
+	Term(Signed, usize),
+}
+
+#[test]
+#[allow(clippy::arc_with_non_send_sync)]
+fn template_does_not_collide_with_inherent_items() {
+	assert_eq!(Signed::ALL, [Signed::Base]);
+	assert_eq!(
+		<Signed as AttributeTemplate>::ALL,
+		[Signed::Base, Signed::Lowered]
+	);
+
+	let map: DenseAttributeMap<Signed, SignedModifier, i32> =
+		DenseAttributeMap::new(Arc::new(Signed::supplier()));
+	assert_eq!(map.value(&Signed::Lowered), Some(3));
+}
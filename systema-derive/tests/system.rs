@@ -0,0 +1,64 @@
+use systema::{AttributeKey, ModifierKey, System, prelude::*};
+
+#[derive(AttributeKey, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[attribute_key(value = i32, operation = ArithmeticOp)]
+enum Stat {
+	#[attribute(default = 3)]
+	Strength,
+	#[attribute(derived(Strength * 2))]
+	Carry,
+}
+
+#[derive(ModifierKey, Debug, Clone, PartialEq, Eq, Hash)]
+enum Modifier {
+	#[modifier_key(attribute)]
+	Stat(Stat),
+	#[modifier_key(term)]
+	Term(Stat, usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Kind {
+	Human,
+	Giant,
+}
+
+fn kinds() -> KindRegistry<Game> {
+	KindRegistry::builder()
+		.kind(Kind::Human, Stat::builder())
+		.kind(
+			Kind::Giant,
+			Stat::builder().add(Stat::Strength, Attribute::Value(8)),
+		)
+		.build()
+}
+
+#[derive(System)]
+#[system(
+	attribute = Stat,
+	modifier = Modifier,
+	value = i32,
+	operation = ArithmeticOp,
+	kind = Kind,
+	kinds = kinds
+)]
+struct Game;
+
+#[derive(System)]
+#[system(attribute = Stat, modifier = Modifier, value = i32, operation = ArithmeticOp)]
+struct Kindless;
+
+#[test]
+fn system_from_annotations() {
+	let human = <Game as System>::Actor::new(Kind::Human);
+	assert_eq!(human.value(&Stat::Carry), Some(6));
+
+	let giant = <Game as System>::Actor::new(Kind::Giant);
+	assert_eq!(giant.value(&Stat::Carry), Some(16));
+}
+
+#[test]
+fn system_without_kinds() {
+	assert!(Kindless::kinds().is_none());
+	assert_eq!(BasicActor::<Kindless>::new(()).value(&Stat::Strength), None);
+}
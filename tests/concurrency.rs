@@ -0,0 +1,135 @@
+// Stress tests for concurrent `value()` calls; these need the default `Sync` caches.
+#![cfg(not(feature = "single-threaded"))]
+
+use std::{
+	sync::{
+		Arc, Barrier,
+		atomic::{AtomicBool, Ordering},
+		mpsc,
+	},
+	thread,
+	time::Duration,
+};
+
+use systema::{
+	attribute::{map::AttributeMap, shared::SharedAttributeMap},
+	prelude::*,
+};
+
+const CHAIN: u16 = 64;
+const READERS: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Link(u16);
+
+type Map = AttributeMap<Link, Link, i64>;
+
+/// `Link(n)` is derived from `Link(n - 1)` plus one, so evaluating the tail walks the whole chain.
+fn chain_supplier() -> Arc<AttributeSupplier<Link, Link, i64>> {
+	let builder = (1..CHAIN).fold(
+		AttributeSupplier::builder().add(Link(0), Attribute::Value(0)),
+		|builder, n| {
+			builder.add(
+				Link(n),
+				AttributeInstance::builder(Attribute::Derived)
+					.modifier(
+						Link(n - 1),
+						AttributeModifier::new(Value::Attribute(Link(n - 1)), Operation::Add),
+					)
+					.modifier(Link(n), AttributeModifier::new(1, Operation::Add)),
+			)
+		},
+	);
+	Arc::new(builder.build())
+}
+
+/// Materialises every link so reads go through the instance caches.
+fn materialised_map() -> Map {
+	let mut map = Map::new(chain_supplier());
+	for n in 0..CHAIN {
+		map.add_modifier(
+			&Link(n),
+			Link(u16::MAX),
+			AttributeModifier::new(0, Operation::Add),
+		);
+	}
+	map
+}
+
+/// Runs `f` on another thread and fails instead of hanging if it dead-locks.
+fn with_timeout(f: impl FnOnce() + Send + 'static) {
+	let (tx, rx) = mpsc::channel();
+	let handle = thread::spawn(move || {
+		f();
+		let _ = tx.send(());
+	});
+
+	match rx.recv_timeout(Duration::from_secs(30)) {
+		Ok(()) => handle.join().unwrap(),
+		Err(mpsc::RecvTimeoutError::Disconnected) => {
+			if let Err(panic) = handle.join() {
+				std::panic::resume_unwind(panic);
+			}
+		}
+		Err(mpsc::RecvTimeoutError::Timeout) => panic!("concurrent reads dead-locked"),
+	}
+}
+
+#[test]
+fn concurrent_cold_cache_fills() {
+	with_timeout(|| {
+		for _ in 0..32 {
+			let map = materialised_map();
+			let barrier = Barrier::new(READERS);
+
+			thread::scope(|s| {
+				for reader in 0..READERS {
+					let (map, barrier) = (&map, &barrier);
+					s.spawn(move || {
+						barrier.wait();
+						// Start at different points so fills overlap mid-chain.
+						for n in (0..CHAIN).rev().skip(reader) {
+							assert_eq!(map.value(&Link(n)), Some(i64::from(n)));
+						}
+					});
+				}
+			});
+		}
+	});
+}
+
+#[test]
+fn concurrent_reads_during_writes() {
+	with_timeout(|| {
+		let shared = SharedAttributeMap::new(materialised_map());
+		let done = Arc::new(AtomicBool::new(false));
+
+		let readers: Vec<_> = (0..READERS)
+			.map(|_| {
+				let (shared, done) = (shared.clone(), done.clone());
+				thread::spawn(move || {
+					while !done.load(Ordering::Relaxed) {
+						let map = shared.read();
+						let root = map.value(&Link(0)).unwrap();
+						let tail = map.value(&Link(CHAIN - 1)).unwrap();
+						assert_eq!(tail - root, i64::from(CHAIN - 1));
+					}
+				})
+			})
+			.collect();
+
+		for raw in 0..2_000 {
+			shared.write().set_raw_value(&Link(0), raw);
+		}
+		done.store(true, Ordering::Relaxed);
+
+		for reader in readers {
+			reader.join().unwrap();
+		}
+
+		assert_eq!(
+			shared.value(&Link(CHAIN - 1)),
+			Some(1_999 + i64::from(CHAIN - 1))
+		);
+	});
+}
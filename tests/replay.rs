@@ -0,0 +1,176 @@
+// Replays the recorded event logs in `tests/replays` and compares the resulting snapshots.
+// Run with `UPDATE_REPLAYS=1` to re-record them after an intended behaviour change.
+#![cfg(feature = "serde")]
+#![cfg_attr(feature = "single-threaded", allow(clippy::arc_with_non_send_sync))]
+
+use std::{collections::BTreeMap, fs, path::Path, sync::Arc, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use systema::{
+	advancement::CostCurve,
+	attribute::{event::EventLog, map::AttributeMap},
+	prelude::*,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+enum Stat {
+	Experience,
+	Strength,
+	Dexterity,
+	Health,
+	Defence,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+enum Source {
+	Base,
+	Shield,
+	Poison,
+	Bless,
+}
+
+type Map = AttributeMap<Stat, Source, i32>;
+type Log = EventLog<Stat, Source, i32, Operation>;
+type Session = (&'static str, fn(&mut Map));
+
+const PHYSICAL: Tag = Tag::new("physical");
+
+/// A recorded session and the values it ended with.
+#[derive(Serialize, Deserialize)]
+struct Recording {
+	events: Log,
+	snapshot: BTreeMap<Stat, i32>,
+}
+
+fn supplier() -> Arc<AttributeSupplier<Stat, Source, i32>> {
+	Arc::new(
+		AttributeSupplier::builder()
+			.add(Stat::Experience, Attribute::Value(40))
+			.add(Stat::Strength, Attribute::Ranged(2, 1, 5))
+			.add(Stat::Dexterity, Attribute::Ranged(2, 1, 5))
+			.add(
+				Stat::Health,
+				AttributeInstance::builder(Attribute::Derived)
+					.modifier(
+						Source::Base,
+						AttributeModifier::new(Value::Attribute(Stat::Strength), Operation::Add),
+					)
+					.modifier(Source::Base, AttributeModifier::new(10, Operation::Add)),
+			)
+			.add(
+				Stat::Defence,
+				AttributeInstance::builder(Attribute::Derived).modifier(
+					Source::Base,
+					AttributeModifier::new(Value::Attribute(Stat::Dexterity), Operation::Add),
+				),
+			)
+			.tag(Stat::Strength, PHYSICAL)
+			.tag(Stat::Dexterity, PHYSICAL)
+			.cost(Stat::Strength, CostCurve::Linear(3))
+			.cost(Stat::Dexterity, CostCurve::Linear(3))
+			.currency(Stat::Experience)
+			.build(),
+	)
+}
+
+fn snapshot(map: &Map) -> BTreeMap<Stat, i32> {
+	map.snapshot().into_iter().collect()
+}
+
+/// Scripted sessions, recorded into `tests/replays/<name>.json`.
+fn sessions() -> Vec<Session> {
+	vec![
+		("combat", |map| {
+			map.add_modifier(
+				&Stat::Defence,
+				Source::Shield,
+				AttributeModifier::new(3, Operation::Add),
+			);
+			map.add_tag_modifier(
+				&PHYSICAL,
+				Source::Poison,
+				AttributeModifier::new(1, Operation::Sub),
+			);
+			map.expire_modifiers(Source::Poison, Duration::from_secs(10));
+			map.tick(Duration::from_secs(4));
+			map.remove_modifier(&Stat::Defence, &Source::Shield);
+			map.tick(Duration::from_secs(6));
+			map.add_tag_modifier(
+				&PHYSICAL,
+				Source::Bless,
+				AttributeModifier::new(2, Operation::Add),
+			);
+		}),
+		("advancement", |map| {
+			map.enable_journal();
+			map.advance(&Stat::Strength, 4).unwrap();
+			map.set_raw_value(&Stat::Dexterity, 3);
+			map.add_modifier(
+				&Stat::Health,
+				Source::Bless,
+				AttributeModifier::new(5, Operation::Add),
+			);
+			map.undo();
+			map.undo();
+			map.redo();
+			map.advance(&Stat::Dexterity, 4).unwrap();
+			map.reset(&Stat::Health);
+		}),
+	]
+}
+
+fn record(session: fn(&mut Map)) -> Recording {
+	let mut map = Map::new(supplier());
+	assert!(map.record_events());
+	session(&mut map);
+	Recording {
+		snapshot: snapshot(&map),
+		events: map.take_events().unwrap(),
+	}
+}
+
+#[test]
+fn recorded_replays_match() {
+	let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/replays");
+	let update = std::env::var_os("UPDATE_REPLAYS").is_some();
+
+	for (name, session) in sessions() {
+		let path = dir.join(format!("{name}.json"));
+		if update {
+			let json = serde_json::to_string_pretty(&record(session)).unwrap();
+			fs::write(&path, json + "\n").unwrap();
+		}
+
+		let json = fs::read_to_string(&path).unwrap();
+		let recording: Recording = serde_json::from_str(&json).unwrap();
+		let replayed = Map::replay(supplier(), &recording.events);
+		assert_eq!(
+			snapshot(&replayed),
+			recording.snapshot,
+			"replay {name} diverged"
+		);
+	}
+}
+
+#[test]
+fn live_and_replayed_values_agree() {
+	for (name, session) in sessions() {
+		let mut live = Map::new(supplier());
+		assert!(live.record_events());
+		session(&mut live);
+
+		let json = serde_json::to_string(live.events().unwrap()).unwrap();
+		let events: Log = serde_json::from_str(&json).unwrap();
+		let replayed = Map::replay(supplier(), &events);
+
+		for stat in live.keys() {
+			assert_eq!(replayed.value(stat), live.value(stat), "{name}: {stat:?}");
+			assert_eq!(
+				replayed.raw_value(stat),
+				live.raw_value(stat),
+				"{name}: {stat:?}"
+			);
+		}
+		assert_eq!(replayed.history(), live.history(), "{name}");
+	}
+}
@@ -1,63 +1,69 @@
 use std::sync::Arc;
 
-use once_cell::sync::Lazy;
 use systema::prelude::*;
 
-static ATTRIBUTES: Lazy<Arc<AttributeSupplier<AttributeKey, ModifierKey, u8>>> = Lazy::new(|| {
-	Arc::new(
-		AttributeSupplier::builder()
-			.add(
-				AttributeKey::MaxHealth,
-				AttributeInstance::builder(Attribute::Derived)
-					.modifier(
-						ModifierKey::Attribute(AttributeKey::Stamina),
-						AttributeModifier::new(
-							Value::Attribute(AttributeKey::Stamina),
-							Operation::Add,
+fn registry() -> KindRegistry<MockSystem> {
+	KindRegistry::builder()
+		.kind(
+			ActorKind::Wizard,
+			AttributeSupplier::builder()
+				.add(
+					AttributeKey::MaxHealth,
+					AttributeInstance::builder(Attribute::Derived)
+						.modifier(
+							ModifierKey::Attribute(AttributeKey::Stamina),
+							AttributeModifier::new(
+								Value::Attribute(AttributeKey::Stamina),
+								Operation::Add,
+							)
+							.base(),
 						)
-						.base(),
-					)
-					.modifier(
-						ModifierKey::Attribute(AttributeKey::Size),
-						AttributeModifier::new(
-							Value::Attribute(AttributeKey::Size),
-							Operation::Add,
+						.modifier(
+							ModifierKey::Attribute(AttributeKey::Size),
+							AttributeModifier::new(
+								Value::Attribute(AttributeKey::Size),
+								Operation::Add,
+							)
+							.base(),
+						),
+				)
+				.add(
+					AttributeKey::Speed,
+					AttributeInstance::builder(Attribute::Derived)
+						.modifier(
+							ModifierKey::Attribute(AttributeKey::Dexterity),
+							AttributeModifier::new(
+								Value::Attribute(AttributeKey::Dexterity),
+								Operation::Add,
+							)
+							.base(),
 						)
-						.base(),
-					),
-			)
-			.add(
-				AttributeKey::Speed,
-				AttributeInstance::builder(Attribute::Derived)
-					.modifier(
-						ModifierKey::Attribute(AttributeKey::Dexterity),
-						AttributeModifier::new(
-							Value::Attribute(AttributeKey::Dexterity),
-							Operation::Add,
+						.modifier(
+							ModifierKey::Attribute(AttributeKey::Strength),
+							AttributeModifier::new(
+								Value::Attribute(AttributeKey::Strength),
+								Operation::Add,
+							)
+							.base(),
 						)
-						.base(),
-					)
-					.modifier(
-						ModifierKey::Attribute(AttributeKey::Strength),
-						AttributeModifier::new(
-							Value::Attribute(AttributeKey::Strength),
-							Operation::Add,
-						)
-						.base(),
-					)
-					.modifier(
-						ModifierKey::Attribute(AttributeKey::Speed),
-						AttributeModifier::new(Value::Value(5), Operation::Add).base(),
-					),
-			)
-			.add(AttributeKey::Size, Attribute::Value(5))
-			.add(AttributeKey::Stamina, Attribute::Value(1))
-			.add(AttributeKey::Strength, Attribute::Value(1))
-			.add(AttributeKey::Dexterity, Attribute::Value(1))
-			.add(AttributeKey::Renown(Renown::Purity), Attribute::Value(0))
-			.build(),
-	)
-});
+						.modifier(
+							ModifierKey::Attribute(AttributeKey::Speed),
+							AttributeModifier::new(Value::Value(5), Operation::Add).base(),
+						),
+				)
+				.add(AttributeKey::Size, Attribute::Value(5))
+				.add(AttributeKey::Stamina, Attribute::Value(1))
+				.add(AttributeKey::Strength, Attribute::Value(1))
+				.add(AttributeKey::Dexterity, Attribute::Value(1)),
+		)
+		.extends(
+			ActorKind::Werewolf,
+			ActorKind::Wizard,
+			AttributeSupplier::builder()
+				.add(AttributeKey::Renown(Renown::Purity), Attribute::Value(0)),
+		)
+		.build()
+}
 
 struct MockActor {
 	pub attributes: AttributeMap<MockSystem>,
@@ -65,97 +71,40 @@ struct MockActor {
 }
 impl MockActor {
 	pub fn set_form(&mut self, form: Form) {
-		if let Some(form_mut) = self.form.as_mut() {
-			let old_form = std::mem::replace(form_mut, form);
-			self.attributes
-				.remove_modifiers(&ModifierKey::Form(old_form));
+		let Some(current) = self.form.as_mut() else {
+			return;
+		};
+		let old_form = std::mem::replace(current, form.clone());
+		self.remove_modifiers(&ModifierKey::Form(old_form));
 
-			match form_mut {
-				Form::Hishu => {}
-				Form::Dalu => {
-					self.attributes
-						.add_modifier(
-							&AttributeKey::Strength,
-							ModifierKey::Form(Form::Dalu),
-							AttributeModifier::new(Value::Value(1), Operation::Add),
-						)
-						.add_modifier(
-							&AttributeKey::Stamina,
-							ModifierKey::Form(Form::Dalu),
-							AttributeModifier::new(Value::Value(1), Operation::Add),
-						)
-						.add_modifier(
-							&AttributeKey::Size,
-							ModifierKey::Form(Form::Dalu),
-							AttributeModifier::new(Value::Value(1), Operation::Add),
-						);
-				}
-				Form::Gauru => {
-					self.attributes
-						.add_modifier(
-							&AttributeKey::Strength,
-							ModifierKey::Form(Form::Gauru),
-							AttributeModifier::new(Value::Value(3), Operation::Add),
-						)
-						.add_modifier(
-							&AttributeKey::Dexterity,
-							ModifierKey::Form(Form::Gauru),
-							AttributeModifier::new(Value::Value(1), Operation::Add),
-						)
-						.add_modifier(
-							&AttributeKey::Stamina,
-							ModifierKey::Form(Form::Gauru),
-							AttributeModifier::new(Value::Value(2), Operation::Add),
-						)
-						.add_modifier(
-							&AttributeKey::Size,
-							ModifierKey::Form(Form::Gauru),
-							AttributeModifier::new(Value::Value(2), Operation::Add),
-						);
-				}
-				Form::Urhan => {
-					self.attributes
-						.add_modifier(
-							&AttributeKey::Dexterity,
-							ModifierKey::Form(Form::Urhan),
-							AttributeModifier::new(Value::Value(2), Operation::Add),
-						)
-						.add_modifier(
-							&AttributeKey::Stamina,
-							ModifierKey::Form(Form::Urhan),
-							AttributeModifier::new(Value::Value(1), Operation::Add),
-						)
-						.add_modifier(
-							&AttributeKey::Size,
-							ModifierKey::Form(Form::Urhan),
-							AttributeModifier::new(Value::Value(1), Operation::Sub),
-						);
-				}
-				Form::Urshul => {
-					self.attributes
-						.add_modifier(
-							&AttributeKey::Strength,
-							ModifierKey::Form(Form::Urshul),
-							AttributeModifier::new(Value::Value(2), Operation::Add),
-						)
-						.add_modifier(
-							&AttributeKey::Dexterity,
-							ModifierKey::Form(Form::Urshul),
-							AttributeModifier::new(Value::Value(2), Operation::Add),
-						)
-						.add_modifier(
-							&AttributeKey::Stamina,
-							ModifierKey::Form(Form::Urshul),
-							AttributeModifier::new(Value::Value(2), Operation::Add),
-						)
-						.add_modifier(
-							&AttributeKey::Size,
-							ModifierKey::Form(Form::Urshul),
-							AttributeModifier::new(Value::Value(1), Operation::Add),
-						);
-				}
-			}
-		}
+		let add = |value| AttributeModifier::new(Value::Value(value), Operation::Add);
+		let sub = |value| AttributeModifier::new(Value::Value(value), Operation::Sub);
+		let modifiers = match form {
+			Form::Hishu => vec![],
+			Form::Dalu => vec![
+				(AttributeKey::Strength, add(1)),
+				(AttributeKey::Stamina, add(1)),
+				(AttributeKey::Size, add(1)),
+			],
+			Form::Gauru => vec![
+				(AttributeKey::Strength, add(3)),
+				(AttributeKey::Dexterity, add(1)),
+				(AttributeKey::Stamina, add(2)),
+				(AttributeKey::Size, add(2)),
+			],
+			Form::Urhan => vec![
+				(AttributeKey::Dexterity, add(2)),
+				(AttributeKey::Stamina, add(1)),
+				(AttributeKey::Size, sub(1)),
+			],
+			Form::Urshul => vec![
+				(AttributeKey::Strength, add(2)),
+				(AttributeKey::Dexterity, add(2)),
+				(AttributeKey::Stamina, add(2)),
+				(AttributeKey::Size, add(1)),
+			],
+		};
+		self.apply_modifiers(&ModifierKey::Form(form), modifiers);
 	}
 }
 impl Actor for MockActor {
@@ -164,7 +113,7 @@ impl Actor for MockActor {
 
 	fn new(kind: ActorKind) -> Self {
 		let mut _self = Self {
-			attributes: AttributeMap::<MockSystem>::new(ATTRIBUTES.clone()),
+			attributes: MockSystem::attributes(&kind),
 			form: None,
 		};
 
@@ -208,10 +157,15 @@ impl System for MockSystem {
 	type AttributeValue = u8;
 	type Operation = Operation;
 
+	type Kind = ActorKind;
 	type Actor = MockActor;
+
+	fn kinds() -> Option<Arc<KindRegistry<Self>>> {
+		systema::shared_kinds!(MockSystem, registry())
+	}
 }
 
-#[derive(PartialEq, Eq, Hash)]
+#[derive(PartialEq, Eq, Hash, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ActorKind {
 	Wizard,